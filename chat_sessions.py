@@ -23,7 +23,7 @@ from datetime import datetime, timezone
 from pathlib import Path
 from typing import Dict, List, Optional, Any, Tuple
 from dataclasses import dataclass, asdict
-from pydantic import BaseModel, Field
+from pydantic import BaseModel, ConfigDict, Field
 import re
 from token_counter import create_context_builder, estimate_tokens, ContextBuilder
 
@@ -49,6 +49,10 @@ class ChatMessage(BaseModel):
 
 class ChatSessionMetadata(BaseModel):
     """Metadata for chat sessions."""
+    # Keep keys written by the Tauri side (generation_options, system_prompt,
+    # forked_from, imported_from) when a session is re-saved here
+    model_config = ConfigDict(extra="allow")
+
     model: Optional[str] = "gemma3n:latest"
     token_count: Optional[int] = 0
     message_count: Optional[int] = 0
@@ -0,0 +1,88 @@
+//! Registry of cancellable background tasks.
+//!
+//! Long-running work started by a command (voice chat sessions, streams) registers
+//! under a key and checks its token; a later command cancels it by the same key.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::Notify;
+use log::info;
+
+static REGISTRY: Mutex<Option<HashMap<String, Arc<CancellationToken>>>> = Mutex::new(None);
+
+#[derive(Debug, Default)]
+pub struct CancellationToken {
+    cancelled: AtomicBool,
+    notify: Notify,
+}
+
+impl CancellationToken {
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Resolves once the token is cancelled.
+    pub async fn cancelled(&self) {
+        while !self.is_cancelled() {
+            let notified = self.notify.notified();
+            // Re-check after registering so a cancel in between isn't missed
+            if self.is_cancelled() {
+                break;
+            }
+            notified.await;
+        }
+    }
+
+    fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+        self.notify.notify_waiters();
+    }
+}
+
+/// Register a task under `key`, cancelling any task already registered there.
+pub fn register(key: &str) -> Arc<CancellationToken> {
+    let token = Arc::new(CancellationToken::default());
+    let previous = REGISTRY
+        .lock()
+        .unwrap()
+        .get_or_insert_with(HashMap::new)
+        .insert(key.to_string(), token.clone());
+
+    if let Some(previous) = previous {
+        info!("⏹️ Replacing running task {}", key);
+        previous.cancel();
+    }
+    token
+}
+
+/// Cancel the task registered under `key`. Returns whether one was running.
+pub fn cancel(key: &str) -> bool {
+    let token = REGISTRY.lock().unwrap().as_mut().and_then(|tasks| tasks.remove(key));
+    match token {
+        Some(token) => {
+            token.cancel();
+            true
+        }
+        None => false,
+    }
+}
+
+/// Drop the registration for a task that finished, unless it was already replaced.
+pub fn unregister(key: &str, token: &Arc<CancellationToken>) {
+    if let Some(tasks) = REGISTRY.lock().unwrap().as_mut() {
+        if tasks.get(key).map_or(false, |current| Arc::ptr_eq(current, token)) {
+            tasks.remove(key);
+        }
+    }
+}
+
+/// Keys currently registered that start with `prefix`.
+pub fn active_keys(prefix: &str) -> Vec<String> {
+    REGISTRY
+        .lock()
+        .unwrap()
+        .as_ref()
+        .map(|tasks| tasks.keys().filter(|key| key.starts_with(prefix)).cloned().collect())
+        .unwrap_or_default()
+}
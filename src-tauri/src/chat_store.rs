@@ -0,0 +1,877 @@
+//! File-backed chat session store.
+//!
+//! Sessions live as one JSON file per chat in `<project root>/chats`, the same
+//! layout the Python backend's `ChatSessionManager` uses. The backend re-reads
+//! these files on every request, so changes made here are visible through its
+//! endpoints too, and chat history keeps working when the backend is down.
+
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use log::{info, error, warn};
+use chrono::{SecondsFormat, Utc};
+
+// How often a streaming reply is written to disk
+const PARTIAL_SAVE_INTERVAL: Duration = Duration::from_secs(1);
+// Temp files younger than this may belong to a save still in progress
+const STALE_TEMP_FILE_AGE: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredMessage {
+    pub id: String,
+    pub content: String,
+    pub role: String,
+    pub timestamp: String,
+    pub token_count: Option<i64>,
+    #[serde(default)]
+    pub metadata: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredSession {
+    pub id: String,
+    pub title: String,
+    #[serde(default)]
+    pub messages: Vec<StoredMessage>,
+    pub created_at: String,
+    pub updated_at: String,
+    #[serde(default)]
+    pub metadata: Option<serde_json::Value>,
+}
+
+/// Directory holding the session files.
+pub fn chats_dir() -> PathBuf {
+    let current_dir = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+
+    // If we're in src-tauri, the backend runs from the project root one level up
+    let project_root = if current_dir.file_name().and_then(|n| n.to_str()) == Some("src-tauri") {
+        current_dir.parent().unwrap_or(&current_dir).to_path_buf()
+    } else {
+        current_dir
+    };
+
+    project_root.join("chats")
+}
+
+fn session_file(chat_id: &str) -> Result<PathBuf, String> {
+    // Chat ids become file names, so refuse anything that could escape the directory
+    if chat_id.is_empty() || !chat_id.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+        return Err(format!("Invalid chat id: {}", chat_id));
+    }
+    Ok(chats_dir().join(format!("{}.json", chat_id)))
+}
+
+pub fn load_session(chat_id: &str) -> Result<StoredSession, String> {
+    let path = session_file(chat_id)?;
+    let contents = std::fs::read_to_string(&path)
+        .map_err(|_| format!("Chat session {} not found", chat_id))?;
+
+    serde_json::from_str(&contents).map_err(|e| {
+        error!("❌ Failed to parse chat session {}: {}", chat_id, e);
+        format!("Chat session {} is corrupt: {}", chat_id, e)
+    })
+}
+
+pub fn save_session(session: &mut StoredSession) -> Result<(), String> {
+    let path = session_file(&session.id)?;
+    std::fs::create_dir_all(chats_dir())
+        .map_err(|e| format!("Failed to create chats directory: {}", e))?;
+
+    touch_metadata(session);
+
+    let contents = serde_json::to_string_pretty(session)
+        .map_err(|e| format!("Failed to serialize chat session: {}", e))?;
+    write_atomically(&path, &contents)
+        .map_err(|e| format!("Failed to write chat session {}: {}", session.id, e))?;
+
+    info!("💾 Saved chat session {} ({} messages)", session.id, session.messages.len());
+    Ok(())
+}
+
+/// Write to a temp file beside `path` and rename it into place, so a crash
+/// mid-write leaves either the old session or the new one, never half of each.
+fn write_atomically(path: &Path, contents: &str) -> std::io::Result<()> {
+    let file_name = path.file_name().and_then(|n| n.to_str()).unwrap_or("session.json");
+    let temp_path = path.with_file_name(format!("{}.{}.tmp", file_name, uuid::Uuid::new_v4()));
+
+    let result = std::fs::File::create(&temp_path)
+        .and_then(|mut file| {
+            file.write_all(contents.as_bytes())?;
+            file.sync_all()
+        })
+        .and_then(|_| std::fs::rename(&temp_path, path));
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temp_path);
+    }
+    result
+}
+
+/// What happened to a session file `verify` couldn't read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChatRepair {
+    /// Rebuilt from the messages that still parsed.
+    Salvaged,
+    /// Replaced by the temp file of a save that was interrupted before its rename.
+    RestoredFromTemp,
+    /// Nothing was readable; the file was only moved aside.
+    Quarantined,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DamagedChat {
+    pub chat_id: String,
+    pub repair: ChatRepair,
+    /// Where the unreadable original was moved.
+    pub backup: Option<String>,
+    pub messages_recovered: usize,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ChatStoreCheck {
+    pub checked: usize,
+    pub healthy: usize,
+    pub damaged: Vec<DamagedChat>,
+    /// Leftovers from interrupted saves that weren't needed for a repair.
+    pub temp_files_removed: usize,
+}
+
+/// Check every session file, repairing what can be repaired and moving
+/// unreadable files to `<id>.json.<timestamp>.bak`.
+pub fn verify() -> Result<ChatStoreCheck, String> {
+    let entries = match std::fs::read_dir(chats_dir()) {
+        Ok(entries) => entries,
+        Err(_) => return Ok(ChatStoreCheck::default()),
+    };
+
+    let mut sessions = Vec::new();
+    let mut temp_files = Vec::new();
+    for path in entries.flatten().map(|entry| entry.path()) {
+        let Some(name) = path.file_name().and_then(|n| n.to_str()).map(str::to_string) else { continue };
+        if let Some(chat_id) = name.strip_suffix(".json") {
+            sessions.push((chat_id.to_string(), path));
+        } else if let Some((chat_id, _)) = name.strip_suffix(".tmp").and_then(|rest| rest.split_once(".json.")) {
+            // A young temp file may belong to a save that is still in progress
+            let age = path.metadata().and_then(|m| m.modified()).ok().and_then(|t| t.elapsed().ok());
+            if age.map_or(false, |age| age >= STALE_TEMP_FILE_AGE) {
+                temp_files.push((chat_id.to_string(), path));
+            }
+        }
+    }
+
+    let mut check = ChatStoreCheck { checked: sessions.len(), ..ChatStoreCheck::default() };
+    for (chat_id, path) in &sessions {
+        let error = match read_session_file(path) {
+            Ok(_) => {
+                check.healthy += 1;
+                continue;
+            }
+            Err(e) => e,
+        };
+        warn!("⚠️ Chat session {} is unreadable: {}", chat_id, error);
+
+        let backup = path.with_file_name(format!("{}.json.{}.bak", chat_id, Utc::now().format("%Y%m%d%H%M%S")));
+        std::fs::rename(path, &backup).map_err(|e| format!("Failed to quarantine chat {}: {}", chat_id, e))?;
+        let repaired = match newest_readable_temp(chat_id, &temp_files) {
+            Some(session) => Some((ChatRepair::RestoredFromTemp, session)),
+            None => salvage_session(chat_id, &backup).map(|session| (ChatRepair::Salvaged, session)),
+        };
+        check.damaged.push(restore(chat_id, repaired, Some(&backup), error)?);
+    }
+
+    // A crash during a chat's very first save leaves only the temp file
+    let mut orphaned: Vec<&String> = temp_files
+        .iter()
+        .map(|(chat_id, _)| chat_id)
+        .filter(|chat_id| !sessions.iter().any(|(id, _)| id == *chat_id))
+        .collect();
+    orphaned.sort();
+    orphaned.dedup();
+    for chat_id in orphaned {
+        if let Some(session) = newest_readable_temp(chat_id, &temp_files) {
+            let repaired = Some((ChatRepair::RestoredFromTemp, session));
+            check.damaged.push(restore(chat_id, repaired, None, "Session file was missing".to_string())?);
+        }
+    }
+
+    for (_, path) in &temp_files {
+        if std::fs::remove_file(path).is_ok() {
+            check.temp_files_removed += 1;
+        }
+    }
+
+    info!(
+        "🩺 Checked {} chat sessions: {} healthy, {} damaged, {} temp files removed",
+        check.checked, check.healthy, check.damaged.len(), check.temp_files_removed
+    );
+    Ok(check)
+}
+
+fn read_session_file(path: &Path) -> Result<StoredSession, String> {
+    let contents = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    serde_json::from_str(&contents).map_err(|e| e.to_string())
+}
+
+fn newest_readable_temp(chat_id: &str, temp_files: &[(String, PathBuf)]) -> Option<StoredSession> {
+    let mut candidates: Vec<&PathBuf> = temp_files.iter().filter(|(id, _)| id == chat_id).map(|(_, path)| path).collect();
+    candidates.sort_by_key(|path| std::cmp::Reverse(path.metadata().and_then(|m| m.modified()).ok()));
+    candidates
+        .into_iter()
+        .filter_map(|path| read_session_file(path).ok())
+        .find(|session| session.id == chat_id)
+}
+
+/// Rebuild a session from whatever parts of a damaged file still parse.
+fn salvage_session(chat_id: &str, path: &Path) -> Option<StoredSession> {
+    let contents = std::fs::read_to_string(path).ok()?;
+    let value: serde_json::Value = serde_json::from_str(&contents).ok()?;
+    let object = value.as_object()?;
+    let text = |key: &str| object.get(key).and_then(|v| v.as_str()).map(str::to_string);
+
+    let messages: Vec<StoredMessage> = object
+        .get("messages")
+        .and_then(|m| m.as_array())
+        .map(|messages| messages.iter().filter_map(|m| serde_json::from_value(m.clone()).ok()).collect())
+        .unwrap_or_default();
+    let now = now_timestamp();
+    Some(StoredSession {
+        id: chat_id.to_string(),
+        title: text("title").unwrap_or_else(|| "Recovered chat".to_string()),
+        messages,
+        created_at: text("created_at").unwrap_or_else(|| now.clone()),
+        updated_at: now,
+        metadata: object.get("metadata").cloned().filter(|m| m.is_object()),
+    })
+}
+
+fn restore(
+    chat_id: &str,
+    repaired: Option<(ChatRepair, StoredSession)>,
+    backup: Option<&Path>,
+    error: String,
+) -> Result<DamagedChat, String> {
+    let (repair, messages_recovered) = match repaired {
+        Some((repair, mut session)) => {
+            let recovered = session.messages.len();
+            save_session(&mut session)?;
+            (repair, recovered)
+        }
+        None => (ChatRepair::Quarantined, 0),
+    };
+    info!("🩹 Chat session {}: {:?}, {} messages recovered", chat_id, repair, messages_recovered);
+
+    Ok(DamagedChat {
+        chat_id: chat_id.to_string(),
+        repair,
+        backup: backup.map(|path| path.to_string_lossy().to_string()),
+        messages_recovered,
+        error,
+    })
+}
+fn now_timestamp() -> String {
+    Utc::now().to_rfc3339_opts(SecondsFormat::Micros, false)
+}
+
+pub fn new_session(title: String) -> StoredSession {
+    let now = now_timestamp();
+    StoredSession {
+        id: format!("chat_{}", &uuid::Uuid::new_v4().simple().to_string()[..12]),
+        title,
+        messages: Vec::new(),
+        created_at: now.clone(),
+        updated_at: now,
+        metadata: None,
+    }
+}
+
+// Mirror ChatSession.update_metadata() in the Python backend
+fn touch_metadata(session: &mut StoredSession) {
+    let now = now_timestamp();
+    session.updated_at = now.clone();
+
+    let token_count: i64 = session.messages.iter().filter_map(|m| m.token_count).sum();
+    let metadata = session.metadata.get_or_insert_with(|| serde_json::json!({}));
+    if let Some(map) = metadata.as_object_mut() {
+        map.insert("message_count".to_string(), serde_json::json!(session.messages.len()));
+        map.insert("token_count".to_string(), serde_json::json!(token_count));
+        map.insert("last_activity".to_string(), serde_json::json!(now));
+    }
+}
+
+/// Copy a session's messages up to and including `up_to_message_id` into a new session.
+pub fn fork_session(chat_id: &str, up_to_message_id: &str, new_title: Option<String>) -> Result<StoredSession, String> {
+    let source = load_session(chat_id)?;
+
+    let cut = source
+        .messages
+        .iter()
+        .position(|m| m.id == up_to_message_id)
+        .ok_or_else(|| format!("Message {} not found in chat {}", up_to_message_id, chat_id))?;
+
+    let title = new_title
+        .filter(|t| !t.trim().is_empty())
+        .unwrap_or_else(|| format!("{} (fork)", source.title));
+
+    let mut forked = new_session(title);
+    forked.messages = source.messages[..=cut].to_vec();
+    forked.metadata = source.metadata.clone();
+    if let Some(map) = forked.metadata.as_mut().and_then(|m| m.as_object_mut()) {
+        map.insert("forked_from".to_string(), serde_json::json!({
+            "chat_id": chat_id,
+            "message_id": up_to_message_id,
+        }));
+    }
+
+    save_session(&mut forked)?;
+    Ok(forked)
+}
+
+pub fn new_message(role: &str, content: String) -> StoredMessage {
+    StoredMessage {
+        id: format!("msg_{}", &uuid::Uuid::new_v4().simple().to_string()[..12]),
+        token_count: Some(crate::llm::estimate_tokens(&content) as i64),
+        content,
+        role: role.to_string(),
+        timestamp: now_timestamp(),
+        metadata: None,
+    }
+}
+
+/// Replace a message's content and drop everything after it.
+pub fn edit_message(chat_id: &str, message_id: &str, new_content: String) -> Result<StoredSession, String> {
+    let mut session = load_session(chat_id)?;
+
+    let index = session
+        .messages
+        .iter()
+        .position(|m| m.id == message_id)
+        .ok_or_else(|| format!("Message {} not found in chat {}", message_id, chat_id))?;
+
+    let removed = session.messages.len() - index - 1;
+    session.messages.truncate(index + 1);
+
+    let message = &mut session.messages[index];
+    message.token_count = Some(crate::llm::estimate_tokens(&new_content) as i64);
+    message.content = new_content;
+    let metadata = message.metadata.get_or_insert_with(|| serde_json::json!({}));
+    if let Some(map) = metadata.as_object_mut() {
+        map.insert("edited_at".to_string(), serde_json::json!(now_timestamp()));
+    }
+
+    save_session(&mut session)?;
+    info!("✏️ Edited message {} in chat {} and removed {} later messages", message_id, chat_id, removed);
+    Ok(session)
+}
+
+/// Permanently drop all but the last `keep_last_n` messages, optionally putting
+/// `summary` in their place as a system message. Returns how many were removed.
+///
+/// The Python backend reads the same file, so its view of the chat changes too.
+pub fn trim_history(chat_id: &str, keep_last_n: usize, summary: Option<String>) -> Result<(StoredSession, usize), String> {
+    let mut session = load_session(chat_id)?;
+
+    let removed = session.messages.len().saturating_sub(keep_last_n);
+    if removed == 0 {
+        return Ok((session, 0));
+    }
+    session.messages.drain(..removed);
+
+    if let Some(summary) = summary {
+        let mut message = new_message("system", summary);
+        message.metadata = Some(serde_json::json!({ "summary_of_messages": removed }));
+        session.messages.insert(0, message);
+    }
+
+    save_session(&mut session)?;
+    info!("✂️ Trimmed {} messages from chat {}, keeping the last {}", removed, chat_id, keep_last_n);
+    Ok((session, removed))
+}
+
+/// Create a session from a ChatGPT export conversation (the `mapping` tree shape).
+///
+/// Only the active branch is kept: the path from `current_node` back to the root,
+/// or the latest child at each step when `current_node` is missing. System, tool,
+/// hidden and non-text nodes are skipped.
+pub fn import_openai_conversation(export: &serde_json::Value) -> Result<StoredSession, String> {
+    // A full export is an array of conversations; accept it when it holds just one
+    let conversation = match export.as_array() {
+        Some(conversations) if conversations.len() == 1 => &conversations[0],
+        Some(conversations) => {
+            return Err(format!(
+                "Export contains {} conversations; import them one at a time",
+                conversations.len()
+            ))
+        }
+        None => export,
+    };
+
+    let mapping = conversation
+        .get("mapping")
+        .and_then(|m| m.as_object())
+        .ok_or("Not an OpenAI conversation export: missing \"mapping\"")?;
+
+    let title = conversation
+        .get("title")
+        .and_then(|t| t.as_str())
+        .filter(|t| !t.trim().is_empty())
+        .unwrap_or("Imported conversation")
+        .to_string();
+
+    let mut session = new_session(title);
+    for node_id in openai_active_path(conversation, mapping)? {
+        let Some(message) = mapping.get(&node_id).and_then(|node| node.get("message")) else {
+            continue;
+        };
+        if let Some(imported) = openai_message(message) {
+            session.messages.push(imported);
+        }
+    }
+
+    if session.messages.is_empty() {
+        return Err("Conversation has no user or assistant messages to import".to_string());
+    }
+
+    session.metadata = Some(serde_json::json!({ "imported_from": "openai" }));
+    save_session(&mut session)?;
+    info!("📥 Imported OpenAI conversation into {} ({} messages)", session.id, session.messages.len());
+    Ok(session)
+}
+
+// Node ids from the root to the active leaf
+fn openai_active_path(
+    conversation: &serde_json::Value,
+    mapping: &serde_json::Map<String, serde_json::Value>,
+) -> Result<Vec<String>, String> {
+    let parent_of = |id: &str| {
+        mapping
+            .get(id)
+            .and_then(|node| node.get("parent"))
+            .and_then(|p| p.as_str())
+            .map(|p| p.to_string())
+    };
+
+    let leaf = match conversation.get("current_node").and_then(|n| n.as_str()) {
+        Some(current) if mapping.contains_key(current) => current.to_string(),
+        _ => {
+            // No active pointer: start at the root and follow the newest child down
+            let mut node = mapping
+                .iter()
+                .find(|(_, node)| node.get("parent").map_or(true, |p| p.is_null()))
+                .map(|(id, _)| id.clone())
+                .ok_or("Conversation tree has no root node")?;
+            let mut steps = 0;
+            while let Some(child) = mapping
+                .get(&node)
+                .and_then(|n| n.get("children"))
+                .and_then(|c| c.as_array())
+                .and_then(|c| c.last())
+                .and_then(|c| c.as_str())
+            {
+                steps += 1;
+                if steps > mapping.len() {
+                    return Err("Conversation tree contains a cycle".to_string());
+                }
+                node = child.to_string();
+            }
+            node
+        }
+    };
+
+    let mut path = vec![leaf];
+    while let Some(parent) = parent_of(path.last().unwrap()) {
+        if path.len() > mapping.len() {
+            return Err("Conversation tree contains a cycle".to_string());
+        }
+        path.push(parent);
+    }
+    path.reverse();
+    Ok(path)
+}
+
+fn openai_message(message: &serde_json::Value) -> Option<StoredMessage> {
+    let role = message.get("author")?.get("role")?.as_str()?;
+    if role != "user" && role != "assistant" {
+        return None;
+    }
+
+    let hidden = message
+        .get("metadata")
+        .and_then(|m| m.get("is_visually_hidden_from_conversation"))
+        .and_then(|h| h.as_bool())
+        .unwrap_or(false);
+    if hidden {
+        return None;
+    }
+
+    // Multimodal parts mix strings with attachment objects; only the text is kept
+    let content = message.get("content")?;
+    let text = content
+        .get("parts")?
+        .as_array()?
+        .iter()
+        .filter_map(|part| part.as_str())
+        .collect::<Vec<_>>()
+        .join("\n");
+    if text.trim().is_empty() {
+        return None;
+    }
+
+    let mut imported = new_message(role, text);
+    if let Some(created) = message
+        .get("create_time")
+        .and_then(|t| t.as_f64())
+        .and_then(|t| chrono::DateTime::from_timestamp_micros((t * 1_000_000.0) as i64))
+    {
+        imported.timestamp = created.to_rfc3339_opts(SecondsFormat::Micros, false);
+    }
+    Some(imported)
+}
+
+/// Model recorded for the session by the backend, if any.
+pub fn session_model(session: &StoredSession) -> Option<String> {
+    session
+        .metadata
+        .as_ref()
+        .and_then(|m| m.get("model"))
+        .and_then(|m| m.as_str())
+        .map(|m| m.to_string())
+}
+
+/// Sampling parameters saved for this chat, if any.
+pub fn session_generation_options(session: &StoredSession) -> Option<crate::llm::GenerationOptions> {
+    session
+        .metadata
+        .as_ref()
+        .and_then(|m| m.get("generation_options"))
+        .and_then(|options| serde_json::from_value(options.clone()).ok())
+}
+
+/// System prompt saved for this chat, if any.
+pub fn session_system_prompt(session: &StoredSession) -> Option<String> {
+    session
+        .metadata
+        .as_ref()
+        .and_then(|m| m.get("system_prompt"))
+        .and_then(|p| p.as_str())
+        .map(|p| p.to_string())
+}
+
+/// Save (or with `None`, clear) a chat's sampling parameters.
+pub fn set_generation_options(chat_id: &str, options: Option<crate::llm::GenerationOptions>) -> Result<(), String> {
+    let value = options
+        .map(serde_json::to_value)
+        .transpose()
+        .map_err(|e| format!("Failed to serialize generation options: {}", e))?;
+    set_session_metadata(chat_id, "generation_options", value)
+}
+
+/// Save (or with `None`, clear) a chat's own system prompt.
+pub fn set_system_prompt(chat_id: &str, prompt: Option<String>) -> Result<(), String> {
+    set_session_metadata(chat_id, "system_prompt", prompt.map(serde_json::Value::String))
+}
+
+fn set_session_metadata(chat_id: &str, key: &str, value: Option<serde_json::Value>) -> Result<(), String> {
+    let mut session = load_session(chat_id)?;
+
+    let metadata = session.metadata.get_or_insert_with(|| serde_json::json!({}));
+    if let Some(map) = metadata.as_object_mut() {
+        match value {
+            Some(value) => {
+                map.insert(key.to_string(), value);
+            }
+            None => {
+                map.remove(key);
+            }
+        }
+    }
+
+    save_session(&mut session)
+}
+
+/// Flatten the conversation into a single prompt for /api/generate.
+pub fn build_conversation_prompt(messages: &[StoredMessage]) -> String {
+    let mut prompt = String::new();
+    for message in messages {
+        let speaker = match message.role.as_str() {
+            "user" => "User",
+            "assistant" => "Assistant",
+            _ => "System",
+        };
+        prompt.push_str(&format!("{}: {}\n\n", speaker, message.content));
+    }
+    prompt.push_str("Assistant:");
+    prompt
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ChatStatistics {
+    pub message_count: usize,
+    pub messages_by_role: std::collections::HashMap<String, usize>,
+    /// Stored token counts, estimated for messages that have none.
+    pub total_tokens: i64,
+    pub average_assistant_chars: usize,
+    pub models_used: Vec<String>,
+    pub first_message_at: Option<String>,
+    pub last_message_at: Option<String>,
+    pub time_span_secs: i64,
+    /// Sum of recorded reply generation times; `None` when no reply has one.
+    pub total_generation_ms: Option<u64>,
+    pub timed_replies: usize,
+}
+
+/// Aggregate counts, tokens, models and timings across the session's messages.
+pub fn session_statistics(session: &StoredSession) -> ChatStatistics {
+    let mut stats = ChatStatistics {
+        message_count: session.messages.len(),
+        first_message_at: session.messages.first().map(|m| m.timestamp.clone()),
+        last_message_at: session.messages.last().map(|m| m.timestamp.clone()),
+        ..Default::default()
+    };
+
+    let mut assistant_chars = 0;
+    let mut assistant_replies = 0;
+    for message in &session.messages {
+        *stats.messages_by_role.entry(message.role.clone()).or_insert(0) += 1;
+        stats.total_tokens += message
+            .token_count
+            .unwrap_or_else(|| crate::llm::estimate_tokens(&message.content) as i64);
+
+        if message.role != "assistant" {
+            continue;
+        }
+        assistant_chars += message.content.chars().count();
+        assistant_replies += 1;
+
+        let metadata = message.metadata.as_ref();
+        if let Some(model) = metadata.and_then(|m| m.get("model")).and_then(|m| m.as_str()) {
+            if !stats.models_used.iter().any(|used| used == model) {
+                stats.models_used.push(model.to_string());
+            }
+        }
+        // The frontend records `executionTime`; replies streamed by the backend record `generation_ms`
+        let generation_ms = metadata
+            .and_then(|m| m.get("generation_ms").or_else(|| m.get("executionTime")))
+            .and_then(|ms| ms.as_u64());
+        if let Some(ms) = generation_ms {
+            *stats.total_generation_ms.get_or_insert(0) += ms;
+            stats.timed_replies += 1;
+        }
+    }
+
+    if let Some(model) = session_model(session) {
+        if !stats.models_used.contains(&model) {
+            stats.models_used.push(model);
+        }
+    }
+    if assistant_replies > 0 {
+        stats.average_assistant_chars = assistant_chars / assistant_replies;
+    }
+    if let (Some(first), Some(last)) = (
+        stats.first_message_at.as_deref().and_then(parse_timestamp),
+        stats.last_message_at.as_deref().and_then(parse_timestamp),
+    ) {
+        stats.time_span_secs = (last - first).num_seconds().max(0);
+    }
+    stats
+}
+
+// Accept both RFC 3339 and the offset-less form Python's isoformat() writes for naive datetimes
+fn parse_timestamp(timestamp: &str) -> Option<chrono::DateTime<Utc>> {
+    chrono::DateTime::parse_from_rfc3339(timestamp)
+        .map(|t| t.with_timezone(&Utc))
+        .or_else(|_| {
+            chrono::NaiveDateTime::parse_from_str(timestamp, "%Y-%m-%dT%H:%M:%S%.f").map(|t| t.and_utc())
+        })
+        .ok()
+}
+
+// Column width for plain-text transcripts
+const TRANSCRIPT_WIDTH: usize = 80;
+
+/// Render the session as a printable, role-labeled transcript without markdown.
+pub fn export_session_as_text(session: &StoredSession) -> String {
+    let rule = "=".repeat(TRANSCRIPT_WIDTH);
+    let mut out = String::new();
+    out.push_str(&format!("{}\n{}\n", session.title, rule));
+    out.push_str(&format!("Date:  {}\n", format_transcript_time(&session.created_at)));
+    out.push_str(&format!("Model: {}\n", session_model(session).unwrap_or_else(|| "unknown".to_string())));
+    out.push_str(&format!("{}\n", rule));
+
+    for message in &session.messages {
+        let speaker = match message.role.as_str() {
+            "user" => "User",
+            "assistant" => "Assistant",
+            _ => "System",
+        };
+        out.push_str(&format!("\n[{}] {}:\n", format_transcript_time(&message.timestamp), speaker));
+        for line in strip_markdown(&message.content).lines() {
+            if line.trim().is_empty() {
+                out.push('\n');
+                continue;
+            }
+            for wrapped in wrap_line(line, TRANSCRIPT_WIDTH - 2) {
+                out.push_str(&format!("  {}\n", wrapped));
+            }
+        }
+    }
+    out
+}
+
+fn format_transcript_time(timestamp: &str) -> String {
+    chrono::DateTime::parse_from_rfc3339(timestamp)
+        .map(|t| t.format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_else(|_| timestamp.to_string())
+}
+
+/// Drop the markdown syntax that reads as noise on paper, keeping the text.
+fn strip_markdown(content: &str) -> String {
+    content
+        .lines()
+        .filter(|line| !line.trim_start().starts_with("```"))
+        .map(|line| {
+            let trimmed = line.trim_start();
+            let line = if trimmed.starts_with('#') {
+                trimmed.trim_start_matches('#').trim_start()
+            } else {
+                line
+            };
+            let mut text = String::with_capacity(line.len());
+            let mut rest = line;
+            // [label](url) -> label (url)
+            while let Some(open) = rest.find('[') {
+                let Some(close) = rest[open..].find("](").map(|i| open + i) else { break };
+                let Some(end) = rest[close..].find(')').map(|i| close + i) else { break };
+                text.push_str(&rest[..open]);
+                text.push_str(&format!("{} ({})", &rest[open + 1..close], &rest[close + 2..end]));
+                rest = &rest[end + 1..];
+            }
+            text.push_str(rest);
+            text.replace("**", "").replace("__", "").replace('`', "")
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Greedy word wrap; words longer than `width` get a line of their own.
+fn wrap_line(line: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut current = String::new();
+    for word in line.split_whitespace() {
+        if !current.is_empty() && current.chars().count() + 1 + word.chars().count() > width {
+            lines.push(std::mem::take(&mut current));
+        }
+        if !current.is_empty() {
+            current.push(' ');
+        }
+        current.push_str(word);
+    }
+    if !current.is_empty() {
+        lines.push(current);
+    }
+    lines
+}
+
+/// Persists an assistant reply while it streams.
+///
+/// The message is added up front with `"incomplete": true` in its metadata and
+/// rewritten at most once per `PARTIAL_SAVE_INTERVAL`. If the app dies mid-stream
+/// the partial text survives, still marked incomplete; `finish` clears the flag.
+pub struct PartialReplyWriter {
+    chat_id: String,
+    message_id: String,
+    started: Instant,
+    last_saved: Instant,
+    has_content: bool,
+}
+
+impl PartialReplyWriter {
+    pub fn start(
+        chat_id: &str,
+        stream_id: &str,
+        system_prompt_source: crate::settings::SystemPromptSource,
+    ) -> Result<Self, String> {
+        let mut session = load_session(chat_id)?;
+
+        let mut message = new_message("assistant", String::new());
+        message.metadata = Some(serde_json::json!({
+            "incomplete": true,
+            "stream_id": stream_id,
+            "system_prompt_source": system_prompt_source,
+        }));
+        let message_id = message.id.clone();
+        session.messages.push(message);
+        save_session(&mut session)?;
+
+        Ok(Self {
+            chat_id: chat_id.to_string(),
+            message_id,
+            started: Instant::now(),
+            last_saved: Instant::now(),
+            has_content: false,
+        })
+    }
+
+    /// Save the text so far if the save interval has passed.
+    pub fn update(&mut self, content: &str) {
+        if self.last_saved.elapsed() < PARTIAL_SAVE_INTERVAL {
+            return;
+        }
+        if let Err(e) = self.write(content, false) {
+            warn!("⚠️ Failed to persist partial reply: {}", e);
+        }
+        self.last_saved = Instant::now();
+    }
+
+    /// Save the text so far right away, keeping the incomplete marker.
+    pub fn save(&mut self, content: &str) {
+        if let Err(e) = self.write(content, false) {
+            warn!("⚠️ Failed to persist partial reply: {}", e);
+        }
+        self.last_saved = Instant::now();
+    }
+
+    /// Save the full reply and clear the incomplete marker.
+    pub fn finish(mut self, content: &str) {
+        if let Err(e) = self.write(content, true) {
+            error!("❌ Failed to persist final reply to chat {}: {}", self.chat_id, e);
+        }
+    }
+
+    /// Give up on the reply: remove the placeholder if nothing was ever saved into
+    /// it, otherwise leave the partial text marked incomplete.
+    pub fn abandon(self) {
+        if self.has_content {
+            return;
+        }
+        let removed = load_session(&self.chat_id).and_then(|mut session| {
+            session.messages.retain(|m| m.id != self.message_id);
+            save_session(&mut session)
+        });
+        if let Err(e) = removed {
+            warn!("⚠️ Failed to remove empty reply from chat {}: {}", self.chat_id, e);
+        }
+    }
+
+    fn write(&mut self, content: &str, complete: bool) -> Result<(), String> {
+        let mut session = load_session(&self.chat_id)?;
+        let message = session
+            .messages
+            .iter_mut()
+            .find(|m| m.id == self.message_id)
+            .ok_or_else(|| format!("Message {} was removed from chat {}", self.message_id, self.chat_id))?;
+
+        message.content = content.to_string();
+        message.token_count = Some(crate::llm::estimate_tokens(content) as i64);
+        self.has_content |= !content.is_empty();
+        if complete {
+            if let Some(map) = message.metadata.as_mut().and_then(|m| m.as_object_mut()) {
+                map.remove("incomplete");
+                map.insert("generation_ms".to_string(), serde_json::json!(self.started.elapsed().as_millis() as u64));
+            }
+        }
+
+        save_session(&mut session)
+    }
+}
@@ -70,7 +70,9 @@ pub fn invoke_llm_prompt(prompt: String) -> Result<String, String> {
 pub struct AppVersion {
     pub version: String,
     pub name: String,
+    /// When the binary was compiled, not when this was called.
     pub build_date: DateTime<Utc>,
+    pub git_commit: String,
 }
 
 #[tauri::command]
@@ -80,7 +82,12 @@ pub fn get_app_version() -> Result<AppVersion, String> {
     Ok(AppVersion {
         version: env!("CARGO_PKG_VERSION").to_string(),
         name: env!("CARGO_PKG_NAME").to_string(),
-        build_date: Utc::now(),
+        build_date: env!("BUILD_TIMESTAMP")
+            .parse()
+            .ok()
+            .and_then(|secs| DateTime::from_timestamp(secs, 0))
+            .unwrap_or_default(),
+        git_commit: env!("GIT_COMMIT_HASH").to_string(),
     })
 }
 
@@ -119,6 +126,7 @@ pub fn get_diagnostic_info() -> Result<serde_json::Value, String> {
             "os": std::env::consts::OS,
             "arch": std::env::consts::ARCH,
         },
+        "ffmpeg": crate::stt_tts::probe_ffmpeg(),
         "commands_available": [
             "get_app_version",
             "get_system_info", 
@@ -497,6 +505,273 @@ pub async fn get_chat_context(chat_id: String, system_prompt: Option<String>) ->
     }
 }
 
+#[tauri::command]
+pub async fn fork_chat_session(
+    chat_id: String,
+    up_to_message_id: String,
+    new_title: Option<String>,
+) -> Result<CreateChatResponse, CommandError> {
+    info!("Forking chat session {} at message {}", chat_id, up_to_message_id);
+
+    match crate::chat_store::fork_session(&chat_id, &up_to_message_id, new_title) {
+        Ok(forked) => {
+            info!("✅ Forked chat {} into {} ({} messages)", chat_id, forked.id, forked.messages.len());
+            Ok(CreateChatResponse {
+                chat_id: forked.id,
+                title: forked.title,
+                success: true,
+                error: None,
+            })
+        }
+        Err(e) => {
+            error!("❌ Failed to fork chat session: {}", e);
+            Ok(CreateChatResponse {
+                chat_id: String::new(),
+                title: String::new(),
+                success: false,
+                error: Some(e),
+            })
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ImportConversationResponse {
+    pub chat_id: String,
+    pub title: String,
+    pub message_count: usize,
+    pub success: bool,
+    pub error: Option<String>,
+}
+
+#[tauri::command]
+pub async fn import_openai_conversation(json: String) -> Result<ImportConversationResponse, CommandError> {
+    info!("Importing OpenAI conversation export ({} bytes)", json.len());
+
+    let imported = serde_json::from_str::<serde_json::Value>(&json)
+        .map_err(|e| format!("Invalid JSON: {}", e))
+        .and_then(|export| crate::chat_store::import_openai_conversation(&export));
+
+    match imported {
+        Ok(session) => Ok(ImportConversationResponse {
+            message_count: session.messages.len(),
+            chat_id: session.id,
+            title: session.title,
+            success: true,
+            error: None,
+        }),
+        Err(e) => {
+            error!("❌ Failed to import conversation: {}", e);
+            Ok(ImportConversationResponse {
+                chat_id: String::new(),
+                title: String::new(),
+                message_count: 0,
+                success: false,
+                error: Some(e),
+            })
+        }
+    }
+}
+
+impl From<crate::chat_store::StoredSession> for ChatSession {
+    fn from(session: crate::chat_store::StoredSession) -> Self {
+        ChatSession {
+            id: session.id,
+            title: session.title,
+            messages: session
+                .messages
+                .into_iter()
+                .map(|m| ChatMessage {
+                    id: m.id,
+                    content: m.content,
+                    role: m.role,
+                    timestamp: m.timestamp,
+                    token_count: m.token_count.map(|t| t as i32),
+                })
+                .collect(),
+            created_at: session.created_at,
+            updated_at: session.updated_at,
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn edit_message(
+    chat_id: String,
+    message_id: String,
+    new_content: String,
+    regenerate: Option<bool>,
+) -> Result<ChatSessionResponse, CommandError> {
+    info!("Editing message {} in chat {}", message_id, chat_id);
+
+    let mut session = match crate::chat_store::edit_message(&chat_id, &message_id, new_content) {
+        Ok(session) => session,
+        Err(e) => {
+            error!("❌ Failed to edit message: {}", e);
+            return Ok(ChatSessionResponse {
+                session: None,
+                success: false,
+                error: Some(e),
+            });
+        }
+    };
+
+    // Regenerate the assistant turn that followed an edited user message
+    let edited_user_message = session.messages.last().map_or(false, |m| m.role == "user");
+    if regenerate.unwrap_or(true) && edited_user_message {
+        let model = crate::chat_store::session_model(&session)
+            .unwrap_or_else(|| crate::llm::LLMConfig::default().model);
+        let system_prompt = crate::settings::resolve_system_prompt(Some(&chat_id), None);
+        let prompt = crate::llm::combine_with_system_prompt(
+            system_prompt.prompt.as_deref(),
+            crate::chat_store::build_conversation_prompt(&session.messages),
+        );
+
+        let client = crate::llm::LLMClient::with_config(crate::llm::LLMConfig {
+            model,
+            options: crate::chat_store::session_generation_options(&session),
+            ..crate::llm::LLMConfig::default()
+        });
+        let generated = match client {
+            Ok(client) => client.generate_response(prompt).await.map_err(|e| e.to_string()),
+            Err(e) => Err(e),
+        };
+
+        match generated {
+            Ok(response) => {
+                let mut reply = crate::chat_store::new_message("assistant", response);
+                reply.metadata = Some(serde_json::json!({ "system_prompt_source": system_prompt.source }));
+                session.messages.push(reply);
+                crate::chat_store::save_session(&mut session).map_err(CommandError::Llm)?;
+                info!("✅ Regenerated assistant response for chat {}", chat_id);
+            }
+            Err(e) => {
+                error!("❌ Regeneration failed after edit: {}", e);
+                return Ok(ChatSessionResponse {
+                    session: Some(session.into()),
+                    success: false,
+                    error: Some(format!("Message edited but regeneration failed: {}", e)),
+                });
+            }
+        }
+    }
+
+    Ok(ChatSessionResponse {
+        session: Some(session.into()),
+        success: true,
+        error: None,
+    })
+}
+
+#[tauri::command]
+pub async fn export_chat_as_text(chat_id: String) -> Result<String, String> {
+    let session = crate::chat_store::load_session(&chat_id)?;
+    info!("📄 Exporting chat {} as plain text ({} messages)", chat_id, session.messages.len());
+    Ok(crate::chat_store::export_session_as_text(&session))
+}
+
+// Validate every local chat file, repairing or quarantining damaged ones
+#[tauri::command]
+pub async fn verify_chat_store() -> Result<crate::chat_store::ChatStoreCheck, String> {
+    info!("🩺 Verifying local chat store");
+    crate::chat_store::verify()
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TrimmedHistory {
+    pub messages_removed: usize,
+    pub summarized: bool,
+    pub remaining_messages: usize,
+    pub token_estimate: i64,
+}
+
+// Irreversibly drop old messages to reclaim space; `summarize` keeps a generated recap of them
+#[tauri::command]
+pub async fn trim_chat_history(
+    chat_id: String,
+    keep_last_n: usize,
+    confirm: bool,
+    summarize: Option<bool>,
+) -> Result<TrimmedHistory, String> {
+    if !confirm {
+        return Err("Refusing to trim chat history without confirm: true".to_string());
+    }
+
+    let session = crate::chat_store::load_session(&chat_id)?;
+    let to_remove = session.messages.len().saturating_sub(keep_last_n);
+
+    // Summarize before touching the file so a failed generation leaves the chat intact
+    let summary = if summarize.unwrap_or(false) && to_remove > 0 {
+        let client = crate::llm::LLMClient::with_config(crate::llm::LLMConfig {
+            model: crate::chat_store::session_model(&session)
+                .unwrap_or_else(|| crate::llm::LLMConfig::default().model),
+            ..crate::llm::LLMConfig::default()
+        })?;
+        let prompt = format!(
+            "Summarize this conversation in one short paragraph, keeping the facts and decisions needed to continue it.\n\n{}",
+            crate::chat_store::build_conversation_prompt(&session.messages[..to_remove])
+        );
+        let summary = client
+            .generate_response(prompt)
+            .await
+            .map_err(|e| format!("Failed to summarize trimmed messages, chat left unchanged: {}", e))?;
+        Some(format!("Summary of earlier conversation: {}", summary))
+    } else {
+        None
+    };
+
+    warn!("✂️ Trimming chat {} to its last {} messages", chat_id, keep_last_n);
+    let summarized = summary.is_some();
+    let (session, messages_removed) = crate::chat_store::trim_history(&chat_id, keep_last_n, summary)?;
+
+    Ok(TrimmedHistory {
+        messages_removed,
+        summarized: summarized && messages_removed > 0,
+        remaining_messages: session.messages.len(),
+        token_estimate: session.messages.iter().filter_map(|m| m.token_count).sum(),
+    })
+}
+
+// Empty chats report zeros rather than an error
+#[tauri::command]
+pub async fn get_chat_statistics(chat_id: String) -> Result<crate::chat_store::ChatStatistics, String> {
+    let session = crate::chat_store::load_session(&chat_id)?;
+    Ok(crate::chat_store::session_statistics(&session))
+}
+
+#[tauri::command]
+pub async fn get_chat_system_prompt(chat_id: String) -> Result<Option<String>, String> {
+    let session = crate::chat_store::load_session(&chat_id)?;
+    Ok(crate::chat_store::session_system_prompt(&session))
+}
+
+/// Set the chat's own system prompt, which takes precedence over the global default.
+#[tauri::command]
+pub async fn set_chat_system_prompt(chat_id: String, prompt: Option<String>) -> Result<(), String> {
+    let prompt = prompt.filter(|p| !p.trim().is_empty());
+    info!("⚙️ Setting system prompt for chat {} (length: {})", chat_id, prompt.as_ref().map_or(0, |p| p.len()));
+    crate::chat_store::set_system_prompt(&chat_id, prompt)
+}
+
+#[tauri::command]
+pub async fn get_chat_generation_options(chat_id: String) -> Result<Option<crate::llm::GenerationOptions>, String> {
+    let session = crate::chat_store::load_session(&chat_id)?;
+    Ok(crate::chat_store::session_generation_options(&session))
+}
+
+#[tauri::command]
+pub async fn set_chat_generation_options(
+    chat_id: String,
+    options: Option<crate::llm::GenerationOptions>,
+) -> Result<(), String> {
+    if let Some(options) = &options {
+        options.validate()?;
+    }
+
+    info!("⚙️ Setting generation options for chat {}: {:?}", chat_id, options);
+    crate::chat_store::set_generation_options(&chat_id, options)
+}
+
 // ===== HARDWARE DETECTION COMMANDS =====
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -506,6 +781,9 @@ pub struct HardwareInfo {
     pub ram_available_mb: Option<i32>,
     pub has_gpu: bool,
     pub gpu_name: Option<String>,
+    /// Whether the reported GPU is a dedicated card; unknown when it came from the Python backend.
+    #[serde(default)]
+    pub gpu_discrete: Option<bool>,
     pub vram_total_mb: Option<i32>,
     pub vram_available_mb: Option<i32>,
     pub platform: Option<String>,
@@ -560,7 +838,11 @@ pub async fn get_hardware_info() -> Result<HardwareResponse, CommandError> {
 
     // Fallback to basic Rust-based hardware detection
     info!("Using fallback hardware detection");
+    Ok(basic_hardware_response())
+}
 
+/// Hardware info and runtime config from the Rust-side detection, re-probed on every call.
+fn basic_hardware_response() -> HardwareResponse {
     let hardware_info = get_basic_hardware_info();
     let runtime_config = determine_basic_runtime_config(&hardware_info);
 
@@ -571,6 +853,7 @@ pub async fn get_hardware_info() -> Result<HardwareResponse, CommandError> {
             "ram_available_mb": hardware_info.ram_available_mb,
             "has_gpu": hardware_info.has_gpu,
             "gpu_name": hardware_info.gpu_name,
+            "gpu_discrete": hardware_info.gpu_discrete,
             "vram_total_mb": hardware_info.vram_total_mb,
             "vram_available_mb": hardware_info.vram_available_mb,
             "platform": hardware_info.platform
@@ -584,12 +867,12 @@ pub async fn get_hardware_info() -> Result<HardwareResponse, CommandError> {
     });
 
     info!("✅ Retrieved hardware information using fallback detection");
-    Ok(HardwareResponse {
+    HardwareResponse {
         success: true,
         data: Some(fallback_data),
         config: Some(runtime_config),
         error: None,
-    })
+    }
 }
 
 #[tauri::command]
@@ -653,30 +936,808 @@ pub async fn refresh_hardware_detection() -> Result<serde_json::Value, CommandEr
 
     match client
         .post(&format!("{}/hardware/refresh", PYTHON_BACKEND_URL))
+        .timeout(std::time::Duration::from_secs(10))
         .send()
         .await
     {
+        Ok(response) if response.status().is_success() => {
+            let result: serde_json::Value = response.json().await?;
+            info!("✅ Hardware detection refreshed");
+            return Ok(result);
+        }
         Ok(response) => {
-            if response.status().is_success() {
-                let result: serde_json::Value = response.json().await?;
-                info!("✅ Hardware detection refreshed");
-                Ok(result)
-            } else {
-                let error_msg = format!("Backend returned status: {}", response.status());
-                error!("❌ Failed to refresh hardware detection: {}", error_msg);
-                Ok(serde_json::json!({
-                    "success": false,
-                    "error": error_msg
-                }))
-            }
+            warn!("⚠️ Backend hardware refresh returned status: {}", response.status());
         }
         Err(e) => {
-            error!("❌ Request failed: {}", e);
-            Err(CommandError::Reqwest(e))
+            warn!("Python backend not available for hardware refresh: {}", e);
+        }
+    }
+
+    // Re-run the Rust-side detection so the hardware panel still updates offline
+    info!("Refreshing hardware using fallback detection");
+    Ok(serde_json::to_value(basic_hardware_response())?)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RecommendedSetting<T> {
+    pub value: T,
+    pub rationale: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GenerationSettingsRecommendation {
+    pub mode: String,
+    pub num_predict: RecommendedSetting<i32>,
+    pub context_length: RecommendedSetting<u32>,
+    pub keep_alive: RecommendedSetting<String>,
+}
+
+#[tauri::command]
+pub async fn recommend_generation_settings() -> Result<GenerationSettingsRecommendation, CommandError> {
+    info!("Recommending generation settings for detected hardware");
+
+    let config = detect_runtime_config().await;
+    let recommendation = generation_settings_for(&config.mode, config.hardware_info.vram_total_mb);
+    info!("✅ Recommended generation settings for {} mode", recommendation.mode);
+    Ok(recommendation)
+}
+
+/// Runtime config from the backend when available, otherwise from Rust-side detection.
+async fn detect_runtime_config() -> RuntimeConfig {
+    // The backend's detection knows about VRAM; the Rust fallback usually doesn't
+    match get_runtime_config().await {
+        Ok(HardwareResponse { config: Some(config), .. }) => config,
+        _ => determine_basic_runtime_config(&get_basic_hardware_info()),
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AppliedTimeoutProfile {
+    pub mode: String,
+    /// Timeouts suggested for this hardware mode, now saved as the profile.
+    pub profile: crate::llm::LlmTimeouts,
+    /// Timeouts actually in effect; differ from `profile` when the user has set their own.
+    pub effective: crate::llm::LlmTimeouts,
+    pub user_override: bool,
+}
+
+// Slow CPU-only machines need longer timeouts than GPU ones to finish long answers
+#[tauri::command]
+pub async fn apply_hardware_timeout_profile() -> Result<AppliedTimeoutProfile, String> {
+    let config = detect_runtime_config().await;
+    let profile = crate::llm::LlmTimeouts::for_hardware_mode(&config.mode);
+
+    info!("⚙️ Applying {} timeout profile: {:?}", config.mode, profile);
+    crate::settings::update(|settings| settings.hardware_timeouts = Some(profile.clone()))?;
+
+    let user_override = crate::settings::get().llm_timeouts.is_some();
+    if user_override {
+        info!("ℹ️ User-set LLM timeouts take precedence over the {} profile", config.mode);
+    }
+    Ok(AppliedTimeoutProfile {
+        mode: config.mode,
+        profile,
+        effective: crate::llm::llm_timeouts(),
+        user_override,
+    })
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GpuFitCheck {
+    pub model: String,
+    pub fits_in_vram: bool,
+    pub estimated_vram_mb: Option<u64>,
+    pub available_vram_mb: Option<u64>,
+    pub quantization: Option<String>,
+    pub recommendation: String,
+}
+
+// Headroom for the KV cache and CUDA/Metal runtime on top of the weights
+const VRAM_OVERHEAD_FACTOR: f64 = 1.2;
+const VRAM_OVERHEAD_BASE_MB: u64 = 512;
+
+#[tauri::command]
+pub async fn check_model_fits_gpu(model: String) -> Result<GpuFitCheck, CommandError> {
+    info!("Checking whether {} fits in GPU memory", model);
+
+    let base_url = crate::llm::active_endpoint().base_url;
+    let client = crate::network::build_http_client(&base_url, "gpu fit check", Some(std::time::Duration::from_secs(10)))
+        .map_err(CommandError::Llm)?;
+    let show: serde_json::Value = client
+        .post(format!("{}/api/show", base_url))
+        .json(&serde_json::json!({ "model": model }))
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+
+    let quantization = show["details"]["quantization_level"].as_str().map(|q| q.to_string());
+
+    // Prefer the on-disk size Ollama reports; otherwise derive it from the parameter count
+    let weights_bytes = match installed_model_size(&client, &base_url, &model).await {
+        Some(size) => Some(size),
+        None => show["model_info"]["general.parameter_count"]
+            .as_u64()
+            .map(|params| (params as f64 * bytes_per_parameter(quantization.as_deref())) as u64),
+    };
+    let estimated_vram_mb = weights_bytes
+        .map(|bytes| (bytes as f64 / 1024.0 / 1024.0 * VRAM_OVERHEAD_FACTOR) as u64 + VRAM_OVERHEAD_BASE_MB);
+
+    let hardware = detect_runtime_config().await.hardware_info;
+    let available_vram_mb = hardware
+        .vram_available_mb
+        .or(hardware.vram_total_mb)
+        .filter(|_| hardware.has_gpu)
+        .map(|mb| mb.max(0) as u64);
+
+    let (fits_in_vram, recommendation) = match (estimated_vram_mb, available_vram_mb) {
+        (_, None) if !hardware.has_gpu => (
+            false,
+            "No GPU detected; this model will run on the CPU".to_string(),
+        ),
+        (_, None) => (
+            false,
+            "GPU memory could not be detected; the model may run partly on the CPU".to_string(),
+        ),
+        (None, Some(_)) => (
+            false,
+            "Model size could not be determined".to_string(),
+        ),
+        (Some(needed), Some(available)) if needed <= available => (
+            true,
+            "The model fits in GPU memory and should run at full speed".to_string(),
+        ),
+        (Some(needed), Some(available)) => (
+            false,
+            format!(
+                "Needs about {}MB but only {}MB of VRAM is available; this will run partly on the CPU and be slow. Consider a smaller or more quantized model.",
+                needed, available
+            ),
+        ),
+    };
+
+    info!("✅ GPU fit for {}: {} ({:?}MB needed, {:?}MB available)", model, fits_in_vram, estimated_vram_mb, available_vram_mb);
+    Ok(GpuFitCheck {
+        model,
+        fits_in_vram,
+        estimated_vram_mb,
+        available_vram_mb,
+        quantization,
+        recommendation,
+    })
+}
+
+async fn installed_model_size(client: &reqwest::Client, base_url: &str, model: &str) -> Option<u64> {
+    let tags = client
+        .get(format!("{}/api/tags", base_url))
+        .timeout(std::time::Duration::from_secs(5))
+        .send()
+        .await
+        .ok()?
+        .json::<crate::python_backend::OllamaModelsResponse>()
+        .await
+        .ok()?;
+
+    tags.models.into_iter().find(|m| m.name == model).map(|m| m.size)
+}
+
+/// Hardware and model details to attach to benchmark results, so throughput
+/// figures shared between users can be compared.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BenchmarkContext {
+    pub cpu_model: Option<String>,
+    pub cpu_cores: i32,
+    pub cpu_physical_cores: Option<usize>,
+    pub ram_total_mb: Option<i32>,
+    pub gpu_name: Option<String>,
+    pub gpu_discrete: Option<bool>,
+    pub vram_total_mb: Option<i32>,
+    pub os: Option<String>,
+    pub arch: String,
+    pub app_version: String,
+    pub ollama_version: Option<String>,
+    pub model: String,
+    pub model_family: Option<String>,
+    pub model_parameter_size: Option<String>,
+    pub model_quantization: Option<String>,
+    pub model_size_bytes: Option<u64>,
+    pub captured_at: DateTime<Utc>,
+}
+
+// One snapshot of the machine and model, for benchmark numbers to be shared with.
+// Ollama details are left empty rather than failing when Ollama isn't reachable.
+#[tauri::command]
+pub async fn get_benchmark_context(model: Option<String>) -> Result<BenchmarkContext, CommandError> {
+    let model = model.unwrap_or_else(|| crate::llm::LLMConfig::default().model);
+    info!("Capturing benchmark context for {}", model);
+
+    let hardware = detect_runtime_config().await.hardware_info;
+    let (cpu_model, cpu_physical_cores) = {
+        let mut sys = System::new();
+        sys.refresh_cpu();
+        let cpu_model = sys
+            .cpus()
+            .first()
+            .map(|cpu| cpu.brand().trim().to_string())
+            .filter(|brand| !brand.is_empty());
+        (cpu_model, sys.physical_core_count())
+    };
+
+    let base_url = crate::llm::active_endpoint().base_url;
+    let client = crate::network::build_http_client(&base_url, "benchmark context", Some(std::time::Duration::from_secs(5)))
+        .map_err(CommandError::Llm)?;
+    let ollama_version = match client.get(format!("{}/api/version", base_url)).send().await {
+        Ok(response) => response
+            .json::<serde_json::Value>()
+            .await
+            .ok()
+            .and_then(|v| v["version"].as_str().map(|v| v.to_string())),
+        Err(e) => {
+            warn!("⚠️ Could not get Ollama version for benchmark context: {}", e);
+            None
+        }
+    };
+    let show = match client
+        .post(format!("{}/api/show", base_url))
+        .json(&serde_json::json!({ "model": model }))
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+    {
+        Ok(response) => response.json::<serde_json::Value>().await.unwrap_or_default(),
+        Err(e) => {
+            warn!("⚠️ Could not get details of {} for benchmark context: {}", model, e);
+            serde_json::Value::Null
         }
+    };
+    let detail = |key: &str| show["details"][key].as_str().map(|value| value.to_string());
+
+    let context = BenchmarkContext {
+        cpu_model,
+        cpu_cores: hardware.cpu_cores,
+        cpu_physical_cores,
+        ram_total_mb: hardware.ram_total_mb,
+        gpu_name: hardware.gpu_name.filter(|_| hardware.has_gpu),
+        gpu_discrete: hardware.gpu_discrete,
+        vram_total_mb: hardware.vram_total_mb,
+        os: hardware.platform,
+        arch: env::consts::ARCH.to_string(),
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        ollama_version,
+        model_family: detail("family"),
+        model_parameter_size: detail("parameter_size"),
+        model_quantization: detail("quantization_level"),
+        model_size_bytes: installed_model_size(&client, &base_url, &model).await,
+        model,
+        captured_at: Utc::now(),
+    };
+    info!("✅ Benchmark context: {:?} / {:?} / {} {:?}", context.cpu_model, context.gpu_name, context.model, context.model_quantization);
+    Ok(context)
+}
+
+fn bytes_per_parameter(quantization: Option<&str>) -> f64 {
+    let quantization = quantization.unwrap_or("").to_uppercase();
+    if quantization.starts_with("Q2") {
+        0.35
+    } else if quantization.starts_with("Q3") {
+        0.45
+    } else if quantization.starts_with("Q4") {
+        0.57
+    } else if quantization.starts_with("Q5") {
+        0.69
+    } else if quantization.starts_with("Q6") {
+        0.82
+    } else if quantization.starts_with("Q8") {
+        1.07
+    } else if quantization.starts_with("F32") {
+        4.0
+    } else {
+        // F16/BF16 and anything unrecognised
+        2.0
     }
 }
 
+fn generation_settings_for(mode: &str, vram_total_mb: Option<i32>) -> GenerationSettingsRecommendation {
+    fn setting<T>(value: T, rationale: &str) -> RecommendedSetting<T> {
+        RecommendedSetting { value, rationale: rationale.to_string() }
+    }
+
+    let (num_predict, context_length, keep_alive) = match (mode, vram_total_mb) {
+        ("gpu", Some(vram)) if vram >= 8000 => (
+            setting(2048, "Plenty of VRAM, so long answers still generate quickly"),
+            setting(8192, "A large context fits in VRAM alongside the model weights"),
+            setting("5m".to_string(), "GPU loads are fast, so the model can be unloaded sooner to free VRAM"),
+        ),
+        ("gpu", _) => (
+            setting(1024, "Moderate cap keeps generation on the GPU responsive"),
+            setting(4096, "Limited or unknown VRAM; a larger context may spill onto the CPU"),
+            setting("5m".to_string(), "GPU loads are fast, so the model can be unloaded sooner to free VRAM"),
+        ),
+        ("hybrid", _) => (
+            setting(1024, "Part of the model runs on the CPU, so very long answers get slow"),
+            setting(4096, "Balances conversation length against RAM use"),
+            setting("15m".to_string(), "Reloading from RAM takes a while, so keep the model warm longer"),
+        ),
+        _ => (
+            setting(512, "CPU-only generation is slow; shorter answers keep waits reasonable"),
+            setting(2048, "A small context keeps prompt processing fast on the CPU"),
+            setting("30m".to_string(), "Loading on CPU is slow, so avoid unloading between messages"),
+        ),
+    };
+
+    GenerationSettingsRecommendation {
+        mode: mode.to_string(),
+        num_predict,
+        context_length,
+        keep_alive,
+    }
+}
+
+// ===== STORAGE COMMANDS =====
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DiskUsage {
+    pub path: String,
+    pub mount_point: String,
+    pub free_bytes: u64,
+    pub total_bytes: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StorageInfo {
+    pub models_dir: DiskUsage,
+    pub app_data_dir: Option<DiskUsage>,
+    pub models_total_bytes: Option<u64>,
+    pub installed_model_count: Option<usize>,
+}
+
+#[tauri::command]
+pub async fn get_storage_info(app_handle: tauri::AppHandle) -> Result<StorageInfo, String> {
+    use tauri::Manager;
+
+    info!("💾 Getting storage information");
+
+    let disks = sysinfo::Disks::new_with_refreshed_list();
+
+    let models_dir = ollama_models_dir();
+    let models_usage = disk_usage_for_path(&disks, &models_dir);
+
+    let app_data_usage = match app_handle.path().app_data_dir() {
+        Ok(dir) => Some(disk_usage_for_path(&disks, &dir)),
+        Err(e) => {
+            warn!("⚠️ Could not resolve app data dir: {}", e);
+            None
+        }
+    };
+
+    // Installed model sizes come straight from Ollama so they match what `ollama list` reports
+    let base_url = crate::llm::active_endpoint().base_url;
+    let response = match crate::network::build_http_client(&base_url, "model storage info", Some(std::time::Duration::from_secs(5))) {
+        Ok(client) => client.get(format!("{}/api/tags", base_url)).send().await.map_err(|e| e.to_string()),
+        Err(e) => Err(e),
+    };
+    let models = match response {
+        Ok(response) if response.status().is_success() => {
+            response.json::<crate::python_backend::OllamaModelsResponse>().await.ok()
+        }
+        Ok(response) => {
+            warn!("⚠️ Ollama tags request returned status: {}", response.status());
+            None
+        }
+        Err(e) => {
+            warn!("⚠️ Ollama not reachable for model sizes: {}", e);
+            None
+        }
+    };
+
+    let models_total_bytes = models.as_ref().map(|m| m.models.iter().map(|model| model.size).sum());
+    let installed_model_count = models.as_ref().map(|m| m.models.len());
+
+    info!("✅ Storage info: {} bytes free on {}", models_usage.free_bytes, models_usage.mount_point);
+    Ok(StorageInfo {
+        models_dir: models_usage,
+        app_data_dir: app_data_usage,
+        models_total_bytes,
+        installed_model_count,
+    })
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OllamaModelsDir {
+    pub path: String,
+    pub exists: bool,
+    /// "env" when OLLAMA_MODELS is set, otherwise "default".
+    pub source: String,
+}
+
+#[tauri::command]
+pub async fn get_ollama_models_dir() -> Result<OllamaModelsDir, String> {
+    let (path, source) = resolve_ollama_models_dir();
+    info!("📁 Ollama models directory ({}): {:?}", source, path);
+
+    Ok(OllamaModelsDir {
+        exists: path.is_dir(),
+        path: path.to_string_lossy().to_string(),
+        source: source.to_string(),
+    })
+}
+
+/// Resolve where Ollama keeps its model blobs.
+fn ollama_models_dir() -> std::path::PathBuf {
+    resolve_ollama_models_dir().0
+}
+
+fn resolve_ollama_models_dir() -> (std::path::PathBuf, &'static str) {
+    if let Ok(dir) = env::var("OLLAMA_MODELS") {
+        if !dir.trim().is_empty() {
+            return (std::path::PathBuf::from(dir), "env");
+        }
+    }
+
+    // %USERPROFILE%\.ollama\models on Windows, ~/.ollama/models elsewhere
+    let home = env::var("USERPROFILE")
+        .or_else(|_| env::var("HOME"))
+        .unwrap_or_else(|_| ".".to_string());
+    let user_dir = std::path::Path::new(&home).join(".ollama").join("models");
+
+    // The Linux install script runs Ollama as its own service user
+    if cfg!(target_os = "linux") && !user_dir.exists() {
+        let service_dir = std::path::PathBuf::from("/usr/share/ollama/.ollama/models");
+        if service_dir.exists() {
+            return (service_dir, "default");
+        }
+    }
+
+    (user_dir, "default")
+}
+
+/// Find the disk whose mount point is the longest prefix of `path`.
+fn disk_usage_for_path(disks: &sysinfo::Disks, path: &std::path::Path) -> DiskUsage {
+    let disk = disks
+        .list()
+        .iter()
+        .filter(|disk| path.starts_with(disk.mount_point()))
+        .max_by_key(|disk| disk.mount_point().as_os_str().len());
+
+    match disk {
+        Some(disk) => DiskUsage {
+            path: path.to_string_lossy().to_string(),
+            mount_point: disk.mount_point().to_string_lossy().to_string(),
+            free_bytes: disk.available_space(),
+            total_bytes: disk.total_space(),
+        },
+        None => DiskUsage {
+            path: path.to_string_lossy().to_string(),
+            mount_point: String::new(),
+            free_bytes: 0,
+            total_bytes: 0,
+        },
+    }
+}
+
+// ===== PRIVACY RESET =====
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DataResetSummary {
+    pub chats_deleted: usize,
+    pub temp_audio_files_deleted: u32,
+    pub temp_audio_bytes_freed: u64,
+    pub audit_entries_cleared: usize,
+    pub cached_pages_cleared: usize,
+    pub settings_reset: bool,
+    pub models_deleted: Vec<String>,
+    /// Things that could not be removed; the reset carries on past them.
+    pub errors: Vec<String>,
+}
+
+#[tauri::command]
+pub async fn reset_all_data(
+    confirm: bool,
+    include_settings: Option<bool>,
+    include_models: Option<bool>,
+) -> Result<DataResetSummary, String> {
+    if !confirm {
+        return Err("Refusing to reset data without confirm: true".to_string());
+    }
+
+    warn!("🧨 Resetting all local data (settings: {:?}, models: {:?})", include_settings, include_models);
+    let mut summary = DataResetSummary {
+        chats_deleted: 0,
+        temp_audio_files_deleted: 0,
+        temp_audio_bytes_freed: 0,
+        audit_entries_cleared: 0,
+        cached_pages_cleared: 0,
+        settings_reset: false,
+        models_deleted: Vec::new(),
+        errors: Vec::new(),
+    };
+
+    // Chat sessions, shared with the Python backend
+    let chats_dir = crate::chat_store::chats_dir();
+    if let Ok(entries) = std::fs::read_dir(&chats_dir) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("json") {
+                continue;
+            }
+            match std::fs::remove_file(&path) {
+                Ok(_) => summary.chats_deleted += 1,
+                Err(e) => summary.errors.push(format!("Failed to delete {:?}: {}", path, e)),
+            }
+        }
+    }
+
+    let cleanup = crate::stt_tts::cleanup_temp_audio_files(std::time::Duration::ZERO);
+    summary.temp_audio_files_deleted = cleanup.files_deleted;
+    summary.temp_audio_bytes_freed = cleanup.bytes_freed;
+
+    summary.audit_entries_cleared = crate::network::clear_audit_log();
+    summary.cached_pages_cleared = crate::web_integration::clear_cached_pages();
+
+    if include_settings.unwrap_or(false) {
+        match crate::settings::reset() {
+            Ok(_) => summary.settings_reset = true,
+            Err(e) => summary.errors.push(e),
+        }
+    }
+
+    if include_models.unwrap_or(false) {
+        match delete_all_ollama_models().await {
+            Ok(deleted) => summary.models_deleted = deleted,
+            Err(e) => summary.errors.push(e),
+        }
+    }
+
+    info!(
+        "✅ Data reset: {} chats, {} temp audio files, {} audit entries, settings reset: {}, {} models",
+        summary.chats_deleted,
+        summary.temp_audio_files_deleted,
+        summary.audit_entries_cleared,
+        summary.settings_reset,
+        summary.models_deleted.len()
+    );
+    Ok(summary)
+}
+
+async fn delete_all_ollama_models() -> Result<Vec<String>, String> {
+    let client = reqwest::Client::new();
+    let models = client
+        .get("http://localhost:11434/api/tags")
+        .timeout(std::time::Duration::from_secs(5))
+        .send()
+        .await
+        .map_err(|e| format!("Ollama not reachable to delete models: {}", e))?
+        .json::<crate::python_backend::OllamaModelsResponse>()
+        .await
+        .map_err(|e| format!("Failed to list Ollama models: {}", e))?;
+
+    let mut deleted = Vec::new();
+    for model in models.models {
+        let response = client
+            .delete("http://localhost:11434/api/delete")
+            .json(&serde_json::json!({ "name": model.name }))
+            .send()
+            .await;
+        match response {
+            Ok(response) if response.status().is_success() => {
+                info!("🗑️ Deleted Ollama model {}", model.name);
+                deleted.push(model.name);
+            }
+            Ok(response) => warn!("⚠️ Ollama refused to delete {}: {}", model.name, response.status()),
+            Err(e) => warn!("⚠️ Failed to delete Ollama model {}: {}", model.name, e),
+        }
+    }
+    Ok(deleted)
+}
+
+// ===== EFFECTIVE CONFIGURATION =====
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EffectiveConfig {
+    pub llm: crate::llm::LLMConfig,
+    pub tts: crate::stt_tts::TtsConfig,
+    pub dashboard: crate::dashboard_api::DashboardConfig,
+    pub offline_mode: bool,
+    /// Proxy picked up from the environment, which reqwest uses automatically.
+    pub proxy: Option<String>,
+    pub python_backend_url: String,
+    pub default_system_prompt: Option<String>,
+    pub fallback_to_smaller_model: bool,
+    pub max_recording_secs: u64,
+}
+
+#[tauri::command]
+pub async fn get_effective_config() -> Result<EffectiveConfig, String> {
+    info!("⚙️ Resolving effective configuration");
+
+    let settings = crate::settings::get();
+    let proxy = ["HTTPS_PROXY", "https_proxy", "HTTP_PROXY", "http_proxy", "ALL_PROXY", "all_proxy"]
+        .iter()
+        .find_map(|name| env::var(name).ok().filter(|value| !value.trim().is_empty()))
+        .map(|proxy| redact_url_credentials(&proxy));
+
+    Ok(EffectiveConfig {
+        llm: crate::llm::LLMConfig::default(),
+        tts: settings.tts.unwrap_or_default(),
+        dashboard: settings.dashboard.unwrap_or_default(),
+        offline_mode: settings.offline_mode,
+        proxy,
+        python_backend_url: PYTHON_BACKEND_URL.to_string(),
+        default_system_prompt: settings.default_system_prompt,
+        fallback_to_smaller_model: settings.fallback_to_smaller_model,
+        max_recording_secs: crate::stt_tts::max_recording_secs(),
+    })
+}
+
+/// Hide `user:password@` in a URL so it can be displayed.
+fn redact_url_credentials(raw: &str) -> String {
+    match url::Url::parse(raw) {
+        Ok(mut parsed) if parsed.password().is_some() || !parsed.username().is_empty() => {
+            let _ = parsed.set_username("[REDACTED]");
+            let _ = parsed.set_password(None);
+            parsed.to_string()
+        }
+        _ => raw.to_string(),
+    }
+}
+
+// ===== DIAGNOSTICS BUNDLE =====
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DiagnosticsBundleResult {
+    pub path: String,
+    pub files: Vec<String>,
+    pub size_bytes: u64,
+}
+
+#[tauri::command]
+pub async fn export_diagnostics_bundle(
+    app_handle: tauri::AppHandle,
+    output_path: Option<String>,
+) -> Result<DiagnosticsBundleResult, String> {
+    use tauri::Manager;
+    use tauri_plugin_dialog::DialogExt;
+
+    info!("📦 Exporting diagnostics bundle");
+
+    let output_path = match output_path {
+        Some(path) => std::path::PathBuf::from(path),
+        None => {
+            let default_name = format!("privacy-ai-diagnostics-{}.zip", Utc::now().format("%Y%m%d-%H%M%S"));
+            let dialog = app_handle
+                .dialog()
+                .file()
+                .add_filter("Zip archive", &["zip"])
+                .set_file_name(default_name);
+
+            // The blocking dialog must not run on an async runtime worker
+            let picked = tokio::task::spawn_blocking(move || dialog.blocking_save_file())
+                .await
+                .map_err(|e| format!("Save dialog failed: {}", e))?;
+
+            picked
+                .ok_or_else(|| "Export cancelled".to_string())?
+                .into_path()
+                .map_err(|e| format!("Invalid save location: {}", e))?
+        }
+    };
+
+    let mut entries: Vec<(String, String)> = Vec::new();
+
+    let version = serde_json::to_value(get_app_version()?).unwrap_or_default();
+    let system = serde_json::to_value(get_system_info()?).unwrap_or_default();
+    entries.push(("app_info.json".to_string(), to_redacted_json(serde_json::json!({
+        "version": version,
+        "system": system,
+    }))));
+
+    entries.push(("diagnostics.json".to_string(), to_redacted_json(get_diagnostic_info()?)));
+
+    let hardware = match get_hardware_info().await {
+        Ok(hardware) => serde_json::to_value(hardware).unwrap_or_default(),
+        Err(e) => serde_json::json!({ "error": e.to_string() }),
+    };
+    entries.push(("hardware.json".to_string(), to_redacted_json(hardware)));
+
+    let base_url = crate::llm::active_endpoint().base_url;
+    let response = match crate::network::build_http_client(&base_url, "diagnostics bundle", Some(std::time::Duration::from_secs(5))) {
+        Ok(client) => client.get(format!("{}/api/tags", base_url)).send().await.map_err(|e| format!("Ollama not reachable: {}", e)),
+        Err(e) => Err(e),
+    };
+    let models = match response {
+        Ok(response) => response.json::<serde_json::Value>().await.unwrap_or_default(),
+        Err(e) => serde_json::json!({ "error": e }),
+    };
+    entries.push(("models.json".to_string(), to_redacted_json(models)));
+
+    let settings = serde_json::to_value(crate::settings::get()).unwrap_or_default();
+    entries.push(("settings.json".to_string(), to_redacted_json(settings)));
+
+    // Recent lines kept in memory by the logger, then any log files on disk
+    let recent_lines = crate::logging::recent_lines();
+    if !recent_lines.is_empty() {
+        entries.push(("logs/recent.log".to_string(), redact_secrets(&recent_lines.join("\n"))));
+    }
+    if let Ok(log_dir) = app_handle.path().app_log_dir() {
+        if let Ok(log_files) = std::fs::read_dir(&log_dir) {
+            for entry in log_files.flatten() {
+                let name = entry.file_name().to_string_lossy().to_string();
+                if !name.ends_with(".log") {
+                    continue;
+                }
+                match std::fs::read_to_string(entry.path()) {
+                    Ok(contents) => entries.push((format!("logs/{}", name), redact_secrets(&contents))),
+                    Err(e) => warn!("⚠️ Skipping unreadable log file {}: {}", name, e),
+                }
+            }
+        }
+    }
+
+    write_zip(&output_path, &entries)?;
+
+    let size_bytes = std::fs::metadata(&output_path).map(|m| m.len()).unwrap_or(0);
+    info!("✅ Diagnostics bundle written to {:?} ({} bytes)", output_path, size_bytes);
+
+    Ok(DiagnosticsBundleResult {
+        path: output_path.to_string_lossy().to_string(),
+        files: entries.into_iter().map(|(name, _)| name).collect(),
+        size_bytes,
+    })
+}
+
+fn write_zip(path: &std::path::Path, entries: &[(String, String)]) -> Result<(), String> {
+    use std::io::Write;
+
+    let file = std::fs::File::create(path)
+        .map_err(|e| format!("Failed to create bundle file: {}", e))?;
+    let mut zip = zip::ZipWriter::new(file);
+    let options = zip::write::FileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated);
+
+    for (name, contents) in entries {
+        zip.start_file(name.as_str(), options)
+            .map_err(|e| format!("Failed to add {} to bundle: {}", name, e))?;
+        zip.write_all(contents.as_bytes())
+            .map_err(|e| format!("Failed to write {} to bundle: {}", name, e))?;
+    }
+
+    zip.finish().map_err(|e| format!("Failed to finalize bundle: {}", e))?;
+    Ok(())
+}
+
+fn to_redacted_json(mut value: serde_json::Value) -> String {
+    redact_json(&mut value);
+    let text = serde_json::to_string_pretty(&value).unwrap_or_default();
+    redact_secrets(&text)
+}
+
+// Blank out values whose keys look like credentials
+fn redact_json(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(map) => {
+            for (key, entry) in map.iter_mut() {
+                let key = key.to_lowercase();
+                let sensitive = ["api_key", "apikey", "secret", "password"]
+                    .iter()
+                    .any(|marker| key.contains(marker))
+                    || key.ends_with("token");
+                if sensitive && !entry.is_null() {
+                    *entry = serde_json::Value::String("[REDACTED]".to_string());
+                } else {
+                    redact_json(entry);
+                }
+            }
+        }
+        serde_json::Value::Array(items) => items.iter_mut().for_each(redact_json),
+        _ => {}
+    }
+}
+
+fn redact_secrets(text: &str) -> String {
+    text.replace(crate::dashboard_api::JWT_SECRET, "[REDACTED]")
+}
+
 // ===== CONTEXT-AWARE LLM COMMANDS =====
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -694,6 +1755,9 @@ pub struct LLMResponse {
     pub model: String,
     pub success: bool,
     pub error: Option<String>,
+    /// Which system prompt was sent; the backend doesn't report it.
+    #[serde(default)]
+    pub system_prompt_source: crate::settings::SystemPromptSource,
 }
 
 #[tauri::command]
@@ -707,12 +1771,14 @@ pub async fn generate_chat_llm_response(
     info!("Generating context-aware LLM response for chat: {}", chat_id);
 
     let client = reqwest::Client::new();
+    let system_prompt = crate::settings::resolve_system_prompt(Some(&chat_id), system_prompt);
+    info!("📝 System prompt source: {:?}", system_prompt.source);
     let request = ChatLLMRequest {
         chat_id,
         prompt,
         model,
         stream,
-        system_prompt,
+        system_prompt: system_prompt.prompt,
     };
 
     match client
@@ -723,7 +1789,10 @@ pub async fn generate_chat_llm_response(
     {
         Ok(response) => {
             if response.status().is_success() {
-                let llm_response: LLMResponse = response.json().await?;
+                let llm_response = LLMResponse {
+                    system_prompt_source: system_prompt.source,
+                    ..response.json().await?
+                };
                 if llm_response.success {
                     info!("✅ Generated context-aware response (length: {})", llm_response.response.len());
                 } else {
@@ -738,6 +1807,7 @@ pub async fn generate_chat_llm_response(
                     model: request.model,
                     success: false,
                     error: Some(error_msg),
+                    system_prompt_source: system_prompt.source,
                 })
             }
         }
@@ -763,8 +1833,9 @@ fn get_basic_hardware_info() -> HardwareInfo {
     let ram_total_mb = Some((total_memory / 1024 / 1024) as i32);
     let ram_available_mb = Some((available_memory / 1024 / 1024) as i32);
 
-    // Basic GPU detection (simplified)
-    let (has_gpu, gpu_name) = detect_basic_gpu();
+    // Report the discrete GPU when there is one, otherwise the integrated one
+    let gpus = list_gpus();
+    let best = best_gpu(&gpus);
 
     // Platform information
     let platform = Some(format!("{} {}",
@@ -776,30 +1847,67 @@ fn get_basic_hardware_info() -> HardwareInfo {
         cpu_cores,
         ram_total_mb,
         ram_available_mb,
-        has_gpu,
-        gpu_name,
-        vram_total_mb: None, // Not easily detectable without specialized tools
+        has_gpu: best.is_some(),
+        gpu_name: best.map(|gpu| gpu.name.clone()),
+        gpu_discrete: best.map(|gpu| gpu.discrete),
+        vram_total_mb: best.and_then(|gpu| gpu.vram_mb),
         vram_available_mb: None,
         platform,
     }
 }
 
-fn detect_basic_gpu() -> (bool, Option<String>) {
-    // Try to detect GPU using system commands
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GpuInfo {
+    pub name: String,
+    pub vendor: String,
+    /// Dedicated graphics card, as opposed to one built into the CPU.
+    pub discrete: bool,
+    pub vram_mb: Option<i32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GpuDetection {
+    pub gpus: Vec<GpuInfo>,
+    /// The GPU recommendations are based on: discrete over integrated, then most VRAM.
+    pub best: Option<GpuInfo>,
+}
+
+// Every video controller on the system, so hybrid laptops aren't judged by their integrated GPU
+#[tauri::command]
+pub async fn detect_gpus() -> Result<GpuDetection, String> {
+    let gpus = tokio::task::spawn_blocking(list_gpus)
+        .await
+        .map_err(|e| format!("GPU detection failed: {}", e))?;
+    let best = best_gpu(&gpus).cloned();
+    info!("🎮 Detected {} GPU(s), best: {:?}", gpus.len(), best.as_ref().map(|gpu| &gpu.name));
+    Ok(GpuDetection { gpus, best })
+}
+
+fn list_gpus() -> Vec<GpuInfo> {
     #[cfg(target_os = "windows")]
     {
+        // AdapterRAM is a 32-bit field, so cards with more than 4GB report 4GB
         if let Ok(output) = Command::new("wmic")
-            .args(&["path", "win32_VideoController", "get", "name"])
+            .args(&["path", "win32_VideoController", "get", "Name,AdapterRAM", "/format:csv"])
             .output()
         {
             let output_str = String::from_utf8_lossy(&output.stdout);
-            if output_str.contains("NVIDIA") {
-                return (true, Some("NVIDIA GPU".to_string()));
-            } else if output_str.contains("AMD") || output_str.contains("Radeon") {
-                return (true, Some("AMD GPU".to_string()));
-            } else if output_str.contains("Intel") {
-                return (true, Some("Intel GPU".to_string()));
-            }
+            let mut lines = output_str.lines().map(|line| line.trim()).filter(|line| !line.is_empty());
+            let header: Vec<String> = lines.next().unwrap_or("").split(',').map(|h| h.to_lowercase()).collect();
+            let name_col = header.iter().position(|h| h == "name");
+            let ram_col = header.iter().position(|h| h == "adapterram");
+
+            return lines
+                .filter_map(|line| {
+                    let fields: Vec<&str> = line.split(',').collect();
+                    let name = fields.get(name_col?)?.trim().to_string();
+                    let vram_mb = ram_col
+                        .and_then(|col| fields.get(col))
+                        .and_then(|ram| ram.trim().parse::<u64>().ok())
+                        .map(|bytes| (bytes / 1024 / 1024) as i32);
+                    Some(classify_gpu(name, vram_mb))
+                })
+                .collect();
         }
     }
 
@@ -807,13 +1915,33 @@ fn detect_basic_gpu() -> (bool, Option<String>) {
     {
         if let Ok(output) = Command::new("lspci").output() {
             let output_str = String::from_utf8_lossy(&output.stdout);
-            if output_str.contains("NVIDIA") {
-                return (true, Some("NVIDIA GPU".to_string()));
-            } else if output_str.contains("AMD") || output_str.contains("Radeon") {
-                return (true, Some("AMD GPU".to_string()));
-            } else if output_str.contains("Intel") && output_str.contains("VGA") {
-                return (true, Some("Intel GPU".to_string()));
-            }
+            // nvidia-smi is the only common source of VRAM here; it lists NVIDIA cards in bus order
+            let mut nvidia_vram = Command::new("nvidia-smi")
+                .args(&["--query-gpu=memory.total", "--format=csv,noheader,nounits"])
+                .output()
+                .map(|output| {
+                    String::from_utf8_lossy(&output.stdout)
+                        .lines()
+                        .filter_map(|line| line.trim().parse::<i32>().ok())
+                        .collect::<Vec<_>>()
+                })
+                .unwrap_or_default()
+                .into_iter();
+
+            return output_str
+                .lines()
+                .filter(|line| {
+                    line.contains("VGA compatible controller")
+                        || line.contains("3D controller")
+                        || line.contains("Display controller")
+                })
+                .filter_map(|line| line.splitn(2, "controller: ").nth(1))
+                .map(|name| {
+                    let name = name.trim().to_string();
+                    let vram_mb = if name.contains("NVIDIA") { nvidia_vram.next() } else { None };
+                    classify_gpu(name, vram_mb)
+                })
+                .collect();
         }
     }
 
@@ -824,17 +1952,70 @@ fn detect_basic_gpu() -> (bool, Option<String>) {
             .output()
         {
             let output_str = String::from_utf8_lossy(&output.stdout);
-            if !output_str.is_empty() {
-                return (true, Some("GPU Detected".to_string()));
+            let mut gpus: Vec<GpuInfo> = Vec::new();
+            for line in output_str.lines().map(|line| line.trim()) {
+                if let Some(name) = line.strip_prefix("Chipset Model:") {
+                    gpus.push(classify_gpu(name.trim().to_string(), None));
+                } else if let Some(vram) = line.strip_prefix("VRAM (Total):").or_else(|| line.strip_prefix("VRAM (Dynamic, Max):")) {
+                    if let Some(gpu) = gpus.last_mut() {
+                        gpu.vram_mb = parse_vram_mb(vram.trim());
+                    }
+                }
             }
+            return gpus;
         }
     }
 
-    (false, None)
+    #[allow(unreachable_code)]
+    Vec::new()
+}
+
+#[cfg(target_os = "macos")]
+fn parse_vram_mb(text: &str) -> Option<i32> {
+    let mut parts = text.split_whitespace();
+    let amount: i32 = parts.next()?.parse().ok()?;
+    match parts.next()? {
+        "GB" => Some(amount * 1024),
+        "MB" => Some(amount),
+        _ => None,
+    }
+}
+
+fn classify_gpu(name: String, vram_mb: Option<i32>) -> GpuInfo {
+    let lower = name.to_lowercase();
+    let (vendor, discrete) = if lower.contains("nvidia") || lower.contains("geforce") || lower.contains("quadro") {
+        ("NVIDIA", true)
+    } else if lower.contains("amd") || lower.contains("radeon") || lower.contains("ati ") {
+        // APUs report as plain "Radeon Graphics" or Vega; dedicated cards carry a series name
+        let integrated = lower.contains("radeon graphics")
+            || lower.contains("radeon(tm) graphics")
+            || (lower.contains("vega") && !lower.contains("rx"));
+        ("AMD", !integrated)
+    } else if lower.contains("intel") {
+        // Only Arc A/B-series cards are dedicated; "Intel Arc Graphics" is the Meteor Lake iGPU
+        ("Intel", lower.contains("arc a") || lower.contains("arc b") || lower.contains("arc(tm) a"))
+    } else if lower.contains("apple") {
+        ("Apple", false)
+    } else {
+        ("Unknown", false)
+    };
+
+    GpuInfo {
+        name,
+        vendor: vendor.to_string(),
+        discrete,
+        vram_mb,
+    }
+}
+
+fn best_gpu(gpus: &[GpuInfo]) -> Option<&GpuInfo> {
+    gpus.iter().max_by_key(|gpu| (gpu.discrete, gpu.vram_mb.unwrap_or(0)))
 }
 
 fn determine_basic_runtime_config(hardware: &HardwareInfo) -> RuntimeConfig {
-    let mode = if hardware.has_gpu {
+    // An integrated GPU shares system RAM and doesn't justify the GPU-tier models
+    let has_discrete_gpu = hardware.has_gpu && hardware.gpu_discrete.unwrap_or(true);
+    let mode = if has_discrete_gpu {
         "gpu".to_string()
     } else if hardware.ram_total_mb.unwrap_or(0) > 8000 {
         "hybrid".to_string()
@@ -842,7 +2023,7 @@ fn determine_basic_runtime_config(hardware: &HardwareInfo) -> RuntimeConfig {
         "cpu".to_string()
     };
 
-    let reason = if hardware.has_gpu {
+    let reason = if has_discrete_gpu {
         format!("GPU detected: {}", hardware.gpu_name.as_ref().unwrap_or(&"Unknown GPU".to_string()))
     } else if hardware.ram_total_mb.unwrap_or(0) > 8000 {
         format!("High RAM available: {}MB", hardware.ram_total_mb.unwrap_or(0))
@@ -851,11 +2032,7 @@ fn determine_basic_runtime_config(hardware: &HardwareInfo) -> RuntimeConfig {
             hardware.ram_total_mb.unwrap_or(0), hardware.cpu_cores)
     };
 
-    let recommended_models = match mode.as_str() {
-        "gpu" => vec!["gemma3n:7b".to_string(), "llama3.1:8b".to_string()],
-        "hybrid" => vec!["gemma3n:3b".to_string(), "phi3:medium".to_string()],
-        _ => vec!["gemma3n:2b".to_string(), "phi3:mini".to_string()],
-    };
+    let recommended_models = recommended_models_for_mode(&mode);
 
     RuntimeConfig {
         mode,
@@ -865,3 +2042,28 @@ fn determine_basic_runtime_config(hardware: &HardwareInfo) -> RuntimeConfig {
         hardware_info: hardware.clone(),
     }
 }
+
+fn recommended_models_for_mode(mode: &str) -> Vec<String> {
+    match mode {
+        "gpu" => vec!["gemma3n:7b".to_string(), "llama3.1:8b".to_string()],
+        "hybrid" => vec!["gemma3n:3b".to_string(), "phi3:medium".to_string()],
+        _ => vec!["gemma3n:2b".to_string(), "phi3:mini".to_string()],
+    }
+}
+
+/// The largest installed recommended model that is strictly smaller than `current`,
+/// going by the on-disk sizes Ollama reports in `installed`.
+///
+/// None when `current` isn't installed, since there is nothing to compare against.
+pub fn next_smaller_model(current: &str, installed: &[crate::python_backend::OllamaModel]) -> Option<String> {
+    let size_of = |name: &str| installed.iter().find(|model| model.name == name).map(|model| model.size);
+    let current_size = size_of(current)?;
+
+    ["gpu", "hybrid", "cpu"]
+        .iter()
+        .flat_map(|mode| recommended_models_for_mode(mode))
+        .filter_map(|model| size_of(&model).map(|size| (model, size)))
+        .filter(|(_, size)| *size < current_size)
+        .max_by_key(|(_, size)| *size)
+        .map(|(model, _)| model)
+}
@@ -7,8 +7,12 @@ use uuid::Uuid;
 use log::{info, error, warn};
 
 // JWT Configuration
-const JWT_SECRET: &str = "privacy_ai_assistant_dashboard_secret_key_2024";
+pub(crate) const JWT_SECRET: &str = "privacy_ai_assistant_dashboard_secret_key_2024";
 const TOKEN_EXPIRATION_HOURS: i64 = 1;
+// Tolerated difference between issue and validation clocks
+const JWT_LEEWAY_SECS: u64 = 60;
+// Server whose HTTP Date header is used as the reference clock
+const CLOCK_REFERENCE_URL: &str = "https://www.cloudflare.com";
 
 // Data structures for dashboard integration
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -65,6 +69,19 @@ pub struct DashboardConfig {
     pub theme: String, // "light", "dark", "auto"
 }
 
+impl Default for DashboardConfig {
+    fn default() -> Self {
+        Self {
+            refresh_interval_ms: 5000,
+            show_hardware_details: true,
+            show_model_metrics: true,
+            show_tool_dashboard: true,
+            auto_refresh_enabled: true,
+            theme: "auto".to_string(),
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 struct Claims {
     sub: String, // Subject (user identifier)
@@ -103,7 +120,8 @@ pub async fn generate_dashboard_token() -> Result<String, String> {
 
 #[command]
 pub async fn validate_dashboard_token(token: String) -> Result<bool, String> {
-    let validation = Validation::new(Algorithm::HS256);
+    let mut validation = Validation::new(Algorithm::HS256);
+    validation.leeway = JWT_LEEWAY_SECS;
     
     match decode::<Claims>(
         &token,
@@ -126,6 +144,78 @@ pub async fn validate_dashboard_token(token: String) -> Result<bool, String> {
     }
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ClockSanity {
+    pub local_time: DateTime<Utc>,
+    pub reference_time: Option<DateTime<Utc>>,
+    pub reference_source: Option<String>,
+    /// Local minus reference time; positive when the local clock is ahead.
+    pub skew_seconds: Option<i64>,
+    pub warning: Option<String>,
+}
+
+// Compare the local clock to a server's Date header, since a skewed clock makes
+// fresh dashboard tokens look expired or not yet valid
+#[command]
+pub async fn check_clock_sanity() -> Result<ClockSanity, String> {
+    info!("🕒 Checking system clock against {}", CLOCK_REFERENCE_URL);
+
+    let unchecked = |warning: String| ClockSanity {
+        local_time: Utc::now(),
+        reference_time: None,
+        reference_source: None,
+        skew_seconds: None,
+        warning: Some(warning),
+    };
+
+    let client = match crate::network::build_http_client(
+        CLOCK_REFERENCE_URL,
+        "clock check",
+        Some(std::time::Duration::from_secs(10)),
+    ) {
+        Ok(client) => client,
+        Err(e) => return Ok(unchecked(format!("Clock not checked: {}", e))),
+    };
+
+    let sent_at = Utc::now();
+    let response = match client.head(CLOCK_REFERENCE_URL).send().await {
+        Ok(response) => response,
+        Err(e) => return Ok(unchecked(format!("Clock not checked: reference server unreachable ({})", e))),
+    };
+    let received_at = Utc::now();
+
+    let reference_time = response
+        .headers()
+        .get(reqwest::header::DATE)
+        .and_then(|date| date.to_str().ok())
+        .and_then(|date| DateTime::parse_from_rfc2822(date).ok())
+        .map(|date| date.with_timezone(&Utc));
+    let Some(reference_time) = reference_time else {
+        return Ok(unchecked("Clock not checked: reference server sent no usable Date header".to_string()));
+    };
+
+    // The server stamped its reply somewhere during the round trip; assume the middle
+    let local_time = sent_at + (received_at - sent_at) / 2;
+    let skew_seconds = (local_time - reference_time).num_seconds();
+    let warning = (skew_seconds.unsigned_abs() > JWT_LEEWAY_SECS).then(|| {
+        warn!("⚠️ System clock is off by {}s; dashboard tokens may be rejected", skew_seconds);
+        format!(
+            "System clock is {}s {} the reference time; dashboard tokens may be rejected until it is corrected",
+            skew_seconds.abs(),
+            if skew_seconds > 0 { "ahead of" } else { "behind" }
+        )
+    });
+
+    info!("✅ Clock skew: {}s", skew_seconds);
+    Ok(ClockSanity {
+        local_time,
+        reference_time: Some(reference_time),
+        reference_source: Some(CLOCK_REFERENCE_URL.to_string()),
+        skew_seconds: Some(skew_seconds),
+        warning,
+    })
+}
+
 // Dashboard data endpoints
 #[command]
 pub async fn get_hardware_metrics() -> Result<HardwareData, String> {
@@ -175,31 +265,32 @@ pub async fn get_model_status() -> Result<ModelStatus, String> {
     
     // Use existing LLM health check
     let health_result = crate::llm::check_llm_health().await;
+    let activity = crate::llm::model_activity();
     
     let model_status = match health_result {
         Ok(is_healthy) => {
             ModelStatus {
-                model_name: "gemma3n:latest".to_string(),
+                model_name: crate::llm::active_model(),
                 connection_state: if is_healthy {
                     "connected".to_string()
                 } else {
                     "disconnected".to_string()
                 },
-                response_time_ms: None, // Will be updated with actual timing
+                response_time_ms: activity.last_latency_ms,
                 last_check: Utc::now(),
                 provider: "local".to_string(),
-                is_streaming: false, // Will be updated based on active streams
+                is_streaming: activity.active_streams > 0,
                 error_message: None,
             }
         }
         Err(e) => {
             ModelStatus {
-                model_name: "gemma3n:latest".to_string(),
+                model_name: crate::llm::active_model(),
                 connection_state: "error".to_string(),
-                response_time_ms: None,
+                response_time_ms: activity.last_latency_ms,
                 last_check: Utc::now(),
                 provider: "local".to_string(),
-                is_streaming: false,
+                is_streaming: activity.active_streams > 0,
                 error_message: Some(e),
             }
         }
@@ -272,14 +363,19 @@ pub async fn update_dashboard_config(config: DashboardConfig) -> Result<(), Stri
         return Err("Refresh interval must be at least 1000ms".to_string());
     }
     
-    // Store configuration (in a real implementation, this would persist to storage)
-    info!("✅ Dashboard configuration updated successfully");
     info!("📊 New config: refresh_interval={}ms, auto_refresh={}, theme={}", 
           config.refresh_interval_ms, config.auto_refresh_enabled, config.theme);
+    crate::settings::update(|settings| settings.dashboard = Some(config))?;
+    info!("✅ Dashboard configuration updated successfully");
     
     Ok(())
 }
 
+#[command]
+pub async fn get_dashboard_config() -> Result<DashboardConfig, String> {
+    Ok(crate::settings::get().dashboard.unwrap_or_default())
+}
+
 // Helper functions
 fn calculate_memory_usage_percentage(hardware: &serde_json::Value) -> f32 {
     let total = hardware.get("ram_total_mb").and_then(|v| v.as_u64()).unwrap_or(0) as f32;
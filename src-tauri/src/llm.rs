@@ -10,12 +10,79 @@ const OLLAMA_BASE_URL: &str = "http://localhost:11434";
 const DEFAULT_MODEL: &str = "gemma3n:latest"; // EXCLUSIVE: Only gemma3n:latest model
 const REQUEST_TIMEOUT: Duration = Duration::from_secs(120); // 2 minutes timeout
 const STREAM_TIMEOUT: Duration = Duration::from_secs(180); // 3 minutes for streaming
+const STREAM_METRICS_INTERVAL: Duration = Duration::from_secs(1); // How often streams report progress
+const STREAM_EVENT_SCHEMA_VERSION: u32 = 2; // Bump when StreamEvent's shape changes
+pub const STREAM_EVENT_NAME: &str = "llm-stream-event"; // Every stream reports through this event
+const PIPELINE_CHECK_TIMEOUT: Duration = Duration::from_secs(2); // How long verify_stream_pipeline waits for its events
+const STOP_REGEX_WINDOW: usize = 1024; // Bytes before the newest token re-checked for a stop_regex match
+const PAUSE_POLL_INTERVAL: Duration = Duration::from_millis(100); // How often an idle stream re-checks its pause flag
+const REPETITION_MAX_NGRAM: usize = 12; // Longest phrase, in words, the repetition guard looks for
+pub(crate) const MIN_REPETITION_THRESHOLD: usize = 3; // Lower thresholds would cut off ordinary emphasis
+const LLM_STREAM_TASK_PREFIX: &str = "llm-stream:";
+const MODEL_LOAD_WAIT: Duration = Duration::from_secs(120); // How long to wait out a model that is still loading
+const DEFAULT_CONTEXT_LENGTH: u32 = 4096; // Used when Ollama doesn't report one
+const SYSTEM_PROMPT_WARN_PERCENT: f64 = 25.0; // Share of the context a system prompt may use before warning
+const CONTEXT_CEILING_SLACK_PERCENT: u32 = 1; // A prompt this close to the window was cut to fit it
+const RESPONSIVENESS_SLOW_MS: u64 = 3_000; // A one-token probe slower than this is "slow"
+const RESPONSIVENESS_VERY_SLOW_MS: u64 = 15_000; // ...and slower than this is "very slow"
+const RESPONSIVENESS_PROBE_TIMEOUT: Duration = Duration::from_secs(60); // Give up and call it very slow
+const SCHEMA_MAX_ATTEMPTS: u32 = 3; // generate_with_schema tries, including the first
+const LOCAL_ENDPOINT_NAME: &str = "local"; // The built-in Ollama endpoint, used when no other is active
+const COMPLETE_ACK_TIMEOUT: Duration = Duration::from_secs(5); // Re-emit a complete event the frontend hasn't acked by then
+const MAX_UNACKED_STREAMS: usize = 50; // Oldest unacked completions are forgotten past this
+const DEFAULT_MAX_CONCURRENT_STREAMS: usize = 4; // Streams allowed at once unless the user sets max_concurrent_streams
+const DEFAULT_FALLBACK_WORD_DELAY_MS: u64 = 80; // Pause between words when replaying a one-shot reply as a stream
+const MAX_FALLBACK_WORD_DELAY_MS: u64 = 1000; // Slower than this looks broken rather than deliberate
+const NON_JSON_SNIPPET_CHARS: usize = 200; // How much of an HTML/plain-text body to quote in the error
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct OllamaRequest {
     pub model: String,
     pub prompt: String,
     pub stream: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub options: Option<GenerationOptions>,
+    /// `"json"` or a JSON schema to constrain the output to.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub format: Option<serde_json::Value>,
+}
+
+/// Sampling parameters passed through to Ollama's `options`; unset fields use the model defaults.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GenerationOptions {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_k: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub repeat_penalty: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub num_predict: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<i64>,
+}
+
+impl GenerationOptions {
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(temperature) = self.temperature {
+            if !(0.0..=2.0).contains(&temperature) {
+                return Err("Temperature must be between 0 and 2".to_string());
+            }
+        }
+        if let Some(top_p) = self.top_p {
+            if !(0.0..=1.0).contains(&top_p) {
+                return Err("top_p must be between 0 and 1".to_string());
+            }
+        }
+        if let Some(repeat_penalty) = self.repeat_penalty {
+            if repeat_penalty < 0.0 {
+                return Err("repeat_penalty must not be negative".to_string());
+            }
+        }
+        Ok(())
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -31,6 +98,9 @@ pub struct OllamaResponse {
     pub prompt_eval_duration: Option<u64>,
     pub eval_count: Option<u32>,
     pub eval_duration: Option<u64>,
+    /// "stop" or "length" (hit num_predict) on the final response.
+    #[serde(default)]
+    pub done_reason: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -38,21 +108,745 @@ pub struct LLMConfig {
     pub model: String,
     pub base_url: String,
     pub timeout_seconds: u64,
+    #[serde(default)]
+    pub options: Option<GenerationOptions>,
+    #[serde(default)]
+    pub flush_every: StreamFlush,
+    /// How long to keep retrying while Ollama is still loading the model.
+    #[serde(default = "default_model_load_wait_seconds")]
+    pub model_load_wait_seconds: u64,
+    /// Passed through as Ollama's `format`.
+    #[serde(default)]
+    pub format: Option<serde_json::Value>,
+    #[serde(default)]
+    pub provider: LlmProvider,
+    #[serde(default, skip_serializing)]
+    pub api_key: Option<String>,
+}
+
+/// The API an endpoint speaks.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LlmProvider {
+    #[default]
+    Ollama,
+    /// Any server with an OpenAI-style `/v1/chat/completions` (llama.cpp, vLLM, LM Studio, ...).
+    OpenaiCompatible,
+}
+
+/// A named LLM server the user can switch generation to.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LlmEndpoint {
+    pub name: String,
+    pub provider: LlmProvider,
+    pub base_url: String,
+    #[serde(default)]
+    pub api_key: Option<String>,
+    /// Model used on this endpoint when a command doesn't name one.
+    #[serde(default)]
+    pub default_model: Option<String>,
+}
+
+impl LlmEndpoint {
+    fn local() -> Self {
+        Self {
+            name: LOCAL_ENDPOINT_NAME.to_string(),
+            provider: LlmProvider::Ollama,
+            base_url: OLLAMA_BASE_URL.to_string(),
+            api_key: None,
+            default_model: None,
+        }
+    }
+}
+
+/// As listed by `list_llm_endpoints`; the API key itself is never sent back.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LlmEndpointInfo {
+    pub name: String,
+    pub provider: LlmProvider,
+    pub base_url: String,
+    pub has_api_key: bool,
+    pub default_model: Option<String>,
+    pub active: bool,
+}
+
+/// Endpoint generation commands talk to; the built-in local Ollama unless another is active.
+pub fn active_endpoint() -> LlmEndpoint {
+    let settings = crate::settings::get();
+    settings
+        .active_llm_endpoint
+        .and_then(|name| settings.llm_endpoints.into_iter().find(|endpoint| endpoint.name == name))
+        .unwrap_or_else(LlmEndpoint::local)
+}
+
+fn default_model_load_wait_seconds() -> u64 {
+    MODEL_LOAD_WAIT.as_secs()
 }
 
+/// Payload of every `llm-stream-event`.
+///
+/// The variant is flattened in as a `type` field, e.g.
+/// `{"schema_version": 2, "stream_id": "...", "type": "chunk", "text": "..."}`.
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct StreamEvent {
+    pub schema_version: u32,
+    pub stream_id: String,
+    #[serde(flatten)]
+    pub payload: StreamPayload,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StreamPayload {
+    Chunk { text: String },
+    /// Progress so far, sent at most once per `STREAM_METRICS_INTERVAL`.
+    Metrics { tokens: usize, elapsed_ms: u64, tokens_per_second: f64 },
+    /// Time from sending the request to the first generated token.
+    FirstToken { ms: u64 },
+    /// `fallback_reason` says why live streaming failed when `method` is `fallback`.
+    Complete {
+        stats: StreamStats,
+        method: StreamMethod,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        fallback_reason: Option<String>,
+    },
+    Error { code: String, message: String },
+    /// Stopped by `stop_llm_stream`; `partial` is the text generated so far.
+    Cancelled { partial: String },
+    /// Output is held back until `resume_llm_stream`; generation continues.
+    Paused,
+    /// Held-back output follows as a chunk.
+    Resumed,
+    /// The output ended in `text` repeated `repeats` times in a row; `cancelled` follows.
+    RepetitionDetected { text: String, repeats: usize },
+}
+
+/// Which path produced a stream's output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StreamMethod {
+    /// Tokens streamed live from the model.
+    Ollama,
+    /// A one-shot response replayed word by word after live streaming failed.
+    Fallback,
+    /// Streaming is turned off, so the whole reply arrived as one chunk.
+    OneShot,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct StreamStats {
+    pub tokens: usize,
+    pub duration_ms: u64,
+    pub tokens_per_second: Option<f64>,
+    /// Generation stopped at the output token limit.
+    pub truncated: bool,
+    /// The prompt filled the model's context window, so Ollama dropped part of it.
+    #[serde(default)]
+    pub context_truncated: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GenerationResult {
+    pub response: String,
+    pub model: String,
+    pub wall_time_ms: u64,
+    pub total_duration_ms: Option<u64>,
+    pub load_duration_ms: Option<u64>,
+    pub prompt_eval_count: Option<u32>,
+    pub eval_count: Option<u32>,
+    pub tokens_per_second: Option<f64>,
+    /// Stopped at the output token limit rather than finishing naturally.
+    pub truncated: bool,
+    /// The prompt filled the model's context window, so Ollama dropped part of it.
+    pub context_truncated: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ModelComparisonEntry {
+    pub model: String,
+    pub result: Option<GenerationResult>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ModelComparison {
+    pub prompt: String,
+    pub model_a: ModelComparisonEntry,
+    pub model_b: ModelComparisonEntry,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FallbackGeneration {
+    pub response: String,
+    pub model: String,
+    /// Model that failed before falling back, if a fallback happened.
+    pub fell_back_from: Option<String>,
+    pub original_error: Option<String>,
+    /// Which system prompt was applied; filled in by the command that resolved it.
+    #[serde(default)]
+    pub system_prompt_source: crate::settings::SystemPromptSource,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ContextFit {
+    pub fits: bool,
+    pub estimated_tokens: usize,
+    pub context_limit: u32,
+    pub overflow_by: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SystemPromptCost {
+    pub tokens: usize,
+    pub context_limit: u32,
+    pub percent_of_context: f64,
+    pub warning: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum LLMSetupStatus {
+    ServiceDown,
+    NoModels,
+    ModelMissing,
+    Healthy,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LLMSetupDiagnosis {
+    pub status: LLMSetupStatus,
+    pub model: String,
+    pub installed_models: Vec<String>,
+    /// What the user should do next, if anything.
+    pub remedy: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OllamaEndpointCheck {
+    pub base_url: String,
+    pub reachable: bool,
+    pub version: String,
+    pub model_count: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Responsiveness {
+    Fast,
+    Slow,
+    VerySlow,
+}
+
+impl Responsiveness {
+    fn from_latency_ms(ms: u64) -> Self {
+        if ms >= RESPONSIVENESS_VERY_SLOW_MS {
+            Responsiveness::VerySlow
+        } else if ms >= RESPONSIVENESS_SLOW_MS {
+            Responsiveness::Slow
+        } else {
+            Responsiveness::Fast
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OllamaResponsiveness {
+    pub class: Responsiveness,
+    pub model: String,
+    /// Probe latency with model loading time taken out; this is what `class` is based on.
+    pub latency_ms: u64,
+    /// Time Ollama spent loading the model for the probe, if it wasn't already resident.
+    pub load_ms: Option<u64>,
+    pub timed_out: bool,
+}
+
+/// How streamed tokens are batched into `chunk` events.
+///
+/// A chunk is emitted once `tokens` tokens are buffered or `interval_ms` has
+/// passed since the last emit, whichever comes first. The default emits every token.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StreamFlush {
+    pub tokens: usize,
+    pub interval_ms: Option<u64>,
+}
+
+impl Default for StreamFlush {
+    fn default() -> Self {
+        Self { tokens: 1, interval_ms: None }
+    }
+}
+
+/// How long a one-shot request and a stream may take, in seconds.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LlmTimeouts {
+    pub request_secs: u64,
+    pub stream_secs: u64,
+}
+
+impl Default for LlmTimeouts {
+    fn default() -> Self {
+        Self {
+            request_secs: REQUEST_TIMEOUT.as_secs(),
+            stream_secs: STREAM_TIMEOUT.as_secs(),
+        }
+    }
+}
+
+impl LlmTimeouts {
+    /// Timeouts suited to a runtime mode from hardware detection (cpu/hybrid/gpu).
+    pub fn for_hardware_mode(mode: &str) -> Self {
+        match mode {
+            "gpu" => Self::default(),
+            "hybrid" => Self { request_secs: 300, stream_secs: 450 },
+            _ => Self { request_secs: 600, stream_secs: 900 },
+        }
+    }
+}
+
+/// Timeouts in effect: the user's own, else the hardware profile, else the defaults.
+pub fn llm_timeouts() -> LlmTimeouts {
+    let settings = crate::settings::get();
+    settings.llm_timeouts.or(settings.hardware_timeouts).unwrap_or_default()
+}
+
+/// Live generation activity, read by the dashboard's model status.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ModelActivity {
+    pub active_streams: usize,
+    pub active_requests: usize,
+    pub last_latency_ms: Option<u64>,
+    pub last_completed_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+static MODEL_ACTIVITY: std::sync::Mutex<ModelActivity> = std::sync::Mutex::new(ModelActivity {
+    active_streams: 0,
+    active_requests: 0,
+    last_latency_ms: None,
+    last_completed_at: None,
+});
+
+pub fn model_activity() -> ModelActivity {
+    MODEL_ACTIVITY.lock().unwrap().clone()
+}
+
+// Counts a generation as active until dropped; `succeeded` records its latency
+struct ActivityGuard {
+    streaming: bool,
+    started: std::time::Instant,
+}
+
+impl ActivityGuard {
+    fn start(streaming: bool) -> Self {
+        let mut activity = MODEL_ACTIVITY.lock().unwrap();
+        if streaming {
+            activity.active_streams += 1;
+        } else {
+            activity.active_requests += 1;
+        }
+        Self { streaming, started: std::time::Instant::now() }
+    }
+
+    fn succeeded(&self) {
+        let mut activity = MODEL_ACTIVITY.lock().unwrap();
+        activity.last_latency_ms = Some(self.started.elapsed().as_millis() as u64);
+        activity.last_completed_at = Some(chrono::Utc::now());
+    }
+}
+
+impl Drop for ActivityGuard {
+    fn drop(&mut self) {
+        let mut activity = MODEL_ACTIVITY.lock().unwrap();
+        if self.streaming {
+            activity.active_streams = activity.active_streams.saturating_sub(1);
+        } else {
+            activity.active_requests = activity.active_requests.saturating_sub(1);
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ModelLoadingEvent {
+    pub model: String,
+    /// False once the model answered and generation is proceeding.
+    pub loading: bool,
+    pub waited_ms: u64,
+    pub deadline_ms: u64,
+}
+
+/// Cap `num_predict` at the configured `max_output_tokens`, if one is set.
+pub fn apply_output_limit(options: Option<GenerationOptions>) -> Option<GenerationOptions> {
+    let Some(max_tokens) = crate::settings::get().max_output_tokens else {
+        return options;
+    };
+    let max_tokens = max_tokens.min(i32::MAX as u32) as i32;
+
+    let mut options = options.unwrap_or_default();
+    // Negative values mean "unlimited" to Ollama, so they are capped too
+    options.num_predict = Some(match options.num_predict {
+        Some(requested) if (0..max_tokens).contains(&requested) => requested,
+        _ => max_tokens,
+    });
+    Some(options)
+}
+
+/// Ollama answers 503 (or names the load in the body) while a model is still being loaded.
+pub fn is_model_loading(status: u16, body: &str) -> bool {
+    let body = body.to_lowercase();
+    status == 503
+        || body.contains("loading model")
+        || body.contains("model is loading")
+        || body.contains("still loading")
+}
+
+// Backoff state for retrying a request until the model has loaded or the deadline passes
+struct ModelLoadWait<'a> {
+    model: String,
+    events: Option<&'a dyn EventSink>,
+    deadline: Duration,
+    started: std::time::Instant,
+    attempts: u32,
+}
+
+impl<'a> ModelLoadWait<'a> {
+    fn new(model: &str, events: Option<&'a dyn EventSink>, deadline: Duration) -> Self {
+        Self {
+            model: model.to_string(),
+            events,
+            deadline,
+            started: std::time::Instant::now(),
+            attempts: 0,
+        }
+    }
+
+    /// Sleep before the next retry; false once the deadline has passed.
+    async fn wait(&mut self) -> bool {
+        let waited = self.started.elapsed();
+        if waited >= self.deadline {
+            warn!("⚠️ Model {} still loading after {:?}, giving up", self.model, waited);
+            return false;
+        }
+
+        self.emit(true);
+        let backoff = Duration::from_secs(1 << self.attempts.min(3)).min(self.deadline - waited);
+        self.attempts += 1;
+        info!("⏳ Model {} is loading, retrying in {:?}", self.model, backoff);
+        tokio::time::sleep(backoff).await;
+        true
+    }
+
+    /// Tell the frontend the wait is over, if there was one.
+    fn ready(&self) {
+        if self.attempts > 0 {
+            info!("✅ Model {} finished loading after {:?}", self.model, self.started.elapsed());
+            self.emit(false);
+        }
+    }
+
+    fn emit(&self, loading: bool) {
+        let Some(events) = self.events else { return };
+        let event = ModelLoadingEvent {
+            model: self.model.clone(),
+            loading,
+            waited_ms: self.started.elapsed().as_millis() as u64,
+            deadline_ms: self.deadline.as_millis() as u64,
+        };
+        emit_json(events, "model-loading", &event);
+    }
+}
+
+/// Where LLM events go: the app's frontend, or a recorder in tests.
+pub trait EventSink: Send + Sync {
+    fn emit_event(&self, event_name: &str, payload: serde_json::Value);
+}
+
+impl EventSink for AppHandle {
+    fn emit_event(&self, event_name: &str, payload: serde_json::Value) {
+        if let Err(e) = self.emit(event_name, payload) {
+            error!("❌ Failed to emit {} event: {}", event_name, e);
+        }
+    }
+}
+
+fn emit_json<T: Serialize>(events: &dyn EventSink, event_name: &str, payload: &T) {
+    match serde_json::to_value(payload) {
+        Ok(value) => events.emit_event(event_name, value),
+        Err(e) => error!("❌ Failed to serialize {} event: {}", event_name, e),
+    }
+}
+
+/// Everything one stream needs, resolved when it starts.
+///
+/// Concurrent streams each own one of these, so changing the active model or a
+/// chat's options mid-stream never leaks into a stream that is already running.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StreamConfig {
+    pub stream_id: String,
+    pub model: String,
+    pub base_url: String,
+    pub options: Option<GenerationOptions>,
+    pub chat_id: Option<String>,
+    pub flush_every: StreamFlush,
+    pub model_load_wait_seconds: u64,
+    pub timeout_seconds: u64,
+    pub system_prompt_source: crate::settings::SystemPromptSource,
+    /// Generation halts once the output matches; the match and anything after it are dropped.
+    #[serde(skip)]
+    pub stop_regex: Option<regex::Regex>,
+    /// Cancel once a phrase repeats this many times back to back; no guard when unset.
+    pub repetition_threshold: Option<usize>,
+    pub provider: LlmProvider,
+    #[serde(skip)]
+    pub api_key: Option<String>,
+}
+
+impl StreamConfig {
+    /// Resolve a stream's model and the chat's own generation options.
+    pub fn new(stream_id: &str, model: Option<String>, chat_id: Option<String>) -> Self {
+        let defaults = LLMConfig::default();
+        let options = chat_id
+            .as_deref()
+            .and_then(|chat_id| crate::chat_store::load_session(chat_id).ok())
+            .and_then(|session| crate::chat_store::session_generation_options(&session));
+
+        Self {
+            stream_id: stream_id.to_string(),
+            model: model.unwrap_or(defaults.model),
+            base_url: defaults.base_url,
+            options,
+            chat_id,
+            flush_every: defaults.flush_every,
+            model_load_wait_seconds: defaults.model_load_wait_seconds,
+            timeout_seconds: llm_timeouts().stream_secs,
+            system_prompt_source: crate::settings::SystemPromptSource::None,
+            stop_regex: None,
+            repetition_threshold: crate::settings::get().repetition_guard_threshold,
+            provider: defaults.provider,
+            api_key: defaults.api_key,
+        }
+    }
+}
+
+/// A stream that is currently generating, as listed by `list_active_streams`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActiveStreamInfo {
+    pub stream_id: String,
+    pub model: String,
+    pub chat_id: Option<String>,
+    pub options: Option<GenerationOptions>,
+    pub started_at: chrono::DateTime<chrono::Utc>,
+    /// Tokens generated so far.
+    pub tokens: usize,
+    /// Set by `pause_llm_stream`; the stream buffers output until it is cleared.
+    pub paused: bool,
+    /// Tokens received but not yet emitted, held by flush batching or a pause.
+    pub buffered_tokens: usize,
+    pub chunks_emitted: usize,
+}
+
+/// Backpressure snapshot for one stream, from `get_stream_buffer_status`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StreamBufferStatus {
+    pub stream_id: String,
+    pub paused: bool,
+    pub buffered_tokens: usize,
+    pub tokens_received: usize,
+    pub chunks_emitted: usize,
+    pub elapsed_ms: u64,
+    pub chunks_per_second: f64,
+    pub tokens_per_second: f64,
+}
+
+static ACTIVE_STREAMS: std::sync::Mutex<Option<std::collections::HashMap<String, ActiveStreamInfo>>> =
+    std::sync::Mutex::new(None);
+
+pub fn active_streams() -> Vec<ActiveStreamInfo> {
+    let mut streams: Vec<ActiveStreamInfo> = ACTIVE_STREAMS
+        .lock()
+        .unwrap()
+        .as_ref()
+        .map(|streams| streams.values().cloned().collect())
+        .unwrap_or_default();
+    streams.sort_by_key(|stream| stream.started_at);
+    streams
+}
+
+fn new_stream_info(config: &StreamConfig) -> ActiveStreamInfo {
+    ActiveStreamInfo {
+        stream_id: config.stream_id.clone(),
+        model: config.model.clone(),
+        chat_id: config.chat_id.clone(),
+        options: config.options.clone(),
+        started_at: chrono::Utc::now(),
+        tokens: 0,
+        paused: false,
+        buffered_tokens: 0,
+        chunks_emitted: 0,
+    }
+}
+
+/// Register a stream unless `reserve_stream` already did.
+fn register_stream(config: &StreamConfig) {
+    ACTIVE_STREAMS
+        .lock()
+        .unwrap()
+        .get_or_insert_with(Default::default)
+        .entry(config.stream_id.clone())
+        .or_insert_with(|| new_stream_info(config));
+}
+
+/// Per-word delay for simulated streaming, from settings; zero sends the whole reply at once.
+pub fn fallback_word_delay_ms() -> u64 {
+    crate::settings::get()
+        .fallback_word_delay_ms
+        .unwrap_or(DEFAULT_FALLBACK_WORD_DELAY_MS)
+        .min(MAX_FALLBACK_WORD_DELAY_MS)
+}
+
+/// Emit already-generated text as chunks, one word per `fallback_word_delay_ms`.
+async fn replay_as_stream(events: &dyn EventSink, stream_id: &str, text: &str) -> StreamStats {
+    let words: Vec<&str> = text.split_whitespace().collect();
+    let delay_ms = fallback_word_delay_ms();
+    info!("🔄 Replaying {} words for {} with a {}ms word delay", words.len(), stream_id, delay_ms);
+
+    let started = std::time::Instant::now();
+    if delay_ms == 0 {
+        emit_stream_chunk(events, stream_id, text).await;
+    } else {
+        for (i, word) in words.iter().enumerate() {
+            let chunk_text = format!("{} ", word);
+            if crate::settings::verbose_llm_logging() {
+                info!("📤 Replayed chunk {}: '{}'", i + 1, chunk_text);
+            }
+            emit_stream_chunk(events, stream_id, &chunk_text).await;
+            tokio::time::sleep(Duration::from_millis(delay_ms)).await;
+        }
+    }
+
+    StreamStats {
+        tokens: words.len(),
+        duration_ms: started.elapsed().as_millis() as u64,
+        ..StreamStats::default()
+    }
+}
+
+pub fn max_concurrent_streams() -> usize {
+    crate::settings::get().max_concurrent_streams.unwrap_or(DEFAULT_MAX_CONCURRENT_STREAMS)
+}
+
+/// Register a stream before it starts, refusing once `max_concurrent_streams` are running.
+fn reserve_stream(config: &StreamConfig) -> Result<(), String> {
+    let limit = max_concurrent_streams();
+    let mut guard = ACTIVE_STREAMS.lock().unwrap();
+    let streams = guard.get_or_insert_with(Default::default);
+    if !streams.contains_key(&config.stream_id) && streams.len() >= limit {
+        let error_msg = format!("Too many active streams: {} running, limit is {}", streams.len(), limit);
+        warn!("⚠️ Rejecting stream {}: {}", config.stream_id, error_msg);
+        return Err(error_msg);
+    }
+    streams.insert(config.stream_id.clone(), new_stream_info(config));
+    Ok(())
+}
+
+fn update_stream_progress(stream_id: &str, tokens: Option<usize>, chunk_buffer: &ChunkBuffer) {
+    if let Some(stream) = ACTIVE_STREAMS.lock().unwrap().as_mut().and_then(|s| s.get_mut(stream_id)) {
+        if let Some(tokens) = tokens {
+            stream.tokens = tokens;
+        }
+        stream.buffered_tokens = chunk_buffer.pending_tokens();
+        stream.chunks_emitted = chunk_buffer.flushes();
+    }
+}
+
+fn stream_paused(stream_id: &str) -> bool {
+    ACTIVE_STREAMS
+        .lock()
+        .unwrap()
+        .as_ref()
+        .and_then(|streams| streams.get(stream_id))
+        .map_or(false, |stream| stream.paused)
+}
+
+fn set_stream_paused(stream_id: &str, paused: bool) -> Result<(), String> {
+    match ACTIVE_STREAMS.lock().unwrap().as_mut().and_then(|s| s.get_mut(stream_id)) {
+        Some(stream) => {
+            stream.paused = paused;
+            Ok(())
+        }
+        None => Err(format!("No active stream with ID {}", stream_id)),
+    }
+}
+
+fn unregister_stream(stream_id: &str) {
+    if let Some(streams) = ACTIVE_STREAMS.lock().unwrap().as_mut() {
+        streams.remove(stream_id);
+    }
+}
+
+/// A stream whose `complete` event the frontend hasn't acknowledged yet.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UnackedStream {
     pub stream_id: String,
-    pub event_type: String,
-    pub data: String,
+    pub completed_at: chrono::DateTime<chrono::Utc>,
+    pub stats: StreamStats,
+    pub method: StreamMethod,
+    pub fallback_reason: Option<String>,
+    /// The complete event has already been sent a second time.
+    pub reemitted: bool,
+}
+
+static UNACKED_STREAMS: std::sync::Mutex<Vec<UnackedStream>> = std::sync::Mutex::new(Vec::new());
+
+fn track_unacked_complete(stream_id: &str, stats: &StreamStats, method: StreamMethod, fallback_reason: Option<&str>) {
+    let mut unacked = UNACKED_STREAMS.lock().unwrap();
+    unacked.retain(|stream| stream.stream_id != stream_id);
+    unacked.push(UnackedStream {
+        stream_id: stream_id.to_string(),
+        completed_at: chrono::Utc::now(),
+        stats: stats.clone(),
+        method,
+        fallback_reason: fallback_reason.map(str::to_string),
+        reemitted: false,
+    });
+    if unacked.len() > MAX_UNACKED_STREAMS {
+        let excess = unacked.len() - MAX_UNACKED_STREAMS;
+        unacked.drain(..excess);
+    }
+}
+
+/// Send a stream's complete event once more if the frontend still hasn't acked it.
+async fn reemit_unacked_complete(events: &dyn EventSink, stream_id: &str) {
+    tokio::time::sleep(COMPLETE_ACK_TIMEOUT).await;
+    let payload = {
+        let mut unacked = UNACKED_STREAMS.lock().unwrap();
+        match unacked.iter_mut().find(|stream| stream.stream_id == stream_id && !stream.reemitted) {
+            Some(stream) => {
+                stream.reemitted = true;
+                StreamPayload::Complete {
+                    stats: stream.stats.clone(),
+                    method: stream.method,
+                    fallback_reason: stream.fallback_reason.clone(),
+                }
+            }
+            None => return,
+        }
+    };
+
+    warn!("⚠️ Complete event for {} not acknowledged after {:?}, re-emitting", stream_id, COMPLETE_ACK_TIMEOUT);
+    emit_stream_event(events, stream_id, payload);
 }
 
 impl Default for LLMConfig {
     fn default() -> Self {
+        let endpoint = active_endpoint();
         Self {
-            model: DEFAULT_MODEL.to_string(),
-            base_url: OLLAMA_BASE_URL.to_string(),
-            timeout_seconds: REQUEST_TIMEOUT.as_secs(),
+            model: endpoint.default_model.unwrap_or_else(active_model),
+            base_url: endpoint.base_url,
+            timeout_seconds: llm_timeouts().request_secs,
+            options: None,
+            flush_every: crate::settings::get().stream_flush.unwrap_or_default(),
+            model_load_wait_seconds: default_model_load_wait_seconds(),
+            format: None,
+            provider: endpoint.provider,
+            api_key: endpoint.api_key,
         }
     }
 }
@@ -67,6 +861,8 @@ pub enum LLMError {
     ServiceUnavailable(String),
     #[error("Invalid response format: {0}")]
     InvalidResponse(String),
+    #[error("Model failed to run: {0}")]
+    ModelFailure(String),
     #[error("Request timeout")]
     Timeout,
     #[error("Empty prompt provided")]
@@ -76,71 +872,204 @@ pub enum LLMError {
 pub struct LLMClient {
     client: reqwest::Client,
     config: LLMConfig,
+    events: Option<std::sync::Arc<dyn EventSink>>,
 }
 
 impl LLMClient {
-    pub fn new() -> Self {
-        let client = reqwest::Client::builder()
-            .timeout(REQUEST_TIMEOUT)
-            .build()
-            .expect("Failed to create HTTP client");
-
-        Self {
-            client,
-            config: LLMConfig::default(),
-        }
+    pub fn new() -> Result<Self, String> {
+        Self::with_config(LLMConfig::default())
     }
 
-    pub fn with_config(config: LLMConfig) -> Self {
+    /// Fails when no HTTP client can be built for the endpoint, e.g. offline mode
+    /// blocking a remote one.
+    pub fn with_config(config: LLMConfig) -> Result<Self, String> {
         let timeout = Duration::from_secs(config.timeout_seconds);
-        let client = reqwest::Client::builder()
-            .timeout(timeout)
-            .build()
-            .expect("Failed to create HTTP client");
+        let client = crate::network::build_http_client(&config.base_url, "llm", Some(timeout))?;
+
+        Ok(Self { client, config, events: None })
+    }
+
+    /// Report `model-loading` events to the frontend while waiting on a cold model.
+    pub fn with_app_handle(mut self, app_handle: AppHandle) -> Self {
+        self.events = Some(std::sync::Arc::new(app_handle));
+        self
+    }
+
+    fn generation_request(&self, request: &OllamaRequest) -> reqwest::RequestBuilder {
+        endpoint_request(&self.client, self.config.provider, &self.config.base_url, self.config.api_key.as_deref(), request)
+    }
 
-        Self { client, config }
+    fn model_load_wait(&self) -> ModelLoadWait<'_> {
+        ModelLoadWait::new(
+            &self.config.model,
+            self.events.as_deref(),
+            Duration::from_secs(self.config.model_load_wait_seconds),
+        )
     }
 
     pub async fn generate_response(&self, prompt: String) -> Result<String, LLMError> {
+        let ollama_response = self.request_generation(prompt).await?;
+        // Return only the response text, not the entire JSON structure
+        Ok(ollama_response.response.trim().to_string())
+    }
+
+    /// Generate a response and keep Ollama's timing and token counters.
+    pub async fn generate_response_with_stats(&self, prompt: String) -> Result<GenerationResult, LLMError> {
+        let started = std::time::Instant::now();
+        let ollama_response = self.request_generation(prompt).await?;
+        let wall_time_ms = started.elapsed().as_millis() as u64;
+
+        let tokens_per_second = match (ollama_response.eval_count, ollama_response.eval_duration) {
+            (Some(count), Some(duration)) if duration > 0 => Some(count as f64 / (duration as f64 / 1e9)),
+            _ => None,
+        };
+
+        let context_truncated = self.config.provider == LlmProvider::Ollama
+            && prompt_hit_context_limit(&ollama_response.model, ollama_response.prompt_eval_count).await;
+        Ok(GenerationResult {
+            context_truncated,
+            response: ollama_response.response.trim().to_string(),
+            model: ollama_response.model,
+            wall_time_ms,
+            total_duration_ms: ollama_response.total_duration.map(|ns| ns / 1_000_000),
+            load_duration_ms: ollama_response.load_duration.map(|ns| ns / 1_000_000),
+            prompt_eval_count: ollama_response.prompt_eval_count,
+            eval_count: ollama_response.eval_count,
+            tokens_per_second,
+            truncated: ollama_response.done_reason.as_deref() == Some("length"),
+        })
+    }
+
+    /// Generate, retrying once with the next-smaller recommended model if the
+    /// configured one fails on the server side (typically out of memory).
+    pub async fn generate_with_model_fallback(&self, prompt: String) -> Result<FallbackGeneration, LLMError> {
+        let original_error = match self.generate_response(prompt.clone()).await {
+            Ok(response) => {
+                return Ok(FallbackGeneration {
+                    response,
+                    model: self.config.model.clone(),
+                    fell_back_from: None,
+                    original_error: None,
+                    system_prompt_source: Default::default(),
+                });
+            }
+            Err(LLMError::ModelFailure(message)) => message,
+            Err(e) => return Err(e),
+        };
+
+        let installed = self.installed_models().await;
+        let Some(fallback_model) = crate::commands::next_smaller_model(&self.config.model, &installed) else {
+            return Err(LLMError::ModelFailure(original_error));
+        };
+
+        warn!("⚠️ Model {} failed ({}), retrying with smaller model {}", self.config.model, original_error, fallback_model);
+
+        let fallback_client = LLMClient::with_config(LLMConfig {
+            model: fallback_model.clone(),
+            base_url: self.config.base_url.clone(),
+            timeout_seconds: self.config.timeout_seconds,
+            options: self.config.options.clone(),
+            flush_every: self.config.flush_every.clone(),
+            model_load_wait_seconds: self.config.model_load_wait_seconds,
+            format: self.config.format.clone(),
+            provider: self.config.provider,
+            api_key: self.config.api_key.clone(),
+        })
+        .map_err(LLMError::ServiceUnavailable)?;
+
+        match fallback_client.generate_response(prompt).await {
+            Ok(response) => Ok(FallbackGeneration {
+                response,
+                model: fallback_model,
+                fell_back_from: Some(self.config.model.clone()),
+                original_error: Some(original_error),
+                system_prompt_source: Default::default(),
+            }),
+            Err(fallback_error) => Err(LLMError::ModelFailure(format!(
+                "{} (fallback to {} also failed: {})",
+                original_error, fallback_model, fallback_error
+            ))),
+        }
+    }
+
+    /// Installed models with their on-disk sizes; empty when Ollama can't be asked.
+    async fn installed_models(&self) -> Vec<crate::python_backend::OllamaModel> {
+        let tags_url = format!("{}/api/tags", self.config.base_url);
+        let response = match self.client.get(&tags_url).timeout(Duration::from_secs(5)).send().await {
+            Ok(response) if response.status().is_success() => response,
+            Ok(response) => {
+                warn!("⚠️ Ollama tags request returned status: {}", response.status());
+                return Vec::new();
+            }
+            Err(e) => {
+                warn!("⚠️ Cannot list installed models: {}", e);
+                return Vec::new();
+            }
+        };
+        response
+            .json::<crate::python_backend::OllamaModelsResponse>()
+            .await
+            .map(|tags| tags.models)
+            .unwrap_or_default()
+    }
+
+    async fn request_generation(&self, prompt: String) -> Result<OllamaResponse, LLMError> {
         if prompt.trim().is_empty() {
             return Err(LLMError::EmptyPrompt);
         }
 
         info!("Generating LLM response for prompt length: {}", prompt.len());
+        let activity = ActivityGuard::start(false);
 
         let request = OllamaRequest {
             model: self.config.model.clone(),
-            prompt: prompt.clone(),
+            prompt: sanitize_prompt(&prompt),
             stream: false,
+            options: apply_output_limit(self.config.options.clone()),
+            format: self.config.format.clone(),
         };
 
-        let url = format!("{}/api/generate", self.config.base_url);
+        let url = generation_url(self.config.provider, &self.config.base_url);
         
         info!("Sending request to Ollama at: {}", url);
 
-        let response = self
-            .client
-            .post(&url)
-            .json(&request)
-            .send()
-            .await
-            .map_err(|e| {
-                error!("Failed to send request to Ollama: {}", e);
-                if e.is_timeout() {
-                    LLMError::Timeout
-                } else if e.is_connect() {
-                    LLMError::ServiceUnavailable(format!(
-                        "Cannot connect to Ollama service. Please ensure Ollama is running and the Gemma 3n model is available."
-                    ))
-                } else {
-                    LLMError::Network(e)
-                }
-            })?;
+        let mut load_wait = self.model_load_wait();
+        let response = loop {
+            let response = self
+                .generation_request(&request)
+                .send()
+                .await
+                .map_err(|e| {
+                    error!("Failed to send request to Ollama: {}", e);
+                    if e.is_timeout() {
+                        LLMError::Timeout
+                    } else if e.is_connect() {
+                        LLMError::ServiceUnavailable(format!(
+                            "Cannot connect to Ollama service. Please ensure Ollama is running and the Gemma 3n model is available."
+                        ))
+                    } else {
+                        LLMError::Network(e)
+                    }
+                })?;
+
+            if response.status().is_success() {
+                load_wait.ready();
+                break response;
+            }
 
-        if !response.status().is_success() {
             let status = response.status();
+            let content_type = response_content_type(&response);
             let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+            if is_model_loading(status.as_u16(), &error_text) && load_wait.wait().await {
+                continue;
+            }
             error!("Ollama API error {}: {}", status, error_text);
+            // Ollama's own plain-text 404 keeps the more specific hints below
+            if status.as_u16() != 404 {
+                if let Some(message) = non_json_response_error(&url, content_type.as_deref(), &error_text) {
+                    return Err(LLMError::InvalidResponse(format!("HTTP {}: {}", status, message)));
+                }
+            }
 
             // Provide specific error messages for common issues
             let error_message = if status.as_u16() == 404 {
@@ -150,26 +1079,42 @@ impl LLMClient {
                     format!("Ollama API endpoint not found. Please ensure Ollama is running on localhost:11434")
                 }
             } else if status.as_u16() == 500 {
-                format!("Ollama service error. The model may be loading or encountering issues.")
+                // Usually the model failed to load or ran out of memory
+                return Err(LLMError::ModelFailure(format!(
+                    "Ollama service error. The model may be loading or encountering issues: {}",
+                    error_text
+                )));
             } else {
                 format!("Ollama API returned error {}: {}", status, error_text)
             };
 
             return Err(LLMError::ServiceUnavailable(error_message));
-        }
+        };
 
         // Log response text for debugging
+        let content_type = response_content_type(&response);
         let response_text = response.text().await.map_err(|e| {
             error!("Failed to get response text: {}", e);
             LLMError::Network(e)
         })?;
         
-        info!("Raw Ollama response: {}", response_text);
+        if crate::settings::verbose_llm_logging() {
+            info!("Raw Ollama response: {}", response_text);
+        } else {
+            info!("Ollama response received ({} chars)", response_text.len());
+        }
         
-        let ollama_response: OllamaResponse = serde_json::from_str(&response_text)
+        if let Some(message) = non_json_response_error(&url, content_type.as_deref(), &response_text) {
+            error!("❌ {}", message);
+            return Err(LLMError::InvalidResponse(message));
+        }
+
+        let ollama_response = parse_generation_response(&response_text)
             .map_err(|e| {
                 error!("Failed to parse Ollama response JSON: {}", e);
-                error!("Response text was: {}", response_text);
+                if crate::settings::verbose_llm_logging() {
+                    error!("Response text was: {}", response_text);
+                }
                 LLMError::InvalidResponse(format!(
                     "Empty or malformed response from Ollama API"
                 ))
@@ -183,8 +1128,8 @@ impl LLMClient {
         }
 
         info!("Successfully generated LLM response (length: {} chars)", ollama_response.response.len());
-        // Return only the response text, not the entire JSON structure
-        Ok(ollama_response.response.trim().to_string())
+        activity.succeeded();
+        Ok(ollama_response)
     }
 
     // Generate response with better error handling for large responses
@@ -194,6 +1139,7 @@ impl LLMClient {
         }
 
         info!("🚀 Generating robust LLM response for prompt length: {}", prompt.len());
+        let activity = ActivityGuard::start(false);
 
         // For very long prompts, we might want to chunk them
         if prompt.len() > 8000 {
@@ -202,25 +1148,26 @@ impl LLMClient {
 
         let request = OllamaRequest {
             model: self.config.model.clone(),
-            prompt: prompt.clone(),
+            prompt: sanitize_prompt(&prompt),
             stream: false,
+            options: apply_output_limit(self.config.options.clone()),
+            format: self.config.format.clone(),
         };
 
-        let url = format!("{}/api/generate", self.config.base_url);
+        let url = generation_url(self.config.provider, &self.config.base_url);
 
         info!("📡 Sending robust request to Ollama at: {}", url);
 
         // Add retry logic for large responses
         let mut attempts = 0;
         const MAX_ATTEMPTS: u32 = 3;
+        let mut load_wait = self.model_load_wait();
 
         while attempts < MAX_ATTEMPTS {
             attempts += 1;
             info!("🔄 Attempt {} of {}", attempts, MAX_ATTEMPTS);
 
-            match self.client
-                .post(&url)
-                .json(&request)
+            match self.generation_request(&request)
                 .send()
                 .await
             {
@@ -228,6 +1175,12 @@ impl LLMClient {
                     if !response.status().is_success() {
                         let status = response.status();
                         let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+
+                        // A cold model isn't a failed attempt; wait for it separately
+                        if is_model_loading(status.as_u16(), &error_text) && load_wait.wait().await {
+                            attempts -= 1;
+                            continue;
+                        }
                         error!("❌ Ollama API error {}: {}", status, error_text);
 
                         if attempts >= MAX_ATTEMPTS {
@@ -243,6 +1196,7 @@ impl LLMClient {
                     }
 
                     // Process successful response
+                    load_wait.ready();
                     let response_text = response.text().await.map_err(|e| {
                         error!("❌ Failed to get response text: {}", e);
                         LLMError::Network(e)
@@ -250,16 +1204,18 @@ impl LLMClient {
 
                     info!("📦 Raw Ollama response length: {} chars", response_text.len());
 
-                    let ollama_response: OllamaResponse = serde_json::from_str(&response_text)
+                    let ollama_response = parse_generation_response(&response_text)
                         .map_err(|e| {
                             error!("❌ Failed to parse Ollama response JSON: {}", e);
-                            error!("Response text preview: {}",
-                                if response_text.len() > 200 {
-                                    format!("{}...", &response_text[..200])
-                                } else {
-                                    response_text.clone()
-                                }
-                            );
+                            if crate::settings::verbose_llm_logging() {
+                                error!("Response text preview: {}",
+                                    if response_text.len() > 200 {
+                                        format!("{}...", &response_text[..200])
+                                    } else {
+                                        response_text.clone()
+                                    }
+                                );
+                            }
                             LLMError::InvalidResponse(format!(
                                 "Failed to parse JSON response from Ollama API"
                             ))
@@ -277,6 +1233,7 @@ impl LLMClient {
                     }
 
                     info!("✅ Successfully generated robust LLM response (length: {} chars)", ollama_response.response.len());
+                    activity.succeeded();
                     // Return only the response text, not the entire JSON structure
                     return Ok(ollama_response.response.trim().to_string());
                 }
@@ -347,43 +1304,718 @@ impl LLMClient {
             }
         }
     }
+
+    /// Work out why the LLM isn't usable, distinguishing a fresh install from an outage.
+    pub async fn diagnose_setup(&self) -> LLMSetupDiagnosis {
+        let model = self.config.model.clone();
+        let tags_url = format!("{}/api/tags", self.config.base_url);
+
+        let tags = match self.client.get(&tags_url).timeout(Duration::from_secs(5)).send().await {
+            Ok(response) if response.status().is_success() => response.json::<serde_json::Value>().await.ok(),
+            Ok(response) => {
+                warn!("⚠️ Ollama tags request returned status: {}", response.status());
+                None
+            }
+            Err(e) => {
+                warn!("⚠️ Cannot reach Ollama: {}", e);
+                None
+            }
+        };
+
+        let Some(tags) = tags else {
+            return LLMSetupDiagnosis {
+                status: LLMSetupStatus::ServiceDown,
+                model,
+                installed_models: Vec::new(),
+                remedy: Some(format!("Start Ollama (run: ollama serve) and make sure it is listening on {}", self.config.base_url)),
+            };
+        };
+
+        let installed_models: Vec<String> = tags["models"]
+            .as_array()
+            .map(|models| {
+                models
+                    .iter()
+                    .filter_map(|m| m["name"].as_str().map(|name| name.to_string()))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let (status, remedy) = if installed_models.is_empty() {
+            (LLMSetupStatus::NoModels, Some(format!("No models installed yet. Run: ollama pull {}", model)))
+        } else if !installed_models.iter().any(|name| name == &model) {
+            (LLMSetupStatus::ModelMissing, Some(format!("Model {} is not installed. Run: ollama pull {}", model, model)))
+        } else {
+            (LLMSetupStatus::Healthy, None)
+        };
+
+        LLMSetupDiagnosis {
+            status,
+            model,
+            installed_models,
+            remedy,
+        }
+    }
+}
+
+/// Model to use when a command doesn't specify one: the user's active model or the built-in default.
+pub fn active_model() -> String {
+    crate::settings::get()
+        .active_model
+        .unwrap_or_else(|| DEFAULT_MODEL.to_string())
+}
+
+/// Rough token estimate (~1.3 tokens per word), matching the Python backend's fallback ratio.
+pub fn estimate_tokens(text: &str) -> usize {
+    let words = text.split_whitespace().count();
+    (words as f64 * 1.3).ceil() as usize
+}
+
+/// Split text on word boundaries into chunks of at most `max_tokens` estimated tokens.
+pub fn chunk_by_tokens(text: &str, max_tokens: usize) -> Vec<String> {
+    // Invert estimate_tokens so each chunk stays under the budget
+    let words_per_chunk = ((max_tokens as f64 / 1.3).floor() as usize).max(1);
+    let words: Vec<&str> = text.split_whitespace().collect();
+
+    words
+        .chunks(words_per_chunk)
+        .map(|chunk| chunk.join(" "))
+        .collect()
+}
+
+/// Prefix a prompt with a system prompt using the format the streaming path expects.
+pub fn combine_with_system_prompt(system_prompt: Option<&str>, prompt: String) -> String {
+    match system_prompt {
+        Some(sys_prompt) => format!("{}\n\nUser: {}", sys_prompt, prompt),
+        None => prompt,
+    }
+}
+
+/// Drop characters that confuse the model or break serialization: control
+/// characters other than tab/newline/carriage return, zero-width spaces, byte
+/// order marks and bidi overrides. Unicode line/paragraph separators become newlines.
+///
+/// Zero-width joiners are kept since emoji sequences and some scripts rely on them.
+pub fn sanitize_prompt(text: &str) -> String {
+    let mut removed = 0usize;
+    let sanitized: String = text
+        .chars()
+        .filter_map(|c| match c {
+            '\t' | '\n' | '\r' => Some(c),
+            '\u{2028}' | '\u{2029}' => Some('\n'),
+            '\u{200B}' | '\u{2060}' | '\u{FEFF}' | '\u{202A}'..='\u{202E}' | '\u{2066}'..='\u{2069}' => {
+                removed += 1;
+                None
+            }
+            c if c.is_control() => {
+                removed += 1;
+                None
+            }
+            c => Some(c),
+        })
+        .collect();
+
+    if removed > 0 {
+        warn!("🧹 Removed {} control or invisible character(s) from the prompt", removed);
+    }
+    sanitized
+}
+
+// Show what a pasted prompt will look like once sanitized for generation
+#[tauri::command]
+pub async fn sanitize_prompt_text(text: String) -> Result<String, String> {
+    Ok(sanitize_prompt(&text))
+}
+
+#[tauri::command]
+pub async fn generate_llm_response(app_handle: AppHandle, prompt: String, chat_id: Option<String>) -> Result<FallbackGeneration, String> {
+    info!("🚀 Received LLM request with prompt length: {}", prompt.len());
+
+    // Apply the chat's own sampling parameters when generating for a chat
+    let options = match &chat_id {
+        Some(chat_id) => crate::chat_store::load_session(chat_id)
+            .ok()
+            .and_then(|session| crate::chat_store::session_generation_options(&session)),
+        None => None,
+    };
+    let client = LLMClient::with_config(LLMConfig {
+        options,
+        ..LLMConfig::default()
+    })?
+    .with_app_handle(app_handle);
+    let system_prompt = crate::settings::resolve_system_prompt(chat_id.as_deref(), None);
+    let prompt = combine_with_system_prompt(system_prompt.prompt.as_deref(), prompt);
+
+    if crate::settings::get().fallback_to_smaller_model {
+        return match client.generate_with_model_fallback(prompt).await {
+            Ok(result) => {
+                if let Some(original) = &result.fell_back_from {
+                    warn!("⚠️ Response generated by fallback model {} after {} failed", result.model, original);
+                }
+                Ok(FallbackGeneration { system_prompt_source: system_prompt.source, ..result })
+            }
+            Err(e) => {
+                error!("❌ LLM generation failed: {}", e);
+                Err(e.to_string())
+            }
+        };
+    }
+
+    // Use robust method for better handling of large responses
+    match client.generate_response_robust(prompt).await {
+        Ok(response) => {
+            info!("✅ LLM response generated successfully (length: {} chars)", response.len());
+            Ok(FallbackGeneration {
+                response,
+                model: client.config.model,
+                fell_back_from: None,
+                original_error: None,
+                system_prompt_source: system_prompt.source,
+            })
+        }
+        Err(e) => {
+            error!("❌ LLM generation failed: {}", e);
+            Err(e.to_string())
+        }
+    }
+}
+
+// One-shot generation that falls back to a smaller model when the requested one fails to run
+#[tauri::command]
+pub async fn generate_with_model_fallback(prompt: String, model: Option<String>) -> Result<FallbackGeneration, String> {
+    let client = LLMClient::with_config(LLMConfig {
+        model: model.unwrap_or_else(active_model),
+        ..LLMConfig::default()
+    })?;
+    let system_prompt = crate::settings::resolve_system_prompt(None, None);
+    let prompt = combine_with_system_prompt(system_prompt.prompt.as_deref(), prompt);
+
+    let result = client.generate_with_model_fallback(prompt).await.map_err(|e| {
+        error!("❌ Generation with fallback failed: {}", e);
+        e.to_string()
+    })?;
+    Ok(FallbackGeneration { system_prompt_source: system_prompt.source, ..result })
+}
+
+// Run the same prompt against two models concurrently for side-by-side evaluation
+#[tauri::command]
+pub async fn compare_models(prompt: String, model_a: String, model_b: String) -> Result<ModelComparison, String> {
+    info!("⚖️ Comparing models '{}' and '{}' (prompt length: {})", model_a, model_b, prompt.len());
+
+    if prompt.trim().is_empty() {
+        return Err(LLMError::EmptyPrompt.to_string());
+    }
+
+    let client_for = |model: &str| {
+        LLMClient::with_config(LLMConfig {
+            model: model.to_string(),
+            ..LLMConfig::default()
+        })
+    };
+    let client_a = client_for(&model_a)?;
+    let client_b = client_for(&model_b)?;
+
+    let (result_a, result_b) = tokio::join!(
+        client_a.generate_response_with_stats(prompt.clone()),
+        client_b.generate_response_with_stats(prompt.clone())
+    );
+
+    // A failure in one model must not hide the other's answer
+    let to_entry = |model: String, result: Result<GenerationResult, LLMError>| match result {
+        Ok(result) => ModelComparisonEntry { model, result: Some(result), error: None },
+        Err(e) => {
+            warn!("⚠️ Model '{}' failed during comparison: {}", model, e);
+            ModelComparisonEntry { model, result: None, error: Some(e.to_string()) }
+        }
+    };
+
+    Ok(ModelComparison {
+        prompt,
+        model_a: to_entry(model_a, result_a),
+        model_b: to_entry(model_b, result_b),
+    })
+}
+
+/// Whether Ollama evaluated a full context window of prompt, meaning it silently
+/// dropped the start of a prompt that didn't fit.
+async fn prompt_hit_context_limit(model: &str, prompt_eval_count: Option<u32>) -> bool {
+    let Some(evaluated) = prompt_eval_count else {
+        return false;
+    };
+    let context_length = detect_context_length(model).await;
+    let hit = evaluated + context_length * CONTEXT_CEILING_SLACK_PERCENT / 100 >= context_length;
+    if hit {
+        warn!(
+            "⚠️ Prompt for {} filled the {}-token context window ({} tokens evaluated); earlier context was cut off",
+            model, context_length, evaluated
+        );
+    }
+    hit
+}
+
+/// Context window for a model, from `num_ctx` or the model's metadata via /api/show.
+pub async fn detect_context_length(model: &str) -> u32 {
+    let base_url = active_endpoint().base_url;
+    let Ok(client) = crate::network::build_http_client(&base_url, "llm", None) else {
+        return DEFAULT_CONTEXT_LENGTH;
+    };
+    let response = client
+        .post(&format!("{}/api/show", base_url))
+        .json(&serde_json::json!({ "model": model }))
+        .timeout(Duration::from_secs(10))
+        .send()
+        .await;
+
+    let info: serde_json::Value = match response {
+        Ok(response) if response.status().is_success() => match response.json().await {
+            Ok(info) => info,
+            Err(e) => {
+                warn!("⚠️ Failed to parse /api/show response for {}: {}", model, e);
+                return DEFAULT_CONTEXT_LENGTH;
+            }
+        },
+        Ok(response) => {
+            warn!("⚠️ /api/show returned {} for {}", response.status(), model);
+            return DEFAULT_CONTEXT_LENGTH;
+        }
+        Err(e) => {
+            warn!("⚠️ Could not query model info for {}: {}", model, e);
+            return DEFAULT_CONTEXT_LENGTH;
+        }
+    };
+
+    // An explicit num_ctx in the Modelfile is what Ollama actually uses
+    let num_ctx = info["parameters"].as_str().and_then(|params| {
+        params.lines().find_map(|line| {
+            let mut parts = line.split_whitespace();
+            match (parts.next(), parts.next()) {
+                (Some("num_ctx"), Some(value)) => value.parse::<u32>().ok(),
+                _ => None,
+            }
+        })
+    });
+
+    let trained_ctx = info["model_info"].as_object().and_then(|model_info| {
+        model_info
+            .iter()
+            .find(|(key, _)| key.ends_with(".context_length"))
+            .and_then(|(_, value)| value.as_u64())
+            .map(|value| value as u32)
+    });
+
+    num_ctx.or(trained_ctx).unwrap_or(DEFAULT_CONTEXT_LENGTH)
+}
+
+// How much of the model's context window a system prompt uses up on every turn
+#[tauri::command]
+pub async fn estimate_system_prompt_cost(prompt: String, model: Option<String>) -> Result<SystemPromptCost, String> {
+    let model = model.unwrap_or_else(active_model);
+    let tokens = estimate_tokens(&prompt);
+    let context_limit = detect_context_length(&model).await;
+    let percent_of_context = tokens as f64 / context_limit as f64 * 100.0;
+
+    let warning = if percent_of_context > SYSTEM_PROMPT_WARN_PERCENT {
+        warn!("⚠️ System prompt uses {:.1}% of {}'s context", percent_of_context, model);
+        Some(format!(
+            "This system prompt uses {:.0}% of the {}-token context, leaving less room for the conversation",
+            percent_of_context, context_limit
+        ))
+    } else {
+        None
+    };
+
+    Ok(SystemPromptCost {
+        tokens,
+        context_limit,
+        percent_of_context,
+        warning,
+    })
+}
+
+// Preflight check that the chat history plus a new prompt fits the model window
+#[tauri::command]
+pub async fn check_context_fits(chat_id: Option<String>, prompt: String, model: Option<String>) -> Result<ContextFit, String> {
+    let model = model.unwrap_or_else(active_model);
+    info!("📏 Checking context fit for model {} (prompt length: {})", model, prompt.len());
+
+    let history_tokens = match chat_id.as_deref() {
+        Some(chat_id) => {
+            let session = crate::chat_store::load_session(chat_id)?;
+            estimate_tokens(&crate::chat_store::build_conversation_prompt(&session.messages))
+        }
+        None => 0,
+    };
+    let system_tokens = crate::settings::resolve_system_prompt(chat_id.as_deref(), None)
+        .prompt
+        .map_or(0, |system_prompt| estimate_tokens(&system_prompt));
+
+    let estimated_tokens = history_tokens + system_tokens + estimate_tokens(&prompt);
+    let context_limit = detect_context_length(&model).await;
+    let overflow_by = estimated_tokens.saturating_sub(context_limit as usize);
+
+    Ok(ContextFit {
+        fits: overflow_by == 0,
+        estimated_tokens,
+        context_limit,
+        overflow_by,
+    })
+}
+
+#[tauri::command]
+pub async fn check_llm_health() -> Result<bool, String> {
+    info!("Checking LLM service health");
+
+    let client = LLMClient::new()?;
+    
+    match client.check_health().await {
+        Ok(is_healthy) => {
+            info!("LLM health check result: {}", is_healthy);
+            Ok(is_healthy)
+        }
+        Err(e) => {
+            error!("LLM health check failed: {}", e);
+            Err(e.to_string())
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn get_active_model() -> Result<String, String> {
+    Ok(active_model())
+}
+
+// Make `name` the default model for all generation commands
+#[tauri::command]
+pub async fn set_active_model(name: String) -> Result<(), String> {
+    let name = name.trim().to_string();
+    info!("🤖 Setting active model to {}", name);
+
+    let diagnosis = LLMClient::new()?.diagnose_setup().await;
+    if diagnosis.status == LLMSetupStatus::ServiceDown {
+        return Err("Cannot validate the model: Ollama is not reachable".to_string());
+    }
+    if !diagnosis.installed_models.iter().any(|installed| installed == &name) {
+        return Err(format!("Model {} is not installed. Run: ollama pull {}", name, name));
+    }
+
+    crate::settings::update(|settings| settings.active_model = Some(name))?;
+    Ok(())
+}
+
+// Check that a (possibly remote) Ollama URL works before the user saves it
+#[tauri::command]
+pub async fn test_ollama_endpoint(base_url: String) -> Result<OllamaEndpointCheck, String> {
+    let base_url = base_url.trim().trim_end_matches('/').to_string();
+    info!("🔌 Testing Ollama endpoint: {}", base_url);
+
+    let parsed = url::Url::parse(&base_url).map_err(|e| format!("Invalid URL: {}", e))?;
+    if parsed.scheme() != "http" && parsed.scheme() != "https" {
+        return Err("Endpoint must use http or https".to_string());
+    }
+
+    let client = crate::network::build_http_client(&base_url, "ollama endpoint test", Some(Duration::from_secs(10)))?;
+
+    let describe = |e: reqwest::Error| {
+        if e.is_timeout() {
+            format!("Timed out connecting to {}", base_url)
+        } else if e.is_connect() {
+            format!("Could not connect to {}: is Ollama running and reachable?", base_url)
+        } else {
+            format!("Request to {} failed: {}", base_url, e)
+        }
+    };
+
+    let version_response = client
+        .get(&format!("{}/api/version", base_url))
+        .send()
+        .await
+        .map_err(describe)?;
+    if !version_response.status().is_success() {
+        return Err(format!("{} answered /api/version with status {}; is this an Ollama server?", base_url, version_response.status()));
+    }
+    let version = version_response
+        .json::<serde_json::Value>()
+        .await
+        .ok()
+        .and_then(|v| v["version"].as_str().map(|v| v.to_string()))
+        .ok_or_else(|| format!("{} did not return an Ollama version; is this an Ollama server?", base_url))?;
+
+    let tags_response = client
+        .get(&format!("{}/api/tags", base_url))
+        .send()
+        .await
+        .map_err(describe)?;
+    if !tags_response.status().is_success() {
+        return Err(format!("{} answered /api/tags with status {}", base_url, tags_response.status()));
+    }
+    let tags: serde_json::Value = tags_response
+        .json()
+        .await
+        .map_err(|e| format!("Invalid model list from {}: {}", base_url, e))?;
+    let model_count = tags["models"].as_array().map_or(0, |models| models.len());
+
+    info!("✅ Ollama {} reachable at {} with {} models", version, base_url, model_count);
+    Ok(OllamaEndpointCheck {
+        base_url,
+        reachable: true,
+        version,
+        model_count,
+    })
+}
+
+// Time a one-token generation so the status bar can tell "up but crawling" from "up"
+#[tauri::command]
+pub async fn measure_ollama_responsiveness(model: Option<String>) -> Result<OllamaResponsiveness, String> {
+    let defaults = LLMConfig::default();
+    let model = model.unwrap_or(defaults.model.clone());
+    info!("⏱️ Measuring Ollama responsiveness with {}", model);
+
+    let client = LLMClient::with_config(LLMConfig {
+        model: model.clone(),
+        timeout_seconds: RESPONSIVENESS_PROBE_TIMEOUT.as_secs(),
+        options: Some(GenerationOptions { num_predict: Some(1), ..Default::default() }),
+        ..defaults
+    })?;
+
+    let (latency_ms, load_ms, timed_out) = match client.generate_response_with_stats("Hi".to_string()).await {
+        Ok(result) => {
+            let load_ms = result.load_duration_ms.filter(|ms| *ms > 0);
+            (result.wall_time_ms.saturating_sub(load_ms.unwrap_or(0)), load_ms, false)
+        }
+        Err(LLMError::Timeout) => (RESPONSIVENESS_PROBE_TIMEOUT.as_millis() as u64, None, true),
+        Err(e) => return Err(e.to_string()),
+    };
+
+    let class = Responsiveness::from_latency_ms(latency_ms);
+    if class == Responsiveness::Fast {
+        info!("✅ Ollama responded in {}ms", latency_ms);
+    } else {
+        warn!("⚠️ Ollama is {:?}: probe took {}ms{}", class, latency_ms, if timed_out { " (timed out)" } else { "" });
+    }
+    Ok(OllamaResponsiveness { class, model, latency_ms, load_ms, timed_out })
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SchemaGeneration {
+    pub value: serde_json::Value,
+    pub attempts: u32,
+}
+
+// Generate JSON that validates against `json_schema`, feeding violations back to the model on retry
+#[tauri::command]
+pub async fn generate_with_schema(
+    prompt: String,
+    json_schema: serde_json::Value,
+    model: Option<String>,
+    max_attempts: Option<u32>,
+) -> Result<SchemaGeneration, String> {
+    let validator = jsonschema::validator_for(&json_schema).map_err(|e| format!("Invalid JSON schema: {}", e))?;
+    let schema_text = serde_json::to_string_pretty(&json_schema).map_err(|e| e.to_string())?;
+    let max_attempts = max_attempts.unwrap_or(SCHEMA_MAX_ATTEMPTS).max(1);
+
+    let defaults = LLMConfig::default();
+    let client = LLMClient::with_config(LLMConfig {
+        model: model.unwrap_or(defaults.model.clone()),
+        format: Some(serde_json::json!("json")),
+        ..defaults
+    })?;
+
+    let base_prompt = format!(
+        "{}\n\nRespond only with JSON that conforms to this JSON Schema:\n{}",
+        prompt, schema_text
+    );
+    let mut violations: Vec<String> = Vec::new();
+    for attempt in 1..=max_attempts {
+        let attempt_prompt = if violations.is_empty() {
+            base_prompt.clone()
+        } else {
+            format!(
+                "{}\n\nYour previous answer did not match the schema:\n- {}\nReturn corrected JSON.",
+                base_prompt,
+                violations.join("\n- ")
+            )
+        };
+
+        let response = client.generate_response(attempt_prompt).await.map_err(|e| e.to_string())?;
+        violations = match serde_json::from_str::<serde_json::Value>(&response) {
+            Ok(value) => {
+                let errors: Vec<String> = validator
+                    .iter_errors(&value)
+                    .map(|e| match e.instance_path().as_str() {
+                        "" => e.to_string(),
+                        path => format!("{}: {}", path, e),
+                    })
+                    .collect();
+                if errors.is_empty() {
+                    info!("✅ Schema-valid JSON generated on attempt {}", attempt);
+                    return Ok(SchemaGeneration { value, attempts: attempt });
+                }
+                errors
+            }
+            Err(e) => vec![format!("Response was not valid JSON: {}", e)],
+        };
+        warn!("⚠️ Attempt {}/{} violated the schema: {:?}", attempt, max_attempts, violations);
+    }
+
+    Err(format!(
+        "No schema-valid response after {} attempts. Violations: {}",
+        max_attempts,
+        violations.join("; ")
+    ))
+}
+
+// Add (or replace) a named LLM endpoint; it is not used until made active
+#[tauri::command]
+pub async fn add_llm_endpoint(
+    name: String,
+    provider: LlmProvider,
+    base_url: String,
+    api_key: Option<String>,
+    default_model: Option<String>,
+) -> Result<(), String> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err("Endpoint name cannot be empty".to_string());
+    }
+    if name == LOCAL_ENDPOINT_NAME {
+        return Err(format!("{} is the built-in local Ollama endpoint", LOCAL_ENDPOINT_NAME));
+    }
+    let base_url = base_url.trim().trim_end_matches('/').to_string();
+    let parsed = url::Url::parse(&base_url).map_err(|e| format!("Invalid URL: {}", e))?;
+    if parsed.scheme() != "http" && parsed.scheme() != "https" {
+        return Err("Endpoint must use http or https".to_string());
+    }
+
+    info!("🔌 Adding {:?} LLM endpoint {} at {}", provider, name, base_url);
+    let endpoint = LlmEndpoint {
+        name,
+        provider,
+        base_url,
+        api_key: api_key.filter(|key| !key.trim().is_empty()),
+        default_model: default_model.filter(|model| !model.trim().is_empty()),
+    };
+    crate::settings::update(move |settings| {
+        settings.llm_endpoints.retain(|existing| existing.name != endpoint.name);
+        settings.llm_endpoints.push(endpoint);
+    })?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn list_llm_endpoints() -> Result<Vec<LlmEndpointInfo>, String> {
+    let active = active_endpoint().name;
+    let settings = crate::settings::get();
+    Ok(std::iter::once(LlmEndpoint::local())
+        .chain(settings.llm_endpoints)
+        .map(|endpoint| LlmEndpointInfo {
+            active: endpoint.name == active,
+            has_api_key: endpoint.api_key.is_some(),
+            name: endpoint.name,
+            provider: endpoint.provider,
+            base_url: endpoint.base_url,
+            default_model: endpoint.default_model,
+        })
+        .collect())
+}
+
+// Route generation commands to `name`; "local" goes back to the built-in Ollama
+#[tauri::command]
+pub async fn set_active_llm_endpoint(name: String) -> Result<(), String> {
+    let active = if name == LOCAL_ENDPOINT_NAME {
+        None
+    } else if crate::settings::get().llm_endpoints.iter().any(|endpoint| endpoint.name == name) {
+        Some(name.clone())
+    } else {
+        return Err(format!("No LLM endpoint named {}", name));
+    };
+
+    info!("🔌 Active LLM endpoint set to {}", name);
+    crate::settings::update(|settings| settings.active_llm_endpoint = active)?;
+    Ok(())
+}
+
+// Removing the active endpoint falls back to the local one
+#[tauri::command]
+pub async fn remove_llm_endpoint(name: String) -> Result<bool, String> {
+    let mut removed = false;
+    crate::settings::update(|settings| {
+        let before = settings.llm_endpoints.len();
+        settings.llm_endpoints.retain(|endpoint| endpoint.name != name);
+        removed = settings.llm_endpoints.len() < before;
+        if settings.active_llm_endpoint.as_deref() == Some(name.as_str()) {
+            settings.active_llm_endpoint = None;
+        }
+    })?;
+    if removed {
+        info!("🗑️ Removed LLM endpoint {}", name);
+    }
+    Ok(removed)
 }
 
+// Batch streamed tokens into fewer events for slow frontends
 #[tauri::command]
-pub async fn generate_llm_response(prompt: String) -> Result<String, String> {
-    info!("🚀 Received LLM request with prompt length: {}", prompt.len());
+pub async fn set_stream_flush(tokens: usize, interval_ms: Option<u64>) -> Result<(), String> {
+    if tokens == 0 {
+        return Err("Flush size must be at least 1 token".to_string());
+    }
 
-    let client = LLMClient::new();
+    info!("⚙️ Stream flush set to every {} tokens / {:?}ms", tokens, interval_ms);
+    crate::settings::update(|settings| settings.stream_flush = Some(StreamFlush { tokens, interval_ms }))?;
+    Ok(())
+}
 
-    // Use robust method for better handling of large responses
-    match client.generate_response_robust(prompt).await {
-        Ok(response) => {
-            info!("✅ LLM response generated successfully (length: {} chars)", response.len());
-            Ok(response)
-        }
-        Err(e) => {
-            error!("❌ LLM generation failed: {}", e);
-            Err(e.to_string())
-        }
+// Delay between words when a reply is replayed as a simulated stream
+#[tauri::command]
+pub async fn get_fallback_word_delay() -> Result<u64, String> {
+    Ok(fallback_word_delay_ms())
+}
+
+// 0 shows replayed replies instantly; None goes back to the default
+#[tauri::command]
+pub async fn set_fallback_word_delay(delay_ms: Option<u64>) -> Result<u64, String> {
+    if delay_ms.map_or(false, |ms| ms > MAX_FALLBACK_WORD_DELAY_MS) {
+        return Err(format!("Word delay must be at most {}ms", MAX_FALLBACK_WORD_DELAY_MS));
     }
+
+    info!("⚙️ Fallback word delay set to {:?}ms", delay_ms);
+    crate::settings::update(|settings| settings.fallback_word_delay_ms = delay_ms)?;
+    Ok(fallback_word_delay_ms())
 }
 
+// Cap how many streams may run at once; None goes back to the default
 #[tauri::command]
-pub async fn check_llm_health() -> Result<bool, String> {
-    info!("Checking LLM service health");
+pub async fn set_max_concurrent_streams(limit: Option<usize>) -> Result<usize, String> {
+    if limit == Some(0) {
+        return Err("At least 1 concurrent stream must be allowed".to_string());
+    }
 
-    let client = LLMClient::new();
-    
-    match client.check_health().await {
-        Ok(is_healthy) => {
-            info!("LLM health check result: {}", is_healthy);
-            Ok(is_healthy)
-        }
-        Err(e) => {
-            error!("LLM health check failed: {}", e);
-            Err(e.to_string())
+    info!("⚙️ Max concurrent streams set to {:?}", limit);
+    crate::settings::update(|settings| settings.max_concurrent_streams = limit)?;
+    Ok(max_concurrent_streams())
+}
+
+// Override the request/stream timeouts; None goes back to the hardware profile
+#[tauri::command]
+pub async fn set_llm_timeouts(timeouts: Option<LlmTimeouts>) -> Result<LlmTimeouts, String> {
+    if let Some(timeouts) = &timeouts {
+        if timeouts.request_secs == 0 || timeouts.stream_secs == 0 {
+            return Err("Timeouts must be at least 1 second".to_string());
         }
     }
+
+    info!("⚙️ LLM timeouts override set to {:?}", timeouts);
+    crate::settings::update(|settings| settings.llm_timeouts = timeouts)?;
+    Ok(llm_timeouts())
+}
+
+// Distinguish "Ollama down" from "no models" and "configured model missing"
+#[tauri::command]
+pub async fn diagnose_llm_setup() -> Result<LLMSetupDiagnosis, String> {
+    info!("🩺 Diagnosing LLM setup");
+
+    let diagnosis = LLMClient::new()?.diagnose_setup().await;
+    info!("🩺 LLM setup status: {:?}", diagnosis.status);
+    Ok(diagnosis)
 }
 
 // Streaming LLM response command
@@ -393,61 +2025,259 @@ pub async fn start_llm_stream(
     streamId: String,
     prompt: String,
     model: Option<String>,
-    systemPrompt: Option<String>
+    systemPrompt: Option<String>,
+    chat_id: Option<String>,
+    stop_regex: Option<String>
+) -> Result<String, String> {
+    let stop_regex = stop_regex
+        .map(|pattern| regex::Regex::new(&pattern))
+        .transpose()
+        .map_err(|e| format!("Invalid stop regex: {}", e))?;
+    let system_prompt = crate::settings::resolve_system_prompt(chat_id.as_deref(), systemPrompt);
+    spawn_llm_stream(app_handle, streamId, prompt, model, system_prompt, chat_id, stop_regex)
+}
+
+fn spawn_llm_stream(
+    app_handle: AppHandle,
+    stream_id: String,
+    prompt: String,
+    model: Option<String>,
+    system_prompt: crate::settings::ResolvedSystemPrompt,
+    chat_id: Option<String>,
+    stop_regex: Option<regex::Regex>
 ) -> Result<String, String> {
-    info!("🚀 Starting LLM stream for streamId: {}, prompt length: {}", streamId, prompt.len());
-    info!("📊 Parameters - Model: {:?}, System prompt: {}", model, systemPrompt.is_some());
+    info!("🚀 Starting LLM stream for stream_id: {}, prompt length: {}", stream_id, prompt.len());
+    info!("📊 Parameters - Model: {:?}, System prompt source: {:?}", model, system_prompt.source);
 
     if prompt.trim().is_empty() {
         error!("❌ Empty prompt provided");
         return Err("Prompt cannot be empty".to_string());
     }
 
-    if streamId.trim().is_empty() {
-        error!("❌ Empty streamId provided");
+    if stream_id.trim().is_empty() {
+        error!("❌ Empty stream_id provided");
         return Err("Stream ID cannot be empty".to_string());
     }
 
-    // Combine system prompt with user prompt if provided
-    let final_prompt = if let Some(sys_prompt) = systemPrompt {
-        format!("{}\n\nUser: {}", sys_prompt, prompt)
-    } else {
-        prompt
-    };
+    // Combine the resolved system prompt with the user prompt
+    let final_prompt = combine_with_system_prompt(system_prompt.prompt.as_deref(), prompt);
 
-    info!("📡 Using stream ID: {}", streamId);
+    info!("📡 Using stream ID: {}", stream_id);
     info!("📝 Final prompt length: {}", final_prompt.len());
 
-    // Clone streamId for the spawn task
-    let streamId_clone = streamId.clone();
+    // Clone stream_id for the spawn task
+    let stream_id_clone = stream_id.clone();
+
+    // Take a slot in the registry now so a burst of starts can't overshoot the limit
+    let config = StreamConfig {
+        system_prompt_source: system_prompt.source,
+        stop_regex,
+        ..StreamConfig::new(&stream_id, model, chat_id)
+    };
+    reserve_stream(&config)?;
+    // Cancellable as soon as the ID is handed back, before the task first runs
+    let cancel_token = crate::cancellation::register(&format!("{}{}", LLM_STREAM_TASK_PREFIX, stream_id));
 
     // Start the streaming process in the background
     info!("🚀 Spawning background streaming task...");
     tokio::spawn(async move {
-        info!("🔄 Background task started for stream: {}", streamId_clone);
-        match stream_llm_response(app_handle, streamId_clone.clone(), final_prompt).await {
+        info!("🔄 Background task started for stream: {}", stream_id_clone);
+        match stream_llm_response(&app_handle, config, final_prompt, cancel_token).await {
             Ok(_) => {
-                info!("✅ Background task completed successfully for stream: {}", streamId_clone);
+                info!("✅ Background task completed successfully for stream: {}", stream_id_clone);
             }
             Err(e) => {
-                error!("❌ Stream error for {}: {}", streamId_clone, e);
+                error!("❌ Stream error for {}: {}", stream_id_clone, e);
                 // The error has already been emitted in the stream_llm_response function
             }
         }
+        reemit_unacked_complete(&app_handle, &stream_id_clone).await;
     });
 
-    info!("✅ Stream command returning ID: {}", streamId);
-    Ok(streamId)
+    info!("✅ Stream command returning ID: {}", stream_id);
+    Ok(stream_id)
 }
 
 // Stop streaming command
 #[tauri::command]
 pub async fn stop_llm_stream(stream_id: String) -> Result<(), String> {
     info!("⏹️ Stopping LLM stream: {}", stream_id);
-    // TODO: Implement stream cancellation
+    if !cancel_stream(&stream_id) {
+        warn!("⚠️ No running stream with ID {}", stream_id);
+    }
     Ok(())
 }
 
+/// Cancel a running stream; false if none is running under `stream_id`.
+pub(crate) fn cancel_stream(stream_id: &str) -> bool {
+    crate::cancellation::cancel(&format!("{}{}", LLM_STREAM_TASK_PREFIX, stream_id))
+}
+
+// The frontend received a stream's complete event; false if none was awaiting an ack
+#[tauri::command]
+pub async fn ack_stream_complete(stream_id: String) -> Result<bool, String> {
+    let mut unacked = UNACKED_STREAMS.lock().unwrap();
+    let before = unacked.len();
+    unacked.retain(|stream| stream.stream_id != stream_id);
+    let acked = unacked.len() < before;
+    if acked {
+        info!("📬 Complete event acknowledged for {}", stream_id);
+    } else {
+        warn!("⚠️ Ack for {} did not match an unacknowledged stream", stream_id);
+    }
+    Ok(acked)
+}
+
+// Completed streams the frontend never acknowledged, oldest first
+#[tauri::command]
+pub async fn get_unacked_streams() -> Result<Vec<UnackedStream>, String> {
+    Ok(UNACKED_STREAMS.lock().unwrap().clone())
+}
+
+// Hold back a stream's output; tokens keep arriving and are buffered until resumed
+#[tauri::command]
+pub async fn pause_llm_stream(stream_id: String) -> Result<(), String> {
+    info!("⏸️ Pausing LLM stream: {}", stream_id);
+    set_stream_paused(&stream_id, true)
+}
+
+#[tauri::command]
+pub async fn resume_llm_stream(stream_id: String) -> Result<(), String> {
+    info!("▶️ Resuming LLM stream: {}", stream_id);
+    set_stream_paused(&stream_id, false)
+}
+
+// How much output a stream is holding back and how fast it is emitting, for tuning flush batching
+#[tauri::command]
+pub async fn get_stream_buffer_status(stream_id: String) -> Result<StreamBufferStatus, String> {
+    let stream = active_streams()
+        .into_iter()
+        .find(|stream| stream.stream_id == stream_id)
+        .ok_or_else(|| format!("No active stream with ID {}", stream_id))?;
+
+    let elapsed = (chrono::Utc::now() - stream.started_at).to_std().unwrap_or_default();
+    let secs = elapsed.as_secs_f64().max(0.001);
+    Ok(StreamBufferStatus {
+        stream_id: stream.stream_id,
+        paused: stream.paused,
+        buffered_tokens: stream.buffered_tokens,
+        tokens_received: stream.tokens,
+        chunks_emitted: stream.chunks_emitted,
+        elapsed_ms: elapsed.as_millis() as u64,
+        chunks_per_second: stream.chunks_emitted as f64 / secs,
+        tokens_per_second: stream.tokens as f64 / secs,
+    })
+}
+
+// Streams currently generating, with the model and options each one is using
+#[tauri::command]
+pub async fn list_active_streams() -> Result<Vec<ActiveStreamInfo>, String> {
+    Ok(active_streams())
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AutoGeneration {
+    /// Results arrive as `llm-stream-event`s for this ID either way.
+    pub stream_id: String,
+    pub streamed: bool,
+    pub system_prompt_source: crate::settings::SystemPromptSource,
+}
+
+// Stream or generate in one shot per the user's preference; both report through stream events
+#[tauri::command]
+pub async fn generate_auto(
+    app_handle: AppHandle,
+    chat_id: Option<String>,
+    prompt: String,
+    stream_id: String,
+    model: Option<String>,
+    system_prompt: Option<String>,
+) -> Result<AutoGeneration, String> {
+    let system_prompt = crate::settings::resolve_system_prompt(chat_id.as_deref(), system_prompt);
+    let system_prompt_source = system_prompt.source;
+
+    if !crate::settings::streaming_enabled() {
+        return generate_one_shot(app_handle, chat_id, prompt, stream_id, model, system_prompt).await;
+    }
+
+    let stream_id = spawn_llm_stream(app_handle, stream_id, prompt, model, system_prompt, chat_id, None)?;
+    Ok(AutoGeneration { stream_id, streamed: true, system_prompt_source })
+}
+
+async fn generate_one_shot(
+    app_handle: AppHandle,
+    chat_id: Option<String>,
+    prompt: String,
+    stream_id: String,
+    model: Option<String>,
+    system_prompt: crate::settings::ResolvedSystemPrompt,
+) -> Result<AutoGeneration, String> {
+    if prompt.trim().is_empty() {
+        return Err("Prompt cannot be empty".to_string());
+    }
+    if stream_id.trim().is_empty() {
+        return Err("Stream ID cannot be empty".to_string());
+    }
+
+    info!("🚀 Generating one-shot response for {} (streaming disabled)", stream_id);
+    let final_prompt = combine_with_system_prompt(system_prompt.prompt.as_deref(), prompt);
+    let config = StreamConfig {
+        system_prompt_source: system_prompt.source,
+        ..StreamConfig::new(&stream_id, model, chat_id)
+    };
+    let client = LLMClient::with_config(LLMConfig {
+        model: config.model.clone(),
+        options: config.options.clone(),
+        ..LLMConfig::default()
+    })?
+    .with_app_handle(app_handle.clone());
+
+    // Counts against the stream limit and can be stopped like a real stream
+    reserve_stream(&config)?;
+    let task_key = format!("{}{}", LLM_STREAM_TASK_PREFIX, stream_id);
+    let cancel_token = crate::cancellation::register(&task_key);
+
+    let id = stream_id.clone();
+    tokio::spawn(async move {
+        let outcome = tokio::select! {
+            result = client.generate_response_with_stats(final_prompt) => Some(result),
+            _ = cancel_token.cancelled() => None,
+        };
+        match outcome {
+            None => {
+                info!("⏹️ One-shot generation {} cancelled", id);
+                emit_stream_event(&app_handle, &id, StreamPayload::Cancelled { partial: String::new() });
+            }
+            Some(Ok(result)) => {
+                if let Some(chat_id) = config.chat_id.as_deref() {
+                    match crate::chat_store::PartialReplyWriter::start(chat_id, &id, config.system_prompt_source) {
+                        Ok(reply) => reply.finish(&result.response),
+                        Err(e) => warn!("⚠️ One-shot reply will not be persisted: {}", e),
+                    }
+                }
+
+                // The whole answer arrives as a single chunk, then the usual completion
+                emit_stream_chunk(&app_handle, &id, &result.response).await;
+                let stats = StreamStats {
+                    tokens: result.eval_count.map_or_else(|| estimate_tokens(&result.response), |count| count as usize),
+                    duration_ms: result.wall_time_ms,
+                    tokens_per_second: result.tokens_per_second,
+                    truncated: result.truncated,
+                    context_truncated: result.context_truncated,
+                };
+                emit_stream_complete(&app_handle, &id, stats, StreamMethod::OneShot, None).await;
+            }
+            Some(Err(e)) => {
+                emit_stream_error(&app_handle, &id, "generation_failed", &format!("LLM generation failed: {}", e)).await;
+            }
+        }
+        crate::cancellation::unregister(&task_key, &cancel_token);
+        unregister_stream(&id);
+    });
+
+    Ok(AutoGeneration { stream_id, streamed: false, system_prompt_source: system_prompt.source })
+}
+
 // Test streaming command for debugging
 #[tauri::command]
 pub async fn test_streaming(app_handle: AppHandle) -> Result<String, String> {
@@ -468,253 +2298,1057 @@ pub async fn test_streaming(app_handle: AppHandle) -> Result<String, String> {
     tokio::spawn(async move {
         info!("🔄 Starting test stream background task for: {}", stream_id_clone);
 
-        // Simulate streaming by sending chunks word by word
-        let words: Vec<&str> = test_response.split_whitespace().collect();
-        let chunk_size = 1; // Send one word at a time for better visual effect
+        // Simulate streaming at the same pace as the fallback path
+        let stats = replay_as_stream(&app_handle_clone, &stream_id_clone, test_response).await;
+        emit_stream_complete(&app_handle_clone, &stream_id_clone, stats, StreamMethod::Fallback, None).await;
+        info!("✅ Test streaming completed for: {}", stream_id_clone);
+    });
+
+    info!("✅ Test streaming command returning ID: {}", test_stream_id);
+    Ok(test_stream_id)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StreamPipelineCheck {
+    pub passed: bool,
+    pub events_sent: usize,
+    pub events_received: usize,
+    /// Time from emitting the first event to a listener receiving it.
+    pub first_event_ms: Option<u64>,
+    pub total_ms: u64,
+    pub error: Option<String>,
+}
+
+// Round-trip a short tagged stream through the event system to catch delivery problems early
+#[tauri::command]
+pub async fn verify_stream_pipeline(app_handle: AppHandle) -> Result<StreamPipelineCheck, String> {
+    Ok(check_stream_pipeline(&app_handle).await)
+}
+
+/// Emit a short test stream and listen for it under the real event name.
+pub async fn check_stream_pipeline(app_handle: &AppHandle) -> StreamPipelineCheck {
+    use tauri::Listener;
+
+    let stream_id = format!("pipeline_check_{}", uuid::Uuid::new_v4());
+    info!("🧪 Verifying stream event pipeline with {}", stream_id);
+
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let expected_id = stream_id.clone();
+    let listener = app_handle.listen(STREAM_EVENT_NAME, move |event| {
+        match serde_json::from_str::<StreamEvent>(event.payload()) {
+            Ok(stream_event) if stream_event.stream_id == expected_id => {
+                let _ = tx.send((std::time::Instant::now(), stream_event));
+            }
+            Ok(_) => {}
+            Err(e) => warn!("⚠️ Unparseable {} payload: {}", STREAM_EVENT_NAME, e),
+        }
+    });
+
+    let words = ["pipeline ", "check ", "ok"];
+    let started = std::time::Instant::now();
+    for word in words {
+        emit_stream_chunk(app_handle, &stream_id, word).await;
+    }
+    let stats = StreamStats { tokens: words.len(), ..StreamStats::default() };
+    emit_stream_complete(app_handle, &stream_id, stats, StreamMethod::Fallback, None).await;
+    let events_sent = words.len() + 1;
+
+    let mut received = Vec::new();
+    let mut first_event_ms = None;
+    let finished = tokio::time::timeout(PIPELINE_CHECK_TIMEOUT, async {
+        while let Some((at, event)) = rx.recv().await {
+            first_event_ms.get_or_insert(at.duration_since(started).as_millis() as u64);
+            let complete = matches!(event.payload, StreamPayload::Complete { .. });
+            received.push(event);
+            if complete {
+                break;
+            }
+        }
+    })
+    .await
+    .is_ok();
+    app_handle.unlisten(listener);
+
+    let texts: Vec<&str> = received
+        .iter()
+        .filter_map(|event| match &event.payload {
+            StreamPayload::Chunk { text } => Some(text.as_str()),
+            _ => None,
+        })
+        .collect();
+    let error = if !finished {
+        Some(format!(
+            "Timed out after {}s with {} of {} events delivered",
+            PIPELINE_CHECK_TIMEOUT.as_secs(), received.len(), events_sent
+        ))
+    } else if received.iter().any(|event| event.schema_version != STREAM_EVENT_SCHEMA_VERSION) {
+        Some("Received events with an unexpected schema version".to_string())
+    } else if texts != words {
+        Some(format!("Chunks arrived out of order or incomplete: {:?}", texts))
+    } else {
+        None
+    };
+
+    let check = StreamPipelineCheck {
+        passed: error.is_none(),
+        events_sent,
+        events_received: received.len(),
+        first_event_ms,
+        total_ms: started.elapsed().as_millis() as u64,
+        error,
+    };
+    match &check.error {
+        None => info!("✅ Stream pipeline OK ({} events in {}ms)", check.events_received, check.total_ms),
+        Some(e) => error!("❌ Stream pipeline check failed: {}", e),
+    }
+    check
+}
+
+// Internal streaming function; the caller registers `cancel_token` so a cancel
+// that lands before the task gets going still stops it
+async fn stream_llm_response(
+    events: &dyn EventSink,
+    config: StreamConfig,
+    prompt: String,
+    cancel_token: std::sync::Arc<crate::cancellation::CancellationToken>,
+) -> Result<(), String> {
+    let stream_id = config.stream_id.as_str();
+    info!("🔄 Starting stream processing for: {} (model: {}, prompt length: {})", stream_id, config.model, prompt.len());
+    if crate::settings::verbose_llm_logging() {
+        info!("📝 Prompt preview: {}", prompt.chars().take(100).collect::<String>());
+    }
+
+    let activity = ActivityGuard::start(true);
+    let task_key = format!("{}{}", LLM_STREAM_TASK_PREFIX, stream_id);
+    register_stream(&config);
+
+    // Try Ollama streaming first
+    info!("🚀 Attempting Ollama streaming for: {}", stream_id);
+    // Save the reply to the chat as it streams so a crash doesn't lose it
+    let mut partial_reply = config.chat_id.as_deref().and_then(|chat_id| {
+        crate::chat_store::PartialReplyWriter::start(chat_id, stream_id, config.system_prompt_source)
+            .map_err(|e| warn!("⚠️ Streamed reply will not be persisted: {}", e))
+            .ok()
+    });
+    let outcome = stream_ollama_response(events, &config, &prompt, &cancel_token, &mut partial_reply).await;
+    let result = match outcome {
+        Ok(_) => {
+            info!("✅ Ollama streaming completed successfully for: {}", stream_id);
+            activity.succeeded();
+            Ok(())
+        }
+        Err(e) if crate::network::blocked_by_offline_mode(&config.base_url) => {
+            // The offline_blocked error event is already out; a fallback would be blocked too
+            warn!("🔒 Streaming for {} blocked by offline mode", stream_id);
+            if let Some(partial_reply) = partial_reply.take() {
+                partial_reply.abandon();
+            }
+            Err(e)
+        }
+        Err(e) => {
+            warn!("⚠️ Ollama streaming failed for {}: {}", stream_id, e);
+            error!("🔍 Ollama error details: {}", e);
+
+            // Try fallback streaming
+            info!("🔄 Attempting fallback streaming for: {}", stream_id);
+            match stream_fallback_response(events, &config, &prompt, &e, partial_reply.take()).await {
+                Ok(_) => {
+                    info!("✅ Fallback streaming completed for: {}", stream_id);
+                    activity.succeeded();
+                    Ok(())
+                }
+                Err(fallback_error) => {
+                    error!("❌ Both Ollama and fallback streaming failed for {}", stream_id);
+                    error!("🔍 Ollama error: {}", e);
+                    error!("🔍 Fallback error: {}", fallback_error);
+
+                    let combined_error = format!("Ollama failed: {}. Fallback failed: {}", e, fallback_error);
+                    emit_stream_error(events, stream_id, "generation_failed", &combined_error).await;
+                    Err(combined_error)
+                }
+            }
+        }
+    };
+
+    crate::cancellation::unregister(&task_key, &cancel_token);
+    unregister_stream(stream_id);
+    result
+}
+
+/// Splits a raw NDJSON byte stream into complete lines.
+///
+/// Bytes are buffered until a newline arrives so multibyte UTF-8 characters
+/// that straddle network chunk boundaries are decoded intact.
+#[derive(Default)]
+pub struct JsonLineDecoder {
+    buffer: Vec<u8>,
+}
+
+impl JsonLineDecoder {
+    pub fn new() -> Self {
+        Self { buffer: Vec::new() }
+    }
+
+    /// Feed a raw chunk and return every complete, non-empty line it finished.
+    pub fn push(&mut self, chunk: &[u8]) -> Vec<String> {
+        self.buffer.extend_from_slice(chunk);
+
+        let mut lines = Vec::new();
+        while let Some(newline_pos) = self.buffer.iter().position(|&b| b == b'\n') {
+            let line_bytes: Vec<u8> = self.buffer.drain(..=newline_pos).collect();
+            let line = String::from_utf8_lossy(&line_bytes[..newline_pos]).trim().to_string();
+            if !line.is_empty() {
+                lines.push(line);
+            }
+        }
+        lines
+    }
+
+    /// Return whatever is left in the buffer once the stream has ended.
+    pub fn finish(&mut self) -> Option<String> {
+        let rest = std::mem::take(&mut self.buffer);
+        let line = String::from_utf8_lossy(&rest).trim().to_string();
+        if line.is_empty() {
+            None
+        } else {
+            Some(line)
+        }
+    }
+}
+
+/// Outcome of interpreting a single line of a streaming response body.
+pub enum StreamLine {
+    /// Blank line, SSE metadata, or an end-of-stream marker.
+    Skip,
+    Response(OllamaResponse),
+    /// The endpoint sent an `{"error": "..."}` payload.
+    Error(String),
+    /// Not JSON on its own; may be part of a multi-line object.
+    Unparsed,
+}
+
+/// Parse one line of an Ollama stream, tolerating SSE `data:` framing.
+pub fn parse_stream_line(line: &str) -> StreamLine {
+    let mut payload = line.trim();
+
+    if let Some(data) = payload.strip_prefix("data:") {
+        payload = data.trim();
+    } else if payload.starts_with(':')
+        || payload.starts_with("event:")
+        || payload.starts_with("id:")
+        || payload.starts_with("retry:")
+    {
+        return StreamLine::Skip;
+    }
+
+    if payload.is_empty() || payload == "[DONE]" {
+        return StreamLine::Skip;
+    }
+
+    if let Ok(ollama_response) = serde_json::from_str::<OllamaResponse>(payload) {
+        return StreamLine::Response(ollama_response);
+    }
+
+    match serde_json::from_str::<serde_json::Value>(payload) {
+        Ok(value) => {
+            if let Some(response) = openai_to_ollama_response(&value) {
+                return StreamLine::Response(response);
+            }
+            // Ollama sends a string; OpenAI-style servers an object with a message
+            let error = &value["error"];
+            match error.as_str().or_else(|| error["message"].as_str()) {
+                Some(message) => StreamLine::Error(message.to_string()),
+                None => {
+                    warn!("⚠️ Unexpected JSON in stream: {}", payload);
+                    StreamLine::Unparsed
+                }
+            }
+        }
+        Err(_) => StreamLine::Unparsed,
+    }
+}
+
+/// Where generation requests go for `provider`. OpenAI-style base URLs may or may not end in `/v1`.
+fn generation_url(provider: LlmProvider, base_url: &str) -> String {
+    let base_url = base_url.trim_end_matches('/');
+    match provider {
+        LlmProvider::Ollama => format!("{}/api/generate", base_url),
+        LlmProvider::OpenaiCompatible if base_url.ends_with("/v1") => format!("{}/chat/completions", base_url),
+        LlmProvider::OpenaiCompatible => format!("{}/v1/chat/completions", base_url),
+    }
+}
+
+/// Build a generation request in the shape `provider` expects.
+fn endpoint_request(
+    client: &reqwest::Client,
+    provider: LlmProvider,
+    base_url: &str,
+    api_key: Option<&str>,
+    request: &OllamaRequest,
+) -> reqwest::RequestBuilder {
+    let builder = client.post(generation_url(provider, base_url));
+    let builder = match provider {
+        LlmProvider::Ollama => builder.json(request),
+        LlmProvider::OpenaiCompatible => builder.json(&openai_chat_body(request)),
+    };
+    match api_key {
+        Some(key) => builder.bearer_auth(key),
+        None => builder,
+    }
+}
+
+/// Translate an Ollama generate request into an OpenAI chat completion request.
+fn openai_chat_body(request: &OllamaRequest) -> serde_json::Value {
+    let mut body = serde_json::json!({
+        "model": request.model,
+        "messages": [{ "role": "user", "content": request.prompt }],
+        "stream": request.stream,
+    });
+    if let Some(options) = &request.options {
+        let fields = [
+            ("temperature", options.temperature.map(serde_json::Value::from)),
+            ("top_p", options.top_p.map(serde_json::Value::from)),
+            ("seed", options.seed.map(serde_json::Value::from)),
+            ("max_tokens", options.num_predict.filter(|n| *n > 0).map(serde_json::Value::from)),
+        ];
+        for (key, value) in fields {
+            if let Some(value) = value {
+                body[key] = value;
+            }
+        }
+    }
+    if request.format.is_some() {
+        body["response_format"] = serde_json::json!({ "type": "json_object" });
+    }
+    body
+}
+
+/// Read an OpenAI-style completion or stream chunk as the equivalent Ollama response.
+fn openai_to_ollama_response(value: &serde_json::Value) -> Option<OllamaResponse> {
+    let choices = value.get("choices")?.as_array()?;
+    let choice = choices.first();
+    let text = choice
+        .and_then(|c| c["delta"]["content"].as_str().or(c["message"]["content"].as_str()).or(c["text"].as_str()))
+        .unwrap_or_default();
+    let finish_reason = choice.and_then(|c| c["finish_reason"].as_str());
+
+    Some(OllamaResponse {
+        model: value["model"].as_str().unwrap_or_default().to_string(),
+        created_at: String::new(),
+        response: text.to_string(),
+        done: finish_reason.is_some(),
+        context: None,
+        total_duration: None,
+        load_duration: None,
+        prompt_eval_count: value["usage"]["prompt_tokens"].as_u64().map(|n| n as u32),
+        prompt_eval_duration: None,
+        eval_count: value["usage"]["completion_tokens"].as_u64().map(|n| n as u32),
+        eval_duration: None,
+        done_reason: finish_reason.map(|reason| if reason == "length" { "length" } else { "stop" }.to_string()),
+    })
+}
+
+/// Parse a complete (non-streamed) response from either provider.
+fn parse_generation_response(text: &str) -> Result<OllamaResponse, serde_json::Error> {
+    serde_json::from_str::<OllamaResponse>(text).or_else(|e| {
+        serde_json::from_str::<serde_json::Value>(text)
+            .ok()
+            .and_then(|value| openai_to_ollama_response(&value))
+            .ok_or(e)
+    })
+}
+
+/// The response's Content-Type header, if it has a readable one.
+fn response_content_type(response: &reqwest::Response) -> Option<String> {
+    response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.to_string())
+}
+
+/// A readable error when a body is clearly not JSON, such as a reverse proxy's
+/// HTML error page, instead of the parser's error on its first character.
+fn non_json_response_error(endpoint: &str, content_type: Option<&str>, body: &str) -> Option<String> {
+    let trimmed = body.trim_start();
+    let html_content_type = content_type.map_or(false, |ct| {
+        let ct = ct.to_ascii_lowercase();
+        ct.starts_with("text/html") || ct.starts_with("text/plain")
+    });
+    if !trimmed.starts_with('<') && !(html_content_type && !trimmed.starts_with('{')) {
+        return None;
+    }
+
+    let snippet: String = trimmed.split_whitespace().collect::<Vec<_>>().join(" ").chars().take(NON_JSON_SNIPPET_CHARS).collect();
+    Some(format!(
+        "Received non-JSON response from {} ({}) - check your base URL/proxy. Body starts: {}",
+        endpoint,
+        content_type.unwrap_or("no Content-Type"),
+        snippet
+    ))
+}
+
+/// One item from a token stream.
+#[derive(Debug)]
+pub enum StreamToken {
+    /// Newly generated text.
+    Text(String),
+    /// The final object with Ollama's counters; its text has already been yielded.
+    Done(OllamaResponse),
+}
+
+/// Why a token stream failed, with the code sent in the `error` stream event.
+#[derive(Debug, Clone)]
+pub struct StreamTokenError {
+    pub code: &'static str,
+    pub message: String,
+}
+
+impl StreamTokenError {
+    fn new(code: &'static str, message: String) -> Self {
+        error!("❌ {}", message);
+        Self { code, message }
+    }
+}
+
+impl std::fmt::Display for StreamTokenError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+/// Generated tokens, ending after `StreamToken::Done` or the first error.
+/// Dropping it closes the connection, which stops Ollama generating.
+pub type TokenStream = std::pin::Pin<Box<dyn futures_util::Stream<Item = Result<StreamToken, StreamTokenError>> + Send>>;
+
+/// Stream `prompt` through `model` on the active endpoint, for Rust code that
+/// wants the tokens directly rather than as `llm-stream-event`s.
+pub async fn stream_tokens(
+    model: &str,
+    prompt: &str,
+    options: Option<GenerationOptions>,
+) -> Result<TokenStream, StreamTokenError> {
+    let mut config = StreamConfig::new("internal", Some(model.to_string()), None);
+    config.options = options;
+    open_token_stream(&config, prompt, None).await
+}
+
+/// Send the streaming request, waiting out a model load, and decode the body into tokens.
+async fn open_token_stream(
+    config: &StreamConfig,
+    prompt: &str,
+    events: Option<&dyn EventSink>,
+) -> Result<TokenStream, StreamTokenError> {
+    let request = OllamaRequest {
+        model: config.model.clone(),
+        prompt: sanitize_prompt(prompt),
+        stream: true,
+        options: apply_output_limit(config.options.clone()),
+        format: None,
+    };
+
+    let endpoint = generation_url(config.provider, &config.base_url);
+    info!("📤 Sending request to Ollama: {}", endpoint);
+
+    let client = crate::network::build_http_client(&config.base_url, "llm stream", None).map_err(|e| {
+        // Offline mode refusing the endpoint must not be mistaken for a connection failure
+        let code = if crate::network::blocked_by_offline_mode(&config.base_url) { "offline_blocked" } else { "request_failed" };
+        StreamTokenError::new(code, e)
+    })?;
+    let load_deadline = Duration::from_secs(config.model_load_wait_seconds);
+    let mut load_wait = ModelLoadWait::new(&config.model, events, load_deadline);
+    let response = loop {
+        let response = endpoint_request(&client, config.provider, &config.base_url, config.api_key.as_deref(), &request)
+            .timeout(Duration::from_secs(config.timeout_seconds))
+            .send()
+            .await
+            .map_err(|e| StreamTokenError::new("request_failed", format!("Failed to send request to Ollama: {}", e)))?;
 
-        info!("🔄 Starting test stream with {} chunks ({} words)", words.chunks(chunk_size).len(), words.len());
+        info!("📥 Received response from Ollama with status: {}", response.status());
 
-        for (i, chunk) in words.chunks(chunk_size).enumerate() {
-            let chunk_text = chunk.join(" ") + " ";
-            info!("📤 Test chunk {}: '{}'", i + 1, chunk_text);
-            emit_stream_chunk(&app_handle_clone, &stream_id_clone, &chunk_text).await;
+        if response.status().is_success() {
+            load_wait.ready();
+            break response;
+        }
 
-            // Shorter delay for more responsive streaming
-            tokio::time::sleep(Duration::from_millis(150)).await;
+        let status = response.status();
+        let content_type = response_content_type(&response);
+        let error_text = response.text().await.unwrap_or_default();
+        if is_model_loading(status.as_u16(), &error_text) && load_wait.wait().await {
+            continue;
+        }
+        if let Some(message) = non_json_response_error(&endpoint, content_type.as_deref(), &error_text) {
+            return Err(StreamTokenError::new("non_json_response", format!("HTTP {}: {}", status, message)));
         }
+        return Err(StreamTokenError::new("ollama_http", format!("HTTP error from Ollama: {}", status)));
+    };
 
-        emit_stream_complete(&app_handle_clone, &stream_id_clone).await;
-        info!("✅ Test streaming completed for: {}", stream_id_clone);
-    });
+    // A proxy can answer 200 with an HTML page; catch it before the line decoder does
+    let content_type = response_content_type(&response);
+    if content_type.as_deref().map_or(false, |ct| ct.to_ascii_lowercase().starts_with("text/html")) {
+        let body = response.text().await.unwrap_or_default();
+        let message = non_json_response_error(&endpoint, content_type.as_deref(), &body)
+            .unwrap_or_else(|| format!("Received an HTML response from {} - check your base URL/proxy", endpoint));
+        return Err(StreamTokenError::new("non_json_response", message));
+    }
 
-    info!("✅ Test streaming command returning ID: {}", test_stream_id);
-    Ok(test_stream_id)
+    info!("📖 Processing streaming response from Ollama...");
+    Ok(decode_token_stream(Box::pin(response.bytes_stream())))
 }
 
-// Internal streaming function
-async fn stream_llm_response(app_handle: AppHandle, stream_id: String, prompt: String) -> Result<(), String> {
-    info!("🔄 Starting stream processing for: {} (prompt length: {})", stream_id, prompt.len());
-    info!("📝 Prompt preview: {}", if prompt.len() > 100 { &prompt[..100] } else { &prompt });
-
-    // Try Ollama streaming first
-    info!("🚀 Attempting Ollama streaming for: {}", stream_id);
-    match stream_ollama_response(&app_handle, &stream_id, &prompt).await {
-        Ok(_) => {
-            info!("✅ Ollama streaming completed successfully for: {}", stream_id);
-            Ok(())
-        }
-        Err(e) => {
-            warn!("⚠️ Ollama streaming failed for {}: {}", stream_id, e);
-            error!("🔍 Ollama error details: {}", e);
+/// Turn a newline-delimited JSON body into tokens.
+fn decode_token_stream(body: ResponseBody) -> TokenStream {
+    let state = TokenStreamState {
+        body,
+        decoder: JsonLineDecoder::new(),
+        pending: std::collections::VecDeque::new(),
+        unparsed_body: String::new(),
+        produced_text: false,
+        ended: false,
+    };
 
-            // Try fallback streaming
-            info!("🔄 Attempting fallback streaming for: {}", stream_id);
-            match stream_fallback_response(&app_handle, &stream_id, &prompt).await {
-                Ok(_) => {
-                    info!("✅ Fallback streaming completed for: {}", stream_id);
-                    Ok(())
+    Box::pin(futures_util::stream::unfold(state, |mut state| async move {
+        loop {
+            if let Some(item) = state.pending.pop_front() {
+                return Some((item, state));
+            }
+            if state.ended {
+                return None;
+            }
+            match state.body.next().await {
+                Some(Ok(chunk)) => {
+                    for line in state.decoder.push(&chunk) {
+                        state.handle_line(line);
+                    }
                 }
-                Err(fallback_error) => {
-                    error!("❌ Both Ollama and fallback streaming failed for {}", stream_id);
-                    error!("🔍 Ollama error: {}", e);
-                    error!("🔍 Fallback error: {}", fallback_error);
-
-                    let combined_error = format!("Ollama failed: {}. Fallback failed: {}", e, fallback_error);
-                    emit_stream_error(&app_handle, &stream_id, &combined_error).await;
-                    Err(combined_error)
+                Some(Err(e)) => state.fail("stream_interrupted", format!("Stream error: {}", e)),
+                None => {
+                    // Ollama may close the connection without a trailing newline after the last object
+                    if let Some(line) = state.decoder.finish() {
+                        state.handle_line(line);
+                    }
+                    state.finish_body();
                 }
             }
         }
-    }
+    }))
 }
 
-// Stream response from Ollama
-async fn stream_ollama_response(app_handle: &AppHandle, stream_id: &str, prompt: &str) -> Result<(), String> {
-    info!("📡 Streaming from Ollama for: {} (model: {})", stream_id, DEFAULT_MODEL);
+type ResponseBody = std::pin::Pin<Box<dyn futures_util::Stream<Item = reqwest::Result<Bytes>> + Send>>;
 
-    let request = OllamaRequest {
-        model: DEFAULT_MODEL.to_string(),
-        prompt: prompt.to_string(),
-        stream: true,
-    };
+/// Decoding state behind a `TokenStream`.
+struct TokenStreamState {
+    body: ResponseBody,
+    decoder: JsonLineDecoder,
+    pending: std::collections::VecDeque<Result<StreamToken, StreamTokenError>>,
+    unparsed_body: String,
+    produced_text: bool,
+    ended: bool,
+}
 
-    info!("📤 Sending request to Ollama: {}/api/generate", OLLAMA_BASE_URL);
+impl TokenStreamState {
+    fn handle_line(&mut self, line: String) {
+        if self.ended {
+            return;
+        }
+        match parse_stream_line(&line) {
+            StreamLine::Skip => {}
+            StreamLine::Response(ollama_response) => {
+                if !ollama_response.response.is_empty() {
+                    self.produced_text = true;
+                    self.pending.push_back(Ok(StreamToken::Text(ollama_response.response.clone())));
+                }
+                if ollama_response.done {
+                    self.ended = true;
+                    self.pending.push_back(Ok(StreamToken::Done(ollama_response)));
+                }
+            }
+            StreamLine::Error(message) => self.fail("ollama_error", format!("Ollama returned an error: {}", message)),
+            StreamLine::Unparsed => {
+                // Keep it around in case the body is a single multi-line JSON object
+                self.unparsed_body.push_str(&line);
+                self.unparsed_body.push('\n');
+            }
+        }
+    }
 
-    let client = reqwest::Client::new();
-    let response = client
-        .post(&format!("{}/api/generate", OLLAMA_BASE_URL))
-        .json(&request)
-        .timeout(REQUEST_TIMEOUT)
-        .send()
-        .await
-        .map_err(|e| {
-            error!("❌ Failed to send request to Ollama: {}", e);
-            format!("Failed to send request to Ollama: {}", e)
-        })?;
+    /// The body closed without a final object.
+    fn finish_body(&mut self) {
+        if self.ended {
+            return;
+        }
+        self.ended = true;
 
-    info!("📥 Received response from Ollama with status: {}", response.status());
+        // Nothing streamed - the whole body may be one (possibly pretty-printed) JSON object
+        if self.produced_text || self.unparsed_body.trim().is_empty() {
+            return;
+        }
+        match parse_stream_line(self.unparsed_body.trim()) {
+            StreamLine::Response(ollama_response) if !ollama_response.response.is_empty() => {
+                info!("📦 Ollama returned a single non-delimited JSON object, handling as one-shot response");
+                self.pending.push_back(Ok(StreamToken::Text(ollama_response.response)));
+            }
+            StreamLine::Error(message) => self.fail("ollama_error", format!("Ollama returned an error: {}", message)),
+            _ => {
+                if let Some(message) = non_json_response_error("the endpoint", None, &self.unparsed_body) {
+                    self.fail("non_json_response", message);
+                    return;
+                }
+                let preview: String = self.unparsed_body.chars().take(200).collect();
+                self.fail("invalid_response", format!("Could not parse any JSON from Ollama response: {}", preview.trim()));
+            }
+        }
+    }
 
-    if !response.status().is_success() {
-        let error_msg = format!("HTTP error from Ollama: {}", response.status());
-        error!("❌ {}", error_msg);
-        emit_stream_error(app_handle, stream_id, &error_msg).await;
-        return Err(error_msg);
+    fn fail(&mut self, code: &'static str, message: String) {
+        self.ended = true;
+        self.pending.push_back(Err(StreamTokenError::new(code, message)));
     }
+}
 
-    // Handle streaming response properly
-    info!("📖 Processing streaming response from Ollama...");
+// Stream response from Ollama. `partial_reply` is finished on success and left
+// for the caller, with whatever text arrived saved, when this fails.
+async fn stream_ollama_response(
+    events: &dyn EventSink,
+    config: &StreamConfig,
+    prompt: &str,
+    cancel_token: &crate::cancellation::CancellationToken,
+    partial_reply: &mut Option<crate::chat_store::PartialReplyWriter>,
+) -> Result<(), String> {
+    let stream_id = config.stream_id.as_str();
+    info!("📡 Streaming from Ollama for: {} (model: {})", stream_id, config.model);
+    let started = std::time::Instant::now();
+
+    let mut tokens = match open_token_stream(config, prompt, Some(events)).await {
+        Ok(tokens) => tokens,
+        Err(e) => {
+            // Connection failures go straight to the fallback without an error event
+            if e.code != "request_failed" {
+                emit_stream_error(events, stream_id, e.code, &e.message).await;
+            }
+            return Err(e.message);
+        }
+    };
 
-    // Use proper streaming with bytes_stream
-    let mut stream = response.bytes_stream();
     let mut accumulated_response = String::new();
-    let mut buffer = String::new();
-
-    while let Some(chunk_result) = stream.next().await {
-        match chunk_result {
-            Ok(chunk) => {
-                let chunk_str = String::from_utf8_lossy(&chunk);
-                buffer.push_str(&chunk_str);
-
-                // Process complete JSON lines
-                while let Some(newline_pos) = buffer.find('\n') {
-                    let line = buffer[..newline_pos].trim().to_string();
-                    buffer = buffer[newline_pos + 1..].to_string();
+    let mut chunk_buffer = ChunkBuffer::new(config.flush_every.clone());
+    let mut token_count = 0usize;
+    let mut last_metrics = std::time::Instant::now();
+
+    loop {
+        sync_stream_pause(events, stream_id, &mut chunk_buffer).await;
+        let next = tokio::select! {
+            next = tokens.next() => next,
+            // Wake up now and then so a resume is noticed even if Ollama goes quiet
+            _ = tokio::time::sleep(PAUSE_POLL_INTERVAL) => continue,
+            _ = cancel_token.cancelled() => {
+                info!("⏹️ Stream {} cancelled after {} tokens", stream_id, token_count);
+                if let Some(tail) = chunk_buffer.take() {
+                    emit_stream_chunk(events, stream_id, &tail).await;
+                }
+                if let Some(partial_reply) = partial_reply.take() {
+                    partial_reply.finish(&accumulated_response);
+                }
+                emit_stream_event(events, stream_id, StreamPayload::Cancelled { partial: accumulated_response });
+                return Ok(());
+            }
+        };
 
-                    if line.is_empty() {
-                        continue;
+        match next {
+            Some(Ok(StreamToken::Text(text))) => {
+                if token_count == 0 {
+                    let ms = started.elapsed().as_millis() as u64;
+                    emit_stream_event(events, stream_id, StreamPayload::FirstToken { ms });
+                }
+                token_count += 1;
+                // Text before this token that has already gone out as chunks
+                let emitted_len = accumulated_response.len() - chunk_buffer.pending_len();
+                accumulated_response.push_str(&text);
+
+                let stop_at = config
+                    .stop_regex
+                    .as_ref()
+                    .and_then(|stop_regex| stop_regex_cut(stop_regex, &accumulated_response, text.len()));
+                if let Some(cut) = stop_at {
+                    info!("🛑 Stream {} matched its stop regex after {} tokens", stream_id, token_count);
+                    // Dropping the token stream below closes the connection, which stops Ollama generating
+                    accumulated_response.truncate(cut);
+                    chunk_buffer.discard();
+                    if cut > emitted_len {
+                        chunk_buffer.push(&accumulated_response[emitted_len..]);
+                    }
+                    while stream_paused(stream_id) && !cancel_token.is_cancelled() {
+                        tokio::time::sleep(PAUSE_POLL_INTERVAL).await;
                     }
+                    sync_stream_pause(events, stream_id, &mut chunk_buffer).await;
+                    if let Some(tail) = chunk_buffer.take() {
+                        emit_stream_chunk(events, stream_id, &tail).await;
+                    }
+                    if let Some(partial_reply) = partial_reply.take() {
+                        partial_reply.finish(&accumulated_response);
+                    }
+                    let stats = StreamStats {
+                        tokens: token_count,
+                        duration_ms: started.elapsed().as_millis() as u64,
+                        ..StreamStats::default()
+                    };
+                    emit_stream_complete(events, stream_id, stats, StreamMethod::Ollama, None).await;
+                    return Ok(());
+                }
 
-                    // Try to parse each line as JSON
-                    match serde_json::from_str::<OllamaResponse>(&line) {
-                        Ok(ollama_response) => {
-                            if !ollama_response.response.is_empty() {
-                                accumulated_response.push_str(&ollama_response.response);
-                                emit_stream_chunk(app_handle, stream_id, &ollama_response.response).await;
-                            }
+                if let Some(chunk) = chunk_buffer.push(&text) {
+                    emit_stream_chunk(events, stream_id, &chunk).await;
+                }
 
-                            // Check if this is the final chunk
-                            if ollama_response.done {
-                                info!("✅ Streaming completed successfully");
-                                emit_stream_complete(app_handle, stream_id).await;
-                                return Ok(());
-                            }
-                        }
-                        Err(e) => {
-                            warn!("⚠️ Failed to parse streaming chunk: {} - Line: {}", e, line);
-                            // Continue processing other chunks
-                        }
+                let repetition = config
+                    .repetition_threshold
+                    .and_then(|threshold| detect_repetition(&accumulated_response, threshold));
+                if let Some(repeated) = repetition {
+                    warn!("🔁 Stream {} is repeating itself after {} tokens, cancelling", stream_id, token_count);
+                    if let Some(tail) = chunk_buffer.take() {
+                        emit_stream_chunk(events, stream_id, &tail).await;
                     }
+                    if let Some(partial_reply) = partial_reply.take() {
+                        partial_reply.finish(&accumulated_response);
+                    }
+                    emit_stream_event(events, stream_id, StreamPayload::RepetitionDetected {
+                        text: repeated,
+                        repeats: config.repetition_threshold.unwrap_or_default(),
+                    });
+                    emit_stream_event(events, stream_id, StreamPayload::Cancelled { partial: accumulated_response });
+                    return Ok(());
+                }
+
+                update_stream_progress(stream_id, Some(token_count), &chunk_buffer);
+                if last_metrics.elapsed() >= STREAM_METRICS_INTERVAL {
+                    last_metrics = std::time::Instant::now();
+                    let elapsed = started.elapsed();
+                    emit_stream_event(events, stream_id, StreamPayload::Metrics {
+                        tokens: token_count,
+                        elapsed_ms: elapsed.as_millis() as u64,
+                        tokens_per_second: token_count as f64 / elapsed.as_secs_f64().max(0.001),
+                    });
+                }
+                if let Some(partial_reply) = partial_reply.as_mut() {
+                    partial_reply.update(&accumulated_response);
                 }
             }
-            Err(e) => {
-                let error_msg = format!("Stream error: {}", e);
-                error!("❌ {}", error_msg);
-                emit_stream_error(app_handle, stream_id, &error_msg).await;
-                return Err(error_msg);
+            Some(Ok(StreamToken::Done(ollama_response))) => {
+                info!("✅ Streaming completed successfully");
+                // A paused stream finishes once the user resumes (or stops) it
+                while stream_paused(stream_id) && !cancel_token.is_cancelled() {
+                    tokio::time::sleep(PAUSE_POLL_INTERVAL).await;
+                }
+                sync_stream_pause(events, stream_id, &mut chunk_buffer).await;
+                if let Some(tail) = chunk_buffer.take() {
+                    emit_stream_chunk(events, stream_id, &tail).await;
+                }
+                if let Some(partial_reply) = partial_reply.take() {
+                    partial_reply.finish(&accumulated_response);
+                }
+                let truncated = ollama_response.done_reason.as_deref() == Some("length");
+                if truncated {
+                    info!("✂️ Stream {} stopped at the output token limit", stream_id);
+                }
+                // Prefer Ollama's own counters, which exclude time spent loading the model
+                let tokens_per_second = match (ollama_response.eval_count, ollama_response.eval_duration) {
+                    (Some(count), Some(duration)) if duration > 0 => Some(count as f64 / (duration as f64 / 1e9)),
+                    _ => None,
+                };
+                let stats = StreamStats {
+                    tokens: ollama_response.eval_count.map_or(token_count, |count| count as usize),
+                    duration_ms: started.elapsed().as_millis() as u64,
+                    tokens_per_second,
+                    truncated,
+                    context_truncated: config.provider == LlmProvider::Ollama
+                        && prompt_hit_context_limit(&config.model, ollama_response.prompt_eval_count).await,
+                };
+                emit_stream_complete(events, stream_id, stats, StreamMethod::Ollama, None).await;
+                return Ok(());
+            }
+            Some(Err(e)) => {
+                if let Some(tail) = chunk_buffer.take() {
+                    emit_stream_chunk(events, stream_id, &tail).await;
+                }
+                if let Some(partial_reply) = partial_reply.as_mut() {
+                    partial_reply.save(&accumulated_response);
+                }
+                emit_stream_error(events, stream_id, e.code, &e.message).await;
+                return Err(e.message);
             }
+            None => break,
         }
     }
 
     // If we reach here without completion, emit what we have
+    if let Some(tail) = chunk_buffer.take() {
+        emit_stream_chunk(events, stream_id, &tail).await;
+    }
     if !accumulated_response.is_empty() {
         info!("✅ Stream ended, emitting final response");
-        emit_stream_complete(app_handle, stream_id).await;
+        if let Some(partial_reply) = partial_reply.take() {
+            partial_reply.finish(&accumulated_response);
+        }
+        let stats = StreamStats {
+            tokens: token_count,
+            duration_ms: started.elapsed().as_millis() as u64,
+            ..StreamStats::default()
+        };
+        emit_stream_complete(events, stream_id, stats, StreamMethod::Ollama, None).await;
         Ok(())
     } else {
         let error_msg = "No response received from Ollama";
         error!("❌ {}", error_msg);
-        emit_stream_error(app_handle, stream_id, error_msg).await;
+        emit_stream_error(events, stream_id, "empty_response", error_msg).await;
         Err(error_msg.to_string())
     }
 }
 
-// Fallback streaming (simulate streaming for non-streaming APIs)
-async fn stream_fallback_response(app_handle: &AppHandle, stream_id: &str, prompt: &str) -> Result<(), String> {
-    info!("🔄 Using fallback streaming for: {}", stream_id);
+/// Byte offset where `stop_regex` first matches in `text`, looking only at matches
+/// that can involve the newest `new_len` bytes.
+fn stop_regex_cut(stop_regex: &regex::Regex, text: &str, new_len: usize) -> Option<usize> {
+    let mut start = text.len().saturating_sub(new_len + STOP_REGEX_WINDOW);
+    while !text.is_char_boundary(start) {
+        start += 1;
+    }
+    stop_regex.find_at(text, start).map(|m| m.start())
+}
 
-    // Generate response using existing robust method
-    let client = LLMClient::new();
-    match client.generate_response_robust(prompt.to_string()).await {
-        Ok(response) => {
-            // Simulate streaming by sending chunks
-            let words: Vec<&str> = response.split_whitespace().collect();
-            let chunk_size = 1; // Send 1 word at a time for better streaming effect
+/// The phrase `text` ends in if its last words are one n-gram (up to
+/// `REPETITION_MAX_NGRAM` words) repeated `threshold` times back to back.
+fn detect_repetition(text: &str, threshold: usize) -> Option<String> {
+    let threshold = threshold.max(MIN_REPETITION_THRESHOLD);
+    // Newest word first; only as many as the longest possible loop needs
+    let tail: Vec<&str> = text.split_whitespace().rev().take(REPETITION_MAX_NGRAM * threshold).collect();
+
+    (1..=REPETITION_MAX_NGRAM)
+        .filter(|n| n * threshold <= tail.len())
+        .find(|&n| (n..n * threshold).all(|i| tail[i] == tail[i % n]))
+        .map(|n| tail[..n].iter().rev().copied().collect::<Vec<_>>().join(" "))
+}
+
+// Turn the repetition guard on with a threshold, or off with None
+#[tauri::command]
+pub async fn set_repetition_guard(threshold: Option<usize>) -> Result<(), String> {
+    if threshold.map_or(false, |t| t < MIN_REPETITION_THRESHOLD) {
+        return Err(format!("Repetition threshold must be at least {}", MIN_REPETITION_THRESHOLD));
+    }
 
-            info!("🔄 Fallback streaming {} words in {} chunks", words.len(), words.chunks(chunk_size).len());
+    info!("⚙️ Repetition guard threshold set to {:?}", threshold);
+    crate::settings::update(|settings| settings.repetition_guard_threshold = threshold)?;
+    Ok(())
+}
 
-            for (i, chunk) in words.chunks(chunk_size).enumerate() {
-                let chunk_text = chunk.join(" ") + " ";
-                info!("📤 Fallback chunk {}: '{}'", i + 1, chunk_text);
-                emit_stream_chunk(app_handle, stream_id, &chunk_text).await;
+/// Apply a pause or resume requested through the registry since the last check.
+async fn sync_stream_pause(events: &dyn EventSink, stream_id: &str, chunk_buffer: &mut ChunkBuffer) {
+    let paused = stream_paused(stream_id);
+    if paused == chunk_buffer.is_paused() {
+        return;
+    }
 
-                // Shorter delay for more responsive streaming
-                tokio::time::sleep(Duration::from_millis(80)).await;
-            }
+    if paused {
+        info!("⏸️ Stream {} paused", stream_id);
+        chunk_buffer.pause();
+        emit_stream_event(events, stream_id, StreamPayload::Paused);
+    } else {
+        info!("▶️ Stream {} resumed", stream_id);
+        emit_stream_event(events, stream_id, StreamPayload::Resumed);
+        if let Some(held) = chunk_buffer.resume() {
+            emit_stream_chunk(events, stream_id, &held).await;
+        }
+        update_stream_progress(stream_id, None, chunk_buffer);
+    }
+}
+
+/// Batches streamed tokens according to a `StreamFlush` policy.
+pub struct ChunkBuffer {
+    flush: StreamFlush,
+    pending: String,
+    pending_tokens: usize,
+    last_flush: std::time::Instant,
+    paused: bool,
+    flushes: usize,
+}
+
+impl ChunkBuffer {
+    pub fn new(flush: StreamFlush) -> Self {
+        Self {
+            flush,
+            pending: String::new(),
+            pending_tokens: 0,
+            last_flush: std::time::Instant::now(),
+            paused: false,
+            flushes: 0,
+        }
+    }
+
+    /// Hold every token until `resume`.
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
 
-            emit_stream_complete(app_handle, stream_id).await;
+    /// Stop holding tokens, returning everything held while paused.
+    pub fn resume(&mut self) -> Option<String> {
+        self.paused = false;
+        self.take()
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Tokens pushed since the last emitted batch.
+    pub fn pending_tokens(&self) -> usize {
+        self.pending_tokens
+    }
+
+    /// Bytes of text waiting in the buffer.
+    pub fn pending_len(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Drop the buffered text without emitting it.
+    pub fn discard(&mut self) {
+        self.pending.clear();
+        self.pending_tokens = 0;
+    }
+
+    /// Batches handed out so far.
+    pub fn flushes(&self) -> usize {
+        self.flushes
+    }
+
+    /// Add a token; returns the batched text when it's time to emit.
+    ///
+    /// The interval is only checked as tokens arrive, so a stalled stream holds
+    /// its partial batch until the next token or `take`.
+    pub fn push(&mut self, token: &str) -> Option<String> {
+        self.pending.push_str(token);
+        self.pending_tokens += 1;
+        if self.paused {
+            return None;
+        }
+
+        let interval_elapsed = self
+            .flush
+            .interval_ms
+            .map_or(false, |ms| self.last_flush.elapsed() >= Duration::from_millis(ms));
+        if self.pending_tokens >= self.flush.tokens.max(1) || interval_elapsed {
+            self.take()
+        } else {
+            None
+        }
+    }
+
+    /// Whatever is buffered, so the tail isn't dropped when the stream ends.
+    pub fn take(&mut self) -> Option<String> {
+        self.pending_tokens = 0;
+        self.last_flush = std::time::Instant::now();
+        if self.pending.is_empty() {
+            None
+        } else {
+            self.flushes += 1;
+            Some(std::mem::take(&mut self.pending))
+        }
+    }
+}
+
+// Fallback streaming (simulate streaming for non-streaming APIs).
+// Finishes the chat message the Ollama attempt started, if any.
+async fn stream_fallback_response(
+    events: &dyn EventSink,
+    config: &StreamConfig,
+    prompt: &str,
+    fallback_reason: &str,
+    partial_reply: Option<crate::chat_store::PartialReplyWriter>,
+) -> Result<(), String> {
+    let stream_id = config.stream_id.as_str();
+    info!("🔄 Using fallback streaming for: {}", stream_id);
+
+    // Generate response using existing robust method, with this stream's own model
+    let client = match LLMClient::with_config(LLMConfig {
+        model: config.model.clone(),
+        base_url: config.base_url.clone(),
+        options: config.options.clone(),
+        ..LLMConfig::default()
+    }) {
+        Ok(client) => client,
+        Err(e) => {
+            if let Some(partial_reply) = partial_reply {
+                partial_reply.abandon();
+            }
+            emit_stream_error(events, stream_id, "generation_failed", &e).await;
+            return Err(e);
+        }
+    };
+    match client.generate_response_robust(prompt.to_string()).await {
+        Ok(response) => {
+            if let Some(partial_reply) = partial_reply {
+                partial_reply.finish(&response);
+            }
+            // Simulate streaming by sending the reply word by word
+            let stats = replay_as_stream(events, stream_id, &response).await;
+            emit_stream_complete(events, stream_id, stats, StreamMethod::Fallback, Some(fallback_reason)).await;
             Ok(())
         }
         Err(e) => {
+            if let Some(partial_reply) = partial_reply {
+                partial_reply.abandon();
+            }
             let error_msg = format!("LLM generation failed: {}", e);
-            emit_stream_error(app_handle, stream_id, &error_msg).await;
+            emit_stream_error(events, stream_id, "generation_failed", &error_msg).await;
             Err(error_msg)
         }
     }
 }
 
 // Emit functions with proper Tauri event emission
-async fn emit_stream_chunk(app_handle: &AppHandle, stream_id: &str, chunk: &str) {
-    info!("📤 Emitting chunk for {}: '{}'", stream_id, chunk);
-
+fn emit_stream_event(events: &dyn EventSink, stream_id: &str, payload: StreamPayload) {
     let event = StreamEvent {
+        schema_version: STREAM_EVENT_SCHEMA_VERSION,
         stream_id: stream_id.to_string(),
-        event_type: "chunk".to_string(),
-        data: chunk.to_string(),
+        payload,
     };
 
     // Use consistent event name for all streaming events
-    let event_name = "llm-stream-event";
-    if let Err(e) = app_handle.emit(event_name, &event) {
-        error!("❌ Failed to emit stream chunk: {}", e);
-    } else {
-        info!("✅ Successfully emitted chunk event: {}", event_name);
-    }
+    emit_json(events, STREAM_EVENT_NAME, &event);
 }
 
-async fn emit_stream_complete(app_handle: &AppHandle, stream_id: &str) {
-    info!("✅ Stream complete for: {}", stream_id);
-
-    let event = StreamEvent {
-        stream_id: stream_id.to_string(),
-        event_type: "complete".to_string(),
-        data: "".to_string(),
-    };
+async fn emit_stream_chunk(events: &dyn EventSink, stream_id: &str, chunk: &str) {
+    if crate::settings::verbose_llm_logging() {
+        info!("📤 Emitting chunk for {}: '{}'", stream_id, chunk);
+    }
+    emit_stream_event(events, stream_id, StreamPayload::Chunk { text: chunk.to_string() });
+}
 
-    // Use consistent event name for all streaming events
-    let event_name = "llm-stream-event";
-    if let Err(e) = app_handle.emit(event_name, &event) {
-        error!("❌ Failed to emit stream complete: {}", e);
-    } else {
-        info!("✅ Successfully emitted complete event: {}", event_name);
+async fn emit_stream_complete(
+    events: &dyn EventSink,
+    stream_id: &str,
+    stats: StreamStats,
+    method: StreamMethod,
+    fallback_reason: Option<&str>,
+) {
+    info!(
+        "✅ Stream complete for: {} ({} tokens in {}ms via {:?})",
+        stream_id, stats.tokens, stats.duration_ms, method
+    );
+    if let Some(reason) = fallback_reason {
+        info!("🔍 {} used fallback streaming because: {}", stream_id, reason);
     }
+    track_unacked_complete(stream_id, &stats, method, fallback_reason);
+    emit_stream_event(events, stream_id, StreamPayload::Complete {
+        stats,
+        method,
+        fallback_reason: fallback_reason.map(str::to_string),
+    });
 }
 
-async fn emit_stream_error(app_handle: &AppHandle, stream_id: &str, error: &str) {
+async fn emit_stream_error(events: &dyn EventSink, stream_id: &str, code: &str, error: &str) {
     error!("❌ Stream error for {}: {}", stream_id, error);
-
-    let event = StreamEvent {
-        stream_id: stream_id.to_string(),
-        event_type: "error".to_string(),
-        data: error.to_string(),
-    };
-
-    // Use consistent event name for all streaming events
-    let event_name = "llm-stream-event";
-    if let Err(e) = app_handle.emit(event_name, &event) {
-        error!("❌ Failed to emit stream error: {}", e);
-    } else {
-        info!("✅ Successfully emitted error event: {}", event_name);
-    }
+    emit_stream_event(events, stream_id, StreamPayload::Error {
+        code: code.to_string(),
+        message: error.to_string(),
+    });
 }
 
 #[cfg(test)]
@@ -731,10 +3365,366 @@ mod tests {
 
     #[test]
     fn test_empty_prompt_error() {
-        let client = LLMClient::new();
+        let client = LLMClient::new().unwrap();
         let rt = tokio::runtime::Runtime::new().unwrap();
         
         let result = rt.block_on(client.generate_response("".to_string()));
         assert!(matches!(result, Err(LLMError::EmptyPrompt)));
     }
+
+    #[test]
+    fn test_stream_event_is_tagged_by_type() {
+        let event = StreamEvent {
+            schema_version: STREAM_EVENT_SCHEMA_VERSION,
+            stream_id: "s1".to_string(),
+            payload: StreamPayload::Error { code: "ollama_error".to_string(), message: "boom".to_string() },
+        };
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!(json["type"], "error");
+        assert_eq!(json["code"], "ollama_error");
+        assert_eq!(json["message"], "boom");
+        assert_eq!(json["stream_id"], "s1");
+        assert_eq!(json["schema_version"], STREAM_EVENT_SCHEMA_VERSION);
+
+        let chunk: StreamEvent = serde_json::from_str(r#"{"schema_version":2,"stream_id":"s1","type":"first_token","ms":42}"#).unwrap();
+        assert!(matches!(chunk.payload, StreamPayload::FirstToken { ms: 42 }));
+    }
+
+    #[test]
+    fn test_is_model_loading() {
+        assert!(is_model_loading(503, ""));
+        assert!(is_model_loading(500, "{\"error\":\"Loading model, please wait\"}"));
+        assert!(!is_model_loading(500, "llama runner process has terminated"));
+        assert!(!is_model_loading(404, "model 'gemma3n' not found"));
+    }
+
+    // Collects stream events instead of sending them to a window
+    #[derive(Default)]
+    struct RecordingSink {
+        events: std::sync::Mutex<Vec<StreamEvent>>,
+    }
+
+    impl EventSink for RecordingSink {
+        fn emit_event(&self, event_name: &str, payload: serde_json::Value) {
+            if event_name == STREAM_EVENT_NAME {
+                self.events.lock().unwrap().push(serde_json::from_value(payload).unwrap());
+            }
+        }
+    }
+
+    impl RecordingSink {
+        fn payloads(&self, stream_id: &str) -> Vec<StreamPayload> {
+            self.events
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|event| event.stream_id == stream_id)
+                .map(|event| event.payload.clone())
+                .collect()
+        }
+
+        fn text(&self, stream_id: &str) -> String {
+            self.payloads(stream_id)
+                .into_iter()
+                .filter_map(|payload| match payload {
+                    StreamPayload::Chunk { text } => Some(text),
+                    _ => None,
+                })
+                .collect()
+        }
+    }
+
+    // Stand-in for Ollama's /api/generate: "model-a" streams three tokens and
+    // finishes, any other model sends one token and then stalls
+    async fn spawn_fake_ollama() -> String {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                tokio::spawn(async move {
+                    let mut request = Vec::new();
+                    let mut buf = [0u8; 4096];
+                    let body = loop {
+                        let n = socket.read(&mut buf).await.unwrap_or(0);
+                        if n == 0 {
+                            return;
+                        }
+                        request.extend_from_slice(&buf[..n]);
+                        let text = String::from_utf8_lossy(&request).to_string();
+                        if let Some(split) = text.find("\r\n\r\n") {
+                            let length = text[..split]
+                                .lines()
+                                .find_map(|line| {
+                                    let line = line.to_lowercase();
+                                    line.strip_prefix("content-length:").and_then(|v| v.trim().parse::<usize>().ok())
+                                })
+                                .unwrap_or(0);
+                            if request.len() >= split + 4 + length {
+                                break text[split + 4..].to_string();
+                            }
+                        }
+                    };
+
+                    let model = serde_json::from_str::<serde_json::Value>(&body).unwrap()["model"]
+                        .as_str()
+                        .unwrap()
+                        .to_string();
+                    let (tokens, stall) = if model == "model-a" {
+                        (vec!["a1 ", "a2 ", "a3"], false)
+                    } else {
+                        (vec!["b1 "], true)
+                    };
+
+                    let header = "HTTP/1.1 200 OK\r\nContent-Type: application/x-ndjson\r\nConnection: close\r\n\r\n";
+                    let _ = socket.write_all(header.as_bytes()).await;
+                    for token in tokens {
+                        let line = serde_json::json!({ "model": model, "created_at": "", "response": token, "done": false });
+                        let _ = socket.write_all(format!("{}\n", line).as_bytes()).await;
+                        tokio::time::sleep(Duration::from_millis(20)).await;
+                    }
+                    if stall {
+                        tokio::time::sleep(Duration::from_secs(30)).await;
+                        return;
+                    }
+                    let done = serde_json::json!({ "model": model, "created_at": "", "response": "", "done": true, "done_reason": "stop" });
+                    let _ = socket.write_all(format!("{}\n", done).as_bytes()).await;
+                });
+            }
+        });
+
+        base_url
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_streams_are_independent() {
+        let base_url = spawn_fake_ollama().await;
+        let sink = RecordingSink::default();
+        let config = |stream_id: &str, model: &str| StreamConfig {
+            stream_id: stream_id.to_string(),
+            model: model.to_string(),
+            base_url: base_url.clone(),
+            options: None,
+            chat_id: None,
+            flush_every: StreamFlush::default(),
+            model_load_wait_seconds: 0,
+            timeout_seconds: 10,
+            system_prompt_source: crate::settings::SystemPromptSource::None,
+            stop_regex: None,
+            repetition_threshold: None,
+            provider: LlmProvider::Ollama,
+            api_key: None,
+        };
+
+        let token_a = crate::cancellation::register("llm-stream:stream-a");
+        let token_b = crate::cancellation::register("llm-stream:stream-b");
+        let stream_a = stream_llm_response(&sink, config("stream-a", "model-a"), "hello".to_string(), token_a);
+        let stream_b = stream_llm_response(&sink, config("stream-b", "model-b"), "hello".to_string(), token_b);
+        let cancel_b = async {
+            // Once B is generating, it is registered with its own model; stop only B
+            while sink.text("stream-b").is_empty() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+            let stream = active_streams().into_iter().find(|s| s.stream_id == "stream-b").unwrap();
+            assert_eq!(stream.model, "model-b");
+            assert!(crate::cancellation::cancel("llm-stream:stream-b"));
+        };
+
+        let (a, b, _) = tokio::time::timeout(Duration::from_secs(10), async { tokio::join!(stream_a, stream_b, cancel_b) })
+            .await
+            .expect("streams did not finish");
+        assert!(a.is_ok());
+        assert!(b.is_ok());
+
+        // Each stream only saw its own model's tokens
+        assert_eq!(sink.text("stream-a"), "a1 a2 a3");
+        assert_eq!(sink.text("stream-b"), "b1 ");
+
+        let a_events = sink.payloads("stream-a");
+        assert!(matches!(a_events.last(), Some(StreamPayload::Complete { .. })));
+        assert!(!a_events.iter().any(|p| matches!(p, StreamPayload::Cancelled { .. })));
+
+        let b_events = sink.payloads("stream-b");
+        assert!(matches!(b_events.last(), Some(StreamPayload::Cancelled { partial }) if partial == "b1 "));
+        assert!(!b_events.iter().any(|p| matches!(p, StreamPayload::Complete { .. })));
+
+        assert!(active_streams().iter().all(|s| s.stream_id != "stream-a" && s.stream_id != "stream-b"));
+    }
+
+    #[test]
+    fn test_chunk_buffer_batches_and_flushes_tail() {
+        let mut buffer = ChunkBuffer::new(StreamFlush { tokens: 3, interval_ms: None });
+        assert_eq!(buffer.push("a"), None);
+        assert_eq!(buffer.push("b"), None);
+        assert_eq!(buffer.push("c"), Some("abc".to_string()));
+        assert_eq!(buffer.push("d"), None);
+        assert_eq!(buffer.take(), Some("d".to_string()));
+        assert_eq!(buffer.take(), None);
+
+        // Default policy emits every token
+        let mut per_token = ChunkBuffer::new(StreamFlush::default());
+        assert_eq!(per_token.push("x"), Some("x".to_string()));
+    }
+
+    #[test]
+    fn test_responsiveness_thresholds() {
+        assert_eq!(Responsiveness::from_latency_ms(0), Responsiveness::Fast);
+        assert_eq!(Responsiveness::from_latency_ms(RESPONSIVENESS_SLOW_MS - 1), Responsiveness::Fast);
+        assert_eq!(Responsiveness::from_latency_ms(RESPONSIVENESS_SLOW_MS), Responsiveness::Slow);
+        assert_eq!(Responsiveness::from_latency_ms(RESPONSIVENESS_VERY_SLOW_MS), Responsiveness::VerySlow);
+    }
+
+    #[test]
+    fn test_detect_repetition() {
+        assert_eq!(detect_repetition("I think that is right.", 3), None);
+        assert_eq!(
+            detect_repetition("Sure. and then and then and then", 3),
+            Some("and then".to_string())
+        );
+        // One short of the threshold
+        assert_eq!(detect_repetition("ok ok ok ok", 5), None);
+        assert_eq!(detect_repetition("ok ok ok ok ok", 5), Some("ok".to_string()));
+        // The loop has to reach the end of the text
+        assert_eq!(detect_repetition("a b a b a b c", 3), None);
+    }
+
+    #[test]
+    fn test_stop_regex_cut() {
+        let fence = regex::Regex::new("```\\s*$").unwrap();
+        assert_eq!(stop_regex_cut(&fence, "```rust\nfn a() {}\n", 3), None);
+        assert_eq!(stop_regex_cut(&fence, "```rust\nfn a() {}\n```", 3), Some(18));
+
+        // A match starting before the newest token is still found
+        let sentinel = regex::Regex::new("<END>").unwrap();
+        assert_eq!(stop_regex_cut(&sentinel, "héllo <EN", 3), None);
+        assert_eq!(stop_regex_cut(&sentinel, "héllo <END> more", 7), Some(7));
+    }
+
+    #[test]
+    fn test_chunk_buffer_holds_tokens_while_paused() {
+        let mut buffer = ChunkBuffer::new(StreamFlush::default());
+        buffer.pause();
+        assert_eq!(buffer.push("a"), None);
+        assert_eq!(buffer.push("b"), None);
+        assert_eq!(buffer.resume(), Some("ab".to_string()));
+        assert_eq!(buffer.push("c"), Some("c".to_string()));
+    }
+
+    #[test]
+    fn test_json_line_decoder_multibyte_split() {
+        let line = "{\"response\":\"héllo 😀\",\"done\":false}\n".as_bytes();
+        // Split in the middle of the 4-byte emoji
+        let split_at = line.iter().position(|&b| b == 0xF0).unwrap() + 2;
+
+        let mut decoder = JsonLineDecoder::new();
+        assert!(decoder.push(&line[..split_at]).is_empty());
+        let lines = decoder.push(&line[split_at..]);
+
+        assert_eq!(lines.len(), 1);
+        let parsed: serde_json::Value = serde_json::from_str(&lines[0]).unwrap();
+        assert_eq!(parsed["response"], "héllo 😀");
+        assert!(decoder.finish().is_none());
+    }
+
+    #[test]
+    fn test_parse_stream_line_sse_framing() {
+        let line = "data: {\"model\":\"m\",\"created_at\":\"t\",\"response\":\"hi\",\"done\":false}";
+        assert!(matches!(parse_stream_line(line), StreamLine::Response(r) if r.response == "hi"));
+        assert!(matches!(parse_stream_line("data: [DONE]"), StreamLine::Skip));
+        assert!(matches!(parse_stream_line("event: message"), StreamLine::Skip));
+        assert!(matches!(parse_stream_line("{\"error\":\"model not found\"}"), StreamLine::Error(e) if e == "model not found"));
+        assert!(matches!(parse_stream_line("{"), StreamLine::Unparsed));
+    }
+
+    #[test]
+    fn test_parse_stream_line_openai_chunks() {
+        let chunk = "data: {\"model\":\"m\",\"choices\":[{\"delta\":{\"content\":\"hi\"},\"finish_reason\":null}]}";
+        assert!(matches!(parse_stream_line(chunk), StreamLine::Response(r) if r.response == "hi" && !r.done));
+        let last = "data: {\"model\":\"m\",\"choices\":[{\"delta\":{},\"finish_reason\":\"length\"}]}";
+        assert!(matches!(parse_stream_line(last), StreamLine::Response(r) if r.done && r.done_reason.as_deref() == Some("length")));
+        let error = "{\"error\":{\"message\":\"bad key\"}}";
+        assert!(matches!(parse_stream_line(error), StreamLine::Error(e) if e == "bad key"));
+
+        assert_eq!(generation_url(LlmProvider::OpenaiCompatible, "http://h:8080/v1/"), "http://h:8080/v1/chat/completions");
+        assert_eq!(generation_url(LlmProvider::OpenaiCompatible, "http://h:8080"), "http://h:8080/v1/chat/completions");
+    }
+
+    #[test]
+    fn test_json_line_decoder_trailing_line() {
+        let mut decoder = JsonLineDecoder::new();
+        let lines = decoder.push(b"{\"a\":1}\n\n{\"b\":2}");
+        assert_eq!(lines, vec!["{\"a\":1}".to_string()]);
+        assert_eq!(decoder.finish(), Some("{\"b\":2}".to_string()));
+    }
+
+    #[test]
+    fn test_sanitize_prompt() {
+        assert_eq!(sanitize_prompt("hello\0 world"), "hello world");
+        assert_eq!(sanitize_prompt("zero\u{200B}width\u{FEFF} text"), "zerowidth text");
+        assert_eq!(sanitize_prompt("line one\r\n\tline two\u{2028}three"), "line one\r\n\tline two\nthree");
+        assert_eq!(sanitize_prompt("esc\u{1b}[31mred\u{7f}"), "esc[31mred");
+        assert_eq!(sanitize_prompt("bidi \u{202E}txet"), "bidi txet");
+        // Joiners inside emoji sequences survive
+        assert_eq!(sanitize_prompt("👩\u{200D}💻 ok"), "👩\u{200D}💻 ok");
+
+        // The sanitized prompt serializes and round-trips cleanly
+        let json = serde_json::to_string(&sanitize_prompt("a\0b\u{200B}c")).unwrap();
+        assert_eq!(serde_json::from_str::<String>(&json).unwrap(), "abc");
+    }
+
+    #[tokio::test]
+    async fn test_decode_token_stream() {
+        let body = |chunks: Vec<&'static str>| -> ResponseBody {
+            Box::pin(futures_util::stream::iter(chunks.into_iter().map(|c| Ok(Bytes::from(c)))))
+        };
+
+        let tokens: Vec<_> = decode_token_stream(body(vec![
+            "{\"model\":\"m\",\"created_at\":\"\",\"response\":\"Hel\",\"done\":false}\n{\"model\":\"m\",",
+            "\"created_at\":\"\",\"response\":\"lo\",\"done\":false}\n",
+            "{\"model\":\"m\",\"created_at\":\"\",\"response\":\"\",\"done\":true,\"eval_count\":2}\n",
+            "{\"model\":\"m\",\"created_at\":\"\",\"response\":\"ignored\",\"done\":false}\n",
+        ]))
+        .collect()
+        .await;
+        assert_eq!(tokens.len(), 3);
+        assert!(matches!(&tokens[0], Ok(StreamToken::Text(t)) if t == "Hel"));
+        assert!(matches!(&tokens[1], Ok(StreamToken::Text(t)) if t == "lo"));
+        assert!(matches!(&tokens[2], Ok(StreamToken::Done(r)) if r.eval_count == Some(2)));
+
+        // A pretty-printed one-shot body still yields its text
+        let tokens: Vec<_> = decode_token_stream(body(vec![
+            "{\n  \"model\": \"m\",\n  \"created_at\": \"\",\n",
+            "  \"response\": \"Hi\",\n  \"done\": true\n}",
+        ]))
+        .collect()
+        .await;
+        assert_eq!(tokens.len(), 1);
+        assert!(matches!(&tokens[0], Ok(StreamToken::Text(t)) if t == "Hi"));
+
+        let tokens: Vec<_> = decode_token_stream(body(vec!["{\"error\":\"model not found\"}\n"])).collect().await;
+        assert!(matches!(&tokens[..], [Err(e)] if e.code == "ollama_error"));
+
+        let tokens: Vec<_> = decode_token_stream(body(vec!["<html>\n<body>502 Bad Gateway</body>\n</html>\n"])).collect().await;
+        assert!(matches!(&tokens[..], [Err(e)] if e.code == "non_json_response" && e.message.contains("502 Bad Gateway")));
+    }
+
+    #[test]
+    fn test_non_json_response_error() {
+        let url = "http://proxy/api/generate";
+        let message = non_json_response_error(url, Some("text/html; charset=utf-8"), "<html>\n  <h1>502 Bad Gateway</h1>\n</html>").unwrap();
+        assert!(message.contains(url));
+        assert!(message.contains("<html> <h1>502 Bad Gateway</h1> </html>"));
+
+        assert!(non_json_response_error(url, None, "  <!DOCTYPE html>").is_some());
+        assert!(non_json_response_error(url, Some("text/plain"), "Bad Gateway").is_some());
+        assert!(non_json_response_error(url, Some("application/json"), "{\"response\":\"hi\"}").is_none());
+        assert!(non_json_response_error(url, Some("text/plain"), "{\"response\":\"hi\"}").is_none());
+        assert!(non_json_response_error(url, None, "not json but not html").is_none());
+
+        let long = format!("<p>{}</p>", "x".repeat(500));
+        let message = non_json_response_error(url, None, &long).unwrap();
+        assert!(message.len() < 500);
+    }
 }
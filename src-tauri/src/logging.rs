@@ -0,0 +1,59 @@
+//! Process-wide logger.
+//!
+//! Records still go to stderr through env_logger (honouring `RUST_LOG`), and the
+//! most recent info-and-above lines are also kept in memory so a diagnostics
+//! bundle can include them without the app writing log files to disk.
+
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use chrono::Utc;
+use log::{LevelFilter, Log, Metadata, Record};
+
+// Oldest lines are dropped past this size
+const MAX_RECENT_LINES: usize = 2000;
+const RECENT_LEVEL: LevelFilter = LevelFilter::Info;
+
+static RECENT_LINES: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+
+struct RecentLogger {
+    stderr: env_logger::Logger,
+}
+
+impl Log for RecentLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= RECENT_LEVEL || self.stderr.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        if self.stderr.matches(record) {
+            self.stderr.log(record);
+        }
+        if record.level() <= RECENT_LEVEL {
+            let line = format!("{} {} {}: {}", Utc::now().to_rfc3339(), record.level(), record.target(), record.args());
+            let mut lines = RECENT_LINES.lock().unwrap();
+            if lines.len() >= MAX_RECENT_LINES {
+                lines.pop_front();
+            }
+            lines.push_back(line);
+        }
+    }
+
+    fn flush(&self) {
+        self.stderr.flush();
+    }
+}
+
+/// Install the logger; call once at startup in place of `env_logger::init`.
+pub fn init() {
+    let stderr = env_logger::Builder::from_default_env().build();
+    let max_level = stderr.filter().max(RECENT_LEVEL);
+    match log::set_boxed_logger(Box::new(RecentLogger { stderr })) {
+        Ok(()) => log::set_max_level(max_level),
+        Err(e) => eprintln!("Logger already initialized: {}", e),
+    }
+}
+
+/// The most recent log lines, oldest first.
+pub fn recent_lines() -> Vec<String> {
+    RECENT_LINES.lock().unwrap().iter().cloned().collect()
+}
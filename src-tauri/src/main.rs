@@ -1,7 +1,6 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-use env_logger;
 use log::{info, warn};
 use tauri::Manager;
 
@@ -12,6 +11,11 @@ mod python_backend;
 mod dashboard_api;
 mod web_integration;
 mod windows_integration;
+mod settings;
+mod chat_store;
+mod network;
+mod cancellation;
+mod logging;
 
 use commands::*;
 use llm::*;
@@ -24,7 +28,7 @@ fn ping() -> String {
 }
 
 fn main() {
-    env_logger::init();
+    logging::init();
     info!("Starting Privacy AI Assistant");
 
     tauri::Builder::default()
@@ -35,6 +39,11 @@ fn main() {
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_os::init())
         .setup(|app| {
+            match app.path().app_config_dir() {
+                Ok(config_dir) => settings::init(config_dir),
+                Err(e) => warn!("⚠️ Could not resolve app config dir, settings will not persist: {}", e),
+            }
+
             let window = app.get_webview_window("main").unwrap();
 
             // Configure window for desktop experience
@@ -50,6 +59,28 @@ fn main() {
                 // Event handling will be added later if needed
             }
 
+            // Clear out temp audio left behind by earlier sessions
+            std::thread::spawn(|| {
+                cleanup_temp_audio_files(std::time::Duration::from_secs(TEMP_AUDIO_MAX_AGE_SECS));
+            });
+
+            // Optionally confirm stream events are delivered before the first chat
+            if settings::get().verify_stream_pipeline_on_startup {
+                let app_handle = app.handle().clone();
+                tauri::async_runtime::spawn(async move {
+                    llm::check_stream_pipeline(&app_handle).await;
+                });
+            }
+
+            if settings::get().warm_up_stt_on_startup {
+                let app_handle = app.handle().clone();
+                tauri::async_runtime::spawn(async move {
+                    if let Err(e) = stt_tts::warm_up_stt(app_handle).await {
+                        warn!("⚠️ STT warm-up failed: {}", e);
+                    }
+                });
+            }
+
             // Initialize Windows-specific features (simplified for initial build)
             info!("Windows desktop mode initialized");
 
@@ -64,21 +95,57 @@ fn main() {
             log_message,
             test_tauri_connection,
             get_diagnostic_info,
+            export_diagnostics_bundle,
+            get_effective_config,
+            reset_all_data,
 
             // LLM commands
             invoke_llm_prompt,
             generate_llm_response,
+            compare_models,
+            generate_with_model_fallback,
+            check_context_fits,
+            estimate_system_prompt_cost,
             check_llm_health,
+            diagnose_llm_setup,
+            get_active_model,
+            set_active_model,
+            add_llm_endpoint,
+            list_llm_endpoints,
+            set_active_llm_endpoint,
+            remove_llm_endpoint,
+            test_ollama_endpoint,
+            measure_ollama_responsiveness,
+            generate_with_schema,
+            set_stream_flush,
             check_ollama_service, // New diagnostic command
             test_gemma_model,     // New diagnostic command
             start_llm_stream,
             stop_llm_stream,
+            ack_stream_complete,
+            sanitize_prompt_text,
+            get_unacked_streams,
+            generate_auto,
+            pause_llm_stream,
+            resume_llm_stream,
+            get_stream_buffer_status,
+            set_llm_timeouts,
+            set_max_concurrent_streams,
+            get_fallback_word_delay,
+            set_fallback_word_delay,
+            set_repetition_guard,
+            list_active_streams,
             test_streaming,
+            verify_stream_pipeline,
 
             // Python backend commands
             start_python_backend,
             stop_python_backend,
+            cancel_backend_startup,
+            get_backend_state,
             check_python_backend,
+            start_health_monitor,
+            stop_health_monitor,
             send_llm_request_to_backend,
             get_ollama_models_from_backend,
 
@@ -90,11 +157,29 @@ fn main() {
             delete_chat_session,
             add_message_to_chat,
             get_chat_context,
+            fork_chat_session,
+            import_openai_conversation,
+            export_chat_as_text,
+            get_chat_statistics,
+            trim_chat_history,
+            verify_chat_store,
+            edit_message,
+            get_chat_system_prompt,
+            set_chat_system_prompt,
+            get_chat_generation_options,
+            set_chat_generation_options,
 
             // Hardware detection commands
             get_hardware_info,
+            detect_gpus,
+            apply_hardware_timeout_profile,
             get_runtime_config,
             refresh_hardware_detection,
+            recommend_generation_settings,
+            check_model_fits_gpu,
+            get_benchmark_context,
+            get_storage_info,
+            get_ollama_models_dir,
 
             // Context-aware LLM commands
             generate_chat_llm_response,
@@ -103,8 +188,21 @@ fn main() {
             run_vosk_stt,
             run_piper_tts,
             get_tts_config,
+            get_selected_tts_voice,
+            test_tts_render,
             set_tts_config,
             test_audio_devices,
+            diagnose_audio,
+            cleanup_temp_audio,
+            segment_speakable_text,
+            speak_llm_reply,
+            speak_text,
+            stop_speaking,
+            benchmark_stt,
+            set_max_recording_secs,
+            get_input_gain,
+            set_input_gain,
+            check_ffmpeg,
             test_stt_debug,
             test_path_escaping,
             test_static_file_stt,
@@ -112,21 +210,55 @@ fn main() {
             process_audio_data,
             vosk_transcribe,
             test_vosk_installation,
+            start_vosk_stream,
+            stop_vosk_stream,
+            list_vosk_models,
+            get_vosk_paths,
+            download_vosk_model,
+            delete_vosk_model,
             stt_tts::start_continuous_voice_chat,
+            stt_tts::stop_continuous_voice_chat,
+            stt_tts::warm_up_stt,
+            stt_tts::voice_message_to_chat,
+            stt_tts::stop_voice_message,
+            stt_tts::transcribe_to_draft,
+            stt_tts::stop_draft_dictation,
 
             // Dashboard API commands
             dashboard_api::generate_dashboard_token,
             dashboard_api::validate_dashboard_token,
+            dashboard_api::check_clock_sanity,
             dashboard_api::get_hardware_metrics,
             dashboard_api::get_model_status,
             dashboard_api::get_tool_metrics,
             dashboard_api::update_dashboard_config,
+            dashboard_api::get_dashboard_config,
 
             // Web integration commands
             web_integration::search_web,
+            web_integration::start_web_search_stream,
+            web_integration::register_custom_search_source,
+            web_integration::list_custom_search_sources,
+            web_integration::set_custom_search_source_enabled,
+            web_integration::remove_custom_search_source,
+            web_integration::test_custom_search_source,
             web_integration::navigate_to_url,
             web_integration::extract_page_content,
+            web_integration::list_cached_pages,
+            web_integration::clear_page_cache,
             web_integration::check_robots_txt,
+            web_integration::get_web_queue_status,
+            web_integration::summarize_url,
+            web_integration::research,
+            web_integration::cancel_research,
+            web_integration::generate_with_file,
+            web_integration::dedupe_pages,
+            web_integration::get_web_data_usage,
+            web_integration::set_session_download_cap,
+            web_integration::format_citations,
+            network::get_network_audit_log,
+            network::clear_network_audit_log,
+            web_integration::set_web_queue_concurrency,
 
             // Windows integration commands
             windows_integration::get_window_state,
@@ -135,7 +267,24 @@ fn main() {
             windows_integration::minimize_to_tray,
             windows_integration::restore_from_tray,
             windows_integration::get_windows_system_info,
-            windows_integration::is_desktop_mode
+            windows_integration::is_desktop_mode,
+
+            // Settings commands
+            settings::get_verbose_llm_logging,
+            settings::set_verbose_llm_logging,
+            settings::get_default_system_prompt,
+            settings::set_default_system_prompt,
+            settings::set_verify_stream_pipeline_on_startup,
+            settings::set_warm_up_stt_on_startup,
+            settings::set_model_fallback_enabled,
+            settings::get_streaming_enabled,
+            settings::set_streaming_enabled,
+            settings::get_max_output_tokens,
+            settings::set_max_output_tokens,
+            settings::get_offline_mode,
+            settings::export_settings,
+            settings::import_settings,
+            settings::set_offline_mode
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
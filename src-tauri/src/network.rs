@@ -0,0 +1,168 @@
+//! Shared HTTP client construction.
+//!
+//! Outbound clients for web search, page extraction and LLM calls are built
+//! here so every destination is recorded in the in-memory network audit log,
+//! giving users a record of exactly which hosts were contacted this session.
+//! When offline mode is on, any destination other than loopback is refused
+//! before a client is even created.
+
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use chrono::{DateTime, Utc};
+use tauri::command;
+use url::Url;
+use log::{info, warn};
+
+// Oldest entries are dropped past this size
+const MAX_AUDIT_ENTRIES: usize = 1000;
+// Same limit reqwest applies by default
+const MAX_REDIRECTS: usize = 10;
+
+// Probed by `internet_reachable`; a host web search contacts anyway
+const CONNECTIVITY_CHECK_URL: &str = "https://en.wikipedia.org";
+const CONNECTIVITY_CHECK_TIMEOUT: Duration = Duration::from_secs(3);
+const CONNECTIVITY_CACHE_TTL: Duration = Duration::from_secs(30);
+
+static AUDIT_LOG: Mutex<Vec<NetworkAuditEntry>> = Mutex::new(Vec::new());
+
+// Last connectivity probe result and when it was taken
+static LAST_CONNECTIVITY: Mutex<Option<(Instant, bool)>> = Mutex::new(None);
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct NetworkAuditEntry {
+    pub host: String,
+    pub timestamp: DateTime<Utc>,
+    pub purpose: String,
+}
+
+/// Build a client for talking to `url`, recording the destination host.
+pub fn build_http_client(url: &str, purpose: &str, timeout: Option<Duration>) -> Result<reqwest::Client, String> {
+    let host = Url::parse(url)
+        .ok()
+        .and_then(|u| u.host_str().map(|h| h.to_string()))
+        .ok_or_else(|| format!("Invalid destination URL: {}", url))?;
+
+    if !is_loopback_host(&host) {
+        ensure_online(purpose)?;
+    }
+
+    record_egress(&host, purpose);
+
+    // Every redirect hop is a new destination: audit it and apply offline mode again
+    let redirect_purpose = purpose.to_string();
+    let redirect_policy = reqwest::redirect::Policy::custom(move |attempt| {
+        if attempt.previous().len() >= MAX_REDIRECTS {
+            return attempt.error("too many redirects");
+        }
+        let Some(host) = attempt.url().host_str().map(|h| h.to_string()) else {
+            return attempt.error("redirect to a URL without a host");
+        };
+        if !is_loopback_host(&host) && crate::settings::get().offline_mode {
+            warn!("🔒 Blocked {} redirect to {}: offline mode is enabled", redirect_purpose, host);
+            return attempt.error(format!("{} redirect to {} blocked by offline mode", capitalize(&redirect_purpose), host));
+        }
+        record_egress(&host, &redirect_purpose);
+        attempt.follow()
+    });
+
+    let mut builder = reqwest::Client::builder().redirect(redirect_policy);
+    if let Some(timeout) = timeout {
+        builder = builder.timeout(timeout);
+    }
+    builder
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))
+}
+
+/// Fail fast with a clear message when offline mode blocks `purpose`.
+pub fn ensure_online(purpose: &str) -> Result<(), String> {
+    if crate::settings::get().offline_mode {
+        warn!("🔒 Blocked {} request: offline mode is enabled", purpose);
+        return Err(format!("{} blocked by offline mode", capitalize(purpose)));
+    }
+    Ok(())
+}
+
+/// Whether offline mode would refuse a client for `url`.
+pub fn blocked_by_offline_mode(url: &str) -> bool {
+    let loopback = Url::parse(url)
+        .ok()
+        .and_then(|u| u.host_str().map(is_loopback_host))
+        .unwrap_or(false);
+    !loopback && crate::settings::get().offline_mode
+}
+
+/// Whether the internet answers at all, from a short HEAD request cached for
+/// `CONNECTIVITY_CACHE_TTL`. Any HTTP response counts; only connect failures and
+/// timeouts mean offline.
+pub async fn internet_reachable() -> bool {
+    if let Some((checked_at, reachable)) = *LAST_CONNECTIVITY.lock().unwrap() {
+        if checked_at.elapsed() < CONNECTIVITY_CACHE_TTL {
+            return reachable;
+        }
+    }
+
+    let reachable = match build_http_client(CONNECTIVITY_CHECK_URL, "connectivity check", Some(CONNECTIVITY_CHECK_TIMEOUT)) {
+        Ok(client) => client.head(CONNECTIVITY_CHECK_URL).send().await.is_ok(),
+        Err(_) => false,
+    };
+    if !reachable {
+        warn!("📴 No internet connection ({} unreachable)", CONNECTIVITY_CHECK_URL);
+    }
+
+    *LAST_CONNECTIVITY.lock().unwrap() = Some((Instant::now(), reachable));
+    reachable
+}
+
+fn is_loopback_host(host: &str) -> bool {
+    if host.eq_ignore_ascii_case("localhost") {
+        return true;
+    }
+    // IPv6 hosts come back from Url bracketed
+    host.trim_start_matches('[')
+        .trim_end_matches(']')
+        .parse::<std::net::IpAddr>()
+        .map(|ip| ip.is_loopback())
+        .unwrap_or(false)
+}
+
+fn capitalize(text: &str) -> String {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+fn record_egress(host: &str, purpose: &str) {
+    let mut log = AUDIT_LOG.lock().unwrap();
+    if log.len() >= MAX_AUDIT_ENTRIES {
+        log.remove(0);
+    }
+    log.push(NetworkAuditEntry {
+        host: host.to_string(),
+        timestamp: Utc::now(),
+        purpose: purpose.to_string(),
+    });
+}
+
+#[command]
+pub async fn get_network_audit_log() -> Result<Vec<NetworkAuditEntry>, String> {
+    Ok(AUDIT_LOG.lock().unwrap().clone())
+}
+
+/// Empty the audit log, returning how many entries were dropped.
+pub fn clear_audit_log() -> usize {
+    let mut log = AUDIT_LOG.lock().unwrap();
+    let cleared = log.len();
+    info!("🧹 Clearing network audit log ({} entries)", cleared);
+    log.clear();
+    cleared
+}
+
+#[command]
+pub async fn clear_network_audit_log() -> Result<(), String> {
+    clear_audit_log();
+    Ok(())
+}
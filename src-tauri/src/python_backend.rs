@@ -1,19 +1,31 @@
 use std::process::{Command, Child, Stdio};
 use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::Duration;
 use serde::{Deserialize, Serialize};
 use tauri::command;
 use log::{info, error, warn};
 use reqwest;
+use tokio::sync::Notify;
 use tokio::time::timeout;
 
 // Python backend configuration
 const PYTHON_BACKEND_URL: &str = "http://127.0.0.1:8000";
 const BACKEND_STARTUP_TIMEOUT: Duration = Duration::from_secs(15);
 
+// Background health monitor
+const HEALTH_MONITOR_TASK: &str = "health-monitor";
+const MIN_HEALTH_INTERVAL_MS: u64 = 1000;
+const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
 // Global backend process handle
 static BACKEND_PROCESS: Mutex<Option<Child>> = Mutex::new(None);
 
+// Startup wait state, so a hung startup can be aborted from the UI
+static STARTUP_IN_PROGRESS: AtomicBool = AtomicBool::new(false);
+static STARTUP_CANCELLED: AtomicBool = AtomicBool::new(false);
+static STARTUP_CANCEL_NOTIFY: Notify = Notify::const_new();
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct BackendHealthResponse {
     pub status: String,
@@ -34,6 +46,9 @@ pub struct LLMResponse {
     pub model: String,
     pub success: bool,
     pub error: Option<String>,
+    /// Which system prompt was sent; the backend doesn't report it.
+    #[serde(default)]
+    pub system_prompt_source: crate::settings::SystemPromptSource,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -48,6 +63,38 @@ pub struct OllamaModel {
     pub digest: String,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum BackendState {
+    Stopped,
+    Starting,
+    Ready,
+    Unhealthy,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthState {
+    Up,
+    Down,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SubsystemHealth {
+    /// "ollama" or "backend".
+    pub subsystem: String,
+    /// None on the monitor's first check.
+    pub previous: Option<HealthState>,
+    pub current: HealthState,
+    pub changed: bool,
+}
+
+/// Payload of the `health-changed` event, listing every subsystem so the UI has the full picture.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct HealthChanged {
+    pub subsystems: Vec<SubsystemHealth>,
+    pub checked_at: String,
+}
+
 /// Start the Python backend server
 #[command]
 pub async fn start_python_backend() -> Result<String, String> {
@@ -79,20 +126,61 @@ pub async fn start_python_backend() -> Result<String, String> {
     }
     
     // Wait for backend to be ready
+    STARTUP_CANCELLED.store(false, Ordering::SeqCst);
+    STARTUP_IN_PROGRESS.store(true, Ordering::SeqCst);
+    let result = wait_for_backend_ready().await;
+    STARTUP_IN_PROGRESS.store(false, Ordering::SeqCst);
+    result
+}
+
+async fn wait_for_backend_ready() -> Result<String, String> {
     let start_time = std::time::Instant::now();
     while start_time.elapsed() < BACKEND_STARTUP_TIMEOUT {
+        if STARTUP_CANCELLED.load(Ordering::SeqCst) {
+            warn!("⚠️ Python backend startup cancelled");
+            kill_backend_process();
+            return Err("Backend startup cancelled".to_string());
+        }
+
         if is_backend_running().await {
             info!("✅ Python backend is ready and responding");
             return Ok("Backend started successfully".to_string());
         }
         
-        tokio::time::sleep(Duration::from_millis(500)).await;
+        tokio::select! {
+            _ = tokio::time::sleep(Duration::from_millis(500)) => {}
+            _ = STARTUP_CANCEL_NOTIFY.notified() => {}
+        }
     }
     
     error!("❌ Python backend failed to start within timeout");
     Err("Backend startup timeout".to_string())
 }
 
+/// Abort a pending `start_python_backend`, killing the partially started process.
+#[command]
+pub async fn cancel_backend_startup() -> Result<bool, String> {
+    if !STARTUP_IN_PROGRESS.load(Ordering::SeqCst) {
+        info!("ℹ️ No Python backend startup in progress to cancel");
+        return Ok(false);
+    }
+
+    info!("⏹️ Cancelling Python backend startup...");
+    STARTUP_CANCELLED.store(true, Ordering::SeqCst);
+    STARTUP_CANCEL_NOTIFY.notify_one();
+    Ok(true)
+}
+
+fn kill_backend_process() {
+    let mut backend_process = BACKEND_PROCESS.lock().unwrap();
+    if let Some(mut process) = backend_process.take() {
+        match process.kill() {
+            Ok(_) => info!("✅ Killed partially started Python backend"),
+            Err(e) => error!("❌ Failed to kill Python backend process: {}", e),
+        }
+    }
+}
+
 /// Stop the Python backend server
 #[command]
 pub async fn stop_python_backend() -> Result<String, String> {
@@ -153,15 +241,48 @@ pub async fn check_python_backend() -> Result<BackendHealthResponse, String> {
     }
 }
 
+/// Lifecycle state of the backend, distinguishing "still booting" from "down"
+#[command]
+pub async fn get_backend_state() -> Result<BackendState, String> {
+    if STARTUP_IN_PROGRESS.load(Ordering::SeqCst) {
+        return Ok(BackendState::Starting);
+    }
+
+    if is_backend_running().await {
+        return Ok(BackendState::Ready);
+    }
+
+    // Not answering health checks: is there still a live process we started?
+    let mut backend_process = BACKEND_PROCESS.lock().unwrap();
+    let state = match backend_process.as_mut().map(|process| process.try_wait()) {
+        Some(Ok(None)) => BackendState::Unhealthy,
+        Some(Ok(Some(status))) => {
+            warn!("⚠️ Python backend process exited with {}", status);
+            backend_process.take();
+            BackendState::Stopped
+        }
+        Some(Err(e)) => {
+            error!("❌ Failed to query Python backend process: {}", e);
+            BackendState::Unhealthy
+        }
+        None => BackendState::Stopped,
+    };
+
+    Ok(state)
+}
+
 /// Send LLM request to Python backend
 #[command]
 pub async fn send_llm_request_to_backend(prompt: String, model: Option<String>) -> Result<LLMResponse, String> {
     info!("🚀 Sending LLM request to Python backend...");
     info!("📝 Prompt length: {}", prompt.len());
     
+    let system_prompt = crate::settings::resolve_system_prompt(None, None);
+    let prompt = crate::llm::combine_with_system_prompt(system_prompt.prompt.as_deref(), prompt);
+
     let request = LLMRequest {
         prompt,
-        model: model.unwrap_or_else(|| "gemma3n:latest".to_string()),
+        model: model.unwrap_or_else(crate::llm::active_model),
         stream: false,
     };
     
@@ -179,7 +300,7 @@ pub async fn send_llm_request_to_backend(prompt: String, model: Option<String>)
                     Ok(llm_response) => {
                         if llm_response.success {
                             info!("✅ LLM response received (length: {})", llm_response.response.len());
-                            Ok(llm_response)
+                            Ok(LLMResponse { system_prompt_source: system_prompt.source, ..llm_response })
                         } else {
                             error!("❌ LLM request failed: {:?}", llm_response.error);
                             Err(llm_response.error.unwrap_or_else(|| "Unknown LLM error".to_string()))
@@ -221,6 +342,71 @@ async fn is_backend_running() -> bool {
     }
 }
 
+async fn is_ollama_running() -> bool {
+    let Ok(client) = crate::llm::LLMClient::new() else {
+        return false;
+    };
+    matches!(timeout(HEALTH_CHECK_TIMEOUT, client.check_health()).await, Ok(Ok(true)))
+}
+
+/// Check Ollama and the backend every `interval_ms`, emitting `health-changed`
+/// on the first check and whenever either goes up or down. Restarts a running monitor.
+#[command]
+pub async fn start_health_monitor(app_handle: tauri::AppHandle, interval_ms: u64) -> Result<(), String> {
+    use tauri::Emitter;
+
+    if interval_ms < MIN_HEALTH_INTERVAL_MS {
+        return Err(format!("Health check interval must be at least {}ms", MIN_HEALTH_INTERVAL_MS));
+    }
+
+    let token = crate::cancellation::register(HEALTH_MONITOR_TASK);
+    info!("🩺 Health monitor started, checking every {}ms", interval_ms);
+
+    tokio::spawn(async move {
+        let mut previous: Option<(HealthState, HealthState)> = None;
+        loop {
+            let (ollama_up, backend_up) = tokio::join!(is_ollama_running(), is_backend_running());
+            let state = |up: bool| if up { HealthState::Up } else { HealthState::Down };
+            let current = (state(ollama_up), state(backend_up));
+
+            if previous != Some(current) {
+                let subsystem = |name: &str, previous: Option<HealthState>, current: HealthState| SubsystemHealth {
+                    subsystem: name.to_string(),
+                    changed: previous != Some(current),
+                    previous,
+                    current,
+                };
+                let event = HealthChanged {
+                    subsystems: vec![
+                        subsystem("ollama", previous.map(|p| p.0), current.0),
+                        subsystem("backend", previous.map(|p| p.1), current.1),
+                    ],
+                    checked_at: chrono::Utc::now().to_rfc3339(),
+                };
+                info!("🩺 Health changed: Ollama {:?}, backend {:?}", current.0, current.1);
+                if let Err(e) = app_handle.emit("health-changed", &event) {
+                    warn!("⚠️ Failed to emit health change: {}", e);
+                }
+                previous = Some(current);
+            }
+
+            tokio::select! {
+                _ = token.cancelled() => break,
+                _ = tokio::time::sleep(Duration::from_millis(interval_ms)) => {}
+            }
+        }
+        crate::cancellation::unregister(HEALTH_MONITOR_TASK, &token);
+        info!("🩺 Health monitor stopped");
+    });
+
+    Ok(())
+}
+
+#[command]
+pub async fn stop_health_monitor() -> Result<bool, String> {
+    Ok(crate::cancellation::cancel(HEALTH_MONITOR_TASK))
+}
+
 /// Get available Ollama models from backend
 #[command]
 pub async fn get_ollama_models_from_backend() -> Result<OllamaModelsResponse, String> {
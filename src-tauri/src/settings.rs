@@ -0,0 +1,376 @@
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+use tauri::command;
+use log::{info, error, warn};
+
+const SETTINGS_FILE_NAME: &str = "settings.json";
+const SETTINGS_BUNDLE_VERSION: u32 = 1; // Bump when a bundle can no longer be read as AppSettings
+
+// Location of the settings file, set once during app setup
+static SETTINGS_PATH: OnceLock<PathBuf> = OnceLock::new();
+
+// Cached settings, loaded lazily from disk on first access
+static SETTINGS: Mutex<Option<AppSettings>> = Mutex::new(None);
+
+/// Application settings persisted across restarts.
+///
+/// Every field has a default so older settings files keep loading as new
+/// options are added.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppSettings {
+    pub default_system_prompt: Option<String>,
+    /// Retry with a smaller model when generation fails server-side.
+    pub fallback_to_smaller_model: bool,
+    /// Block all outbound HTTP except to loopback addresses.
+    pub offline_mode: bool,
+    /// Model used when a command doesn't name one.
+    pub active_model: Option<String>,
+    pub tts: Option<crate::stt_tts::TtsConfig>,
+    pub dashboard: Option<crate::dashboard_api::DashboardConfig>,
+    /// Recording length ceiling in seconds; see `stt_tts::max_recording_secs`.
+    pub max_recording_secs: Option<u64>,
+    /// Token batching for streamed responses; per-token when unset.
+    pub stream_flush: Option<crate::llm::StreamFlush>,
+    /// Hard cap on generated tokens, applied as `num_predict` to every request.
+    pub max_output_tokens: Option<u32>,
+    /// Stream tokens as they are generated; `generate_auto` waits for the full answer when off.
+    pub streaming_enabled: Option<bool>,
+    /// Log full prompts and Ollama response bodies; only sizes and status otherwise.
+    pub verbose_llm_logging: bool,
+    /// Run `llm::check_stream_pipeline` when the app starts.
+    pub verify_stream_pipeline_on_startup: bool,
+    /// Run `stt_tts::warm_up_stt` when the app starts.
+    pub warm_up_stt_on_startup: bool,
+    /// Timeouts chosen by the user; these win over `hardware_timeouts`.
+    pub llm_timeouts: Option<crate::llm::LlmTimeouts>,
+    /// Timeouts from the last `apply_hardware_timeout_profile`.
+    pub hardware_timeouts: Option<crate::llm::LlmTimeouts>,
+    /// User-configured search endpoints queried by `search_web` alongside the built-ins.
+    pub custom_search_sources: Vec<crate::web_integration::CustomSearchSource>,
+    /// Cancel a stream once a phrase repeats this many times in a row; off when unset.
+    pub repetition_guard_threshold: Option<usize>,
+    /// Extra LLM servers besides the built-in local Ollama.
+    pub llm_endpoints: Vec<crate::llm::LlmEndpoint>,
+    /// Name of the endpoint generation uses; the local Ollama when unset.
+    pub active_llm_endpoint: Option<String>,
+    /// Streams allowed at once; see `llm::max_concurrent_streams`.
+    pub max_concurrent_streams: Option<usize>,
+    /// Per-page cap on extracted text and raw HTML; see `web_integration::max_content_bytes`.
+    pub max_content_bytes: Option<usize>,
+    /// Web downloads allowed per app session before fetches are refused; unlimited when unset.
+    pub session_download_cap_bytes: Option<u64>,
+    /// Software gain for recorded audio; see `stt_tts::input_gain`.
+    pub input_gain: Option<f32>,
+    /// Per-word delay for simulated streaming; see `llm::fallback_word_delay_ms`.
+    pub fallback_word_delay_ms: Option<u64>,
+}
+
+/// Point the settings store at the app config directory and load it.
+pub fn init(config_dir: PathBuf) {
+    let path = config_dir.join(SETTINGS_FILE_NAME);
+    info!("⚙️ Using settings file: {:?}", path);
+
+    if SETTINGS_PATH.set(path).is_err() {
+        warn!("⚠️ Settings store already initialized");
+        return;
+    }
+
+    let settings = load_from_disk();
+    *SETTINGS.lock().unwrap() = Some(settings);
+}
+
+/// Snapshot of the current settings.
+pub fn get() -> AppSettings {
+    let mut guard = SETTINGS.lock().unwrap();
+    guard.get_or_insert_with(load_from_disk).clone()
+}
+
+/// Apply `change` to the settings and persist the result.
+pub fn update<F>(change: F) -> Result<AppSettings, String>
+where
+    F: FnOnce(&mut AppSettings),
+{
+    let mut guard = SETTINGS.lock().unwrap();
+    let settings = guard.get_or_insert_with(load_from_disk);
+    change(settings);
+    save_to_disk(settings)?;
+    Ok(settings.clone())
+}
+
+/// Restore every setting to its default and persist that.
+pub fn reset() -> Result<(), String> {
+    let mut guard = SETTINGS.lock().unwrap();
+    let defaults = AppSettings::default();
+    save_to_disk(&defaults)?;
+    *guard = Some(defaults);
+    Ok(())
+}
+
+fn load_from_disk() -> AppSettings {
+    let Some(path) = SETTINGS_PATH.get() else {
+        return AppSettings::default();
+    };
+
+    match std::fs::read_to_string(path) {
+        Ok(contents) => match serde_json::from_str(&contents) {
+            Ok(settings) => settings,
+            Err(e) => {
+                error!("❌ Failed to parse settings file, using defaults: {}", e);
+                AppSettings::default()
+            }
+        },
+        Err(_) => {
+            info!("📄 No settings file found, using defaults");
+            AppSettings::default()
+        }
+    }
+}
+
+fn save_to_disk(settings: &AppSettings) -> Result<(), String> {
+    let Some(path) = SETTINGS_PATH.get() else {
+        // Not initialized (e.g. in tests) - keep settings in memory only
+        return Ok(());
+    };
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create settings directory: {}", e))?;
+    }
+
+    let contents = serde_json::to_string_pretty(settings)
+        .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+    std::fs::write(path, contents)
+        .map_err(|e| format!("Failed to write settings file: {}", e))?;
+
+    Ok(())
+}
+
+/// Settings exported for moving to another machine.
+///
+/// Credentials are never included; `omitted_secrets` lists what has to be re-entered.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SettingsBundle {
+    pub format_version: u32,
+    pub app_version: String,
+    pub exported_at: String,
+    pub settings: AppSettings,
+    #[serde(default)]
+    pub omitted_secrets: Vec<String>,
+}
+
+// Portable copy of every setting, minus secrets and this machine's hardware profile
+#[command]
+pub async fn export_settings() -> Result<String, String> {
+    let mut settings = get();
+    let mut omitted_secrets = Vec::new();
+
+    // Tuned to this machine's hardware; the new machine applies its own
+    settings.hardware_timeouts = None;
+    for endpoint in &mut settings.llm_endpoints {
+        if endpoint.api_key.take().is_some() {
+            omitted_secrets.push(format!("API key for LLM endpoint {}", endpoint.name));
+        }
+    }
+    for source in &mut settings.custom_search_sources {
+        if crate::web_integration::strip_url_template_secrets(source) {
+            omitted_secrets.push(format!("Credentials in the URL of search source {}", source.name));
+        }
+    }
+
+    let bundle = SettingsBundle {
+        format_version: SETTINGS_BUNDLE_VERSION,
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        exported_at: chrono::Utc::now().to_rfc3339(),
+        settings,
+        omitted_secrets,
+    };
+    info!("📦 Exported settings ({} secrets omitted)", bundle.omitted_secrets.len());
+    serde_json::to_string_pretty(&bundle).map_err(|e| format!("Failed to serialize settings: {}", e))
+}
+
+// Replace the current settings with an exported bundle after checking it
+#[command]
+pub async fn import_settings(json: String) -> Result<AppSettings, String> {
+    let bundle: SettingsBundle = serde_json::from_str(&json)
+        .map_err(|e| format!("Not a valid settings bundle: {}", e))?;
+    if bundle.format_version > SETTINGS_BUNDLE_VERSION {
+        return Err(format!(
+            "Settings bundle format {} is newer than this app supports ({}); update the app first",
+            bundle.format_version, SETTINGS_BUNDLE_VERSION
+        ));
+    }
+
+    let imported = bundle.settings;
+    if imported.max_output_tokens == Some(0) {
+        return Err("Invalid settings: output token limit must be at least 1".to_string());
+    }
+    if let Some(timeouts) = &imported.llm_timeouts {
+        if timeouts.request_secs == 0 || timeouts.stream_secs == 0 {
+            return Err("Invalid settings: timeouts must be at least 1 second".to_string());
+        }
+    }
+    if imported.max_concurrent_streams == Some(0) {
+        return Err("Invalid settings: at least 1 concurrent stream must be allowed".to_string());
+    }
+    if imported.input_gain.map_or(false, |gain| !gain.is_finite() || gain <= 0.0) {
+        return Err("Invalid settings: input gain must be a positive number".to_string());
+    }
+    if imported.repetition_guard_threshold.map_or(false, |t| t < crate::llm::MIN_REPETITION_THRESHOLD) {
+        return Err(format!(
+            "Invalid settings: repetition threshold must be at least {}",
+            crate::llm::MIN_REPETITION_THRESHOLD
+        ));
+    }
+    for source in &imported.custom_search_sources {
+        crate::web_integration::validate_custom_search_source(source).map_err(|e| format!("Invalid settings: {}", e))?;
+    }
+
+    info!("📦 Importing settings exported by version {} on {}", bundle.app_version, bundle.exported_at);
+    if !bundle.omitted_secrets.is_empty() {
+        warn!("⚠️ Imported settings need secrets re-entered: {:?}", bundle.omitted_secrets);
+    }
+    update(|settings| {
+        let hardware_timeouts = settings.hardware_timeouts.take();
+        *settings = imported;
+        settings.hardware_timeouts = hardware_timeouts;
+    })
+}
+
+/// Where the system prompt for a generation came from.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SystemPromptSource {
+    Request,
+    Chat,
+    Default,
+    #[default]
+    None,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ResolvedSystemPrompt {
+    pub prompt: Option<String>,
+    pub source: SystemPromptSource,
+}
+
+/// Pick the system prompt for a generation.
+///
+/// Precedence: the request's own argument, then the chat's saved prompt, then the
+/// configured default, then none. Blank prompts are skipped at every level.
+pub fn resolve_system_prompt(chat_id: Option<&str>, request_arg: Option<String>) -> ResolvedSystemPrompt {
+    let non_blank = |prompt: Option<String>| prompt.filter(|p| !p.trim().is_empty());
+    let resolved = |prompt: String, source| ResolvedSystemPrompt { prompt: Some(prompt), source };
+
+    if let Some(prompt) = non_blank(request_arg) {
+        return resolved(prompt, SystemPromptSource::Request);
+    }
+    let chat_prompt = chat_id
+        .and_then(|chat_id| crate::chat_store::load_session(chat_id).ok())
+        .and_then(|session| non_blank(crate::chat_store::session_system_prompt(&session)));
+    if let Some(prompt) = chat_prompt {
+        return resolved(prompt, SystemPromptSource::Chat);
+    }
+    if let Some(prompt) = non_blank(get().default_system_prompt) {
+        return resolved(prompt, SystemPromptSource::Default);
+    }
+    ResolvedSystemPrompt::default()
+}
+
+/// Whether responses stream token by token (the default).
+pub fn streaming_enabled() -> bool {
+    get().streaming_enabled.unwrap_or(true)
+}
+
+#[command]
+pub async fn get_streaming_enabled() -> Result<bool, String> {
+    Ok(streaming_enabled())
+}
+
+#[command]
+pub async fn set_streaming_enabled(enabled: bool) -> Result<(), String> {
+    info!("⚙️ Streaming {}", if enabled { "enabled" } else { "disabled" });
+    update(|settings| settings.streaming_enabled = Some(enabled))?;
+    Ok(())
+}
+
+/// Whether prompt and response contents may be written to the log.
+pub fn verbose_llm_logging() -> bool {
+    get().verbose_llm_logging
+}
+
+#[command]
+pub async fn get_verbose_llm_logging() -> Result<bool, String> {
+    Ok(verbose_llm_logging())
+}
+
+#[command]
+pub async fn set_verbose_llm_logging(enabled: bool) -> Result<(), String> {
+    info!("⚙️ Verbose LLM logging {}", if enabled { "enabled" } else { "disabled" });
+    update(|settings| settings.verbose_llm_logging = enabled)?;
+    Ok(())
+}
+
+#[command]
+pub async fn get_default_system_prompt() -> Result<Option<String>, String> {
+    Ok(get().default_system_prompt)
+}
+
+#[command]
+pub async fn set_default_system_prompt(prompt: Option<String>) -> Result<(), String> {
+    let prompt = prompt.filter(|p| !p.trim().is_empty());
+    info!("⚙️ Setting default system prompt (length: {})", prompt.as_ref().map_or(0, |p| p.len()));
+
+    update(|settings| settings.default_system_prompt = prompt)?;
+    Ok(())
+}
+
+#[command]
+pub async fn set_verify_stream_pipeline_on_startup(enabled: bool) -> Result<(), String> {
+    info!("⚙️ Stream pipeline check on startup {}", if enabled { "enabled" } else { "disabled" });
+    update(|settings| settings.verify_stream_pipeline_on_startup = enabled)?;
+    Ok(())
+}
+
+#[command]
+pub async fn set_warm_up_stt_on_startup(enabled: bool) -> Result<(), String> {
+    info!("⚙️ STT warm-up on startup {}", if enabled { "enabled" } else { "disabled" });
+    update(|settings| settings.warm_up_stt_on_startup = enabled)?;
+    Ok(())
+}
+
+#[command]
+pub async fn set_model_fallback_enabled(enabled: bool) -> Result<(), String> {
+    info!("⚙️ Smaller-model fallback {}", if enabled { "enabled" } else { "disabled" });
+    update(|settings| settings.fallback_to_smaller_model = enabled)?;
+    Ok(())
+}
+
+#[command]
+pub async fn get_max_output_tokens() -> Result<Option<u32>, String> {
+    Ok(get().max_output_tokens)
+}
+
+#[command]
+pub async fn set_max_output_tokens(tokens: Option<u32>) -> Result<(), String> {
+    if tokens == Some(0) {
+        return Err("Output token limit must be at least 1".to_string());
+    }
+
+    info!("⚙️ Max output tokens set to {:?}", tokens);
+    update(|settings| settings.max_output_tokens = tokens)?;
+    Ok(())
+}
+
+#[command]
+pub async fn get_offline_mode() -> Result<bool, String> {
+    Ok(get().offline_mode)
+}
+
+#[command]
+pub async fn set_offline_mode(enabled: bool) -> Result<(), String> {
+    info!("🔒 Offline mode {}", if enabled { "enabled - only localhost is reachable" } else { "disabled" });
+    update(|settings| settings.offline_mode = enabled)?;
+    Ok(())
+}
@@ -13,24 +13,127 @@ use log::{info, error, warn};
 use serde::{Serialize, Deserialize};
 use serde_json;
 
-const RECORDING_DURATION: u64 = 5; // seconds
+const RECORDING_DURATION: u64 = 5; // seconds, when run_vosk_stt isn't given a duration
+const MAX_STT_DURATION_SECS: u64 = 120; // longest capture run_vosk_stt will make
+const DEFAULT_MAX_RECORDING_SECS: u64 = 300; // used when no limit is configured
+const HARD_MAX_RECORDING_SECS: u64 = 3600; // no setting can raise the limit past this
+pub const TEMP_AUDIO_MAX_AGE_SECS: u64 = 3600; // leftovers older than an hour are safe to remove
 const SAMPLE_RATE: u32 = 16000; // 16kHz for speech recognition
 const CHANNELS: u16 = 1; // Mono
+const DEFAULT_INPUT_GAIN: f32 = 1.0; // recorded samples are left as captured
+const MIN_INPUT_GAIN: f32 = 0.1;
+const MAX_INPUT_GAIN: f32 = 10.0; // 20 dB, enough for quiet headset mics
+const TARGET_INPUT_PEAK: f32 = 0.9; // suggested gain brings the peak up to this level
+const VOICE_CHAT_TASK_PREFIX: &str = "voice-chat:";
+const VOICE_MESSAGE_TASK_PREFIX: &str = "voice-message:";
+const VOICE_DRAFT_TASK_PREFIX: &str = "voice-draft:";
+const TTS_TASK_PREFIX: &str = "tts:";
+const VOSK_STREAM_TASK_PREFIX: &str = "vosk-stream:";
+const VOSK_MODEL_PREFIX: &str = "vosk-model-";
+const DEFAULT_VOSK_MODEL: &str = "vosk-model-small-en-us-0.15";
+const VOSK_SCRIPT_NAME: &str = "tauri_vosk_integration.py";
+const MIN_SPEAKABLE_CHARS: usize = 10; // Shorter fragments are merged into a neighbour
+
+// Loudest sample of the current or most recent recording, before gain
+static INPUT_PEAK: std::sync::atomic::AtomicU32 = std::sync::atomic::AtomicU32::new(0);
+
+// Words that end in a period without ending the sentence
+const ABBREVIATIONS: &[&str] = &[
+    "mr", "mrs", "ms", "dr", "prof", "sr", "jr", "st", "vs", "etc", "e.g", "i.e",
+    "approx", "no", "fig", "inc", "ltd", "co", "corp", "dept", "est", "mt",
+];
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SttResult {
     pub text: String,
     pub confidence: f32,
     pub success: bool,
+    /// The recording was cut off at the configured maximum length.
+    #[serde(default)]
+    pub hit_max_length: bool,
+    /// Input format checks, reported by `test_static_file_stt`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub audio_format: Option<AudioFormatCheck>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WavFormat {
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub bits_per_sample: u16,
+    pub float: bool,
+}
+
+impl WavFormat {
+    fn read(path: &str) -> Option<Self> {
+        let spec = hound::WavReader::open(path).ok()?.spec();
+        Some(Self {
+            sample_rate: spec.sample_rate,
+            channels: spec.channels,
+            bits_per_sample: spec.bits_per_sample,
+            float: spec.sample_format == hound::SampleFormat::Float,
+        })
+    }
+
+    /// What the recorder writes and Windows Speech Recognition reliably accepts.
+    fn is_recognizer_ready(&self) -> bool {
+        self.sample_rate == SAMPLE_RATE && self.channels == CHANNELS && self.bits_per_sample == 16 && !self.float
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AudioFormatCheck {
+    /// `None` when the file isn't a readable WAV.
+    pub original: Option<WavFormat>,
+    /// Set when the file had to be converted before recognition.
+    pub converted: Option<WavFormat>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TtsConfig {
     pub voice_model: String,
     pub speed: f32,
     pub enabled: bool,
 }
 
+/// Result of `test_tts_render`: what synthesis produced, without playing it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TtsRenderTest {
+    pub duration_ms: u64,
+    /// Samples across all channels.
+    pub sample_count: u32,
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub voice: Option<String>,
+}
+
+/// A SAPI voice installed on the system.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InstalledVoice {
+    pub name: String,
+    /// Culture name such as "en-US".
+    pub culture: String,
+}
+
+/// The voice TTS will actually speak with, from `get_selected_tts_voice`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SelectedTtsVoice {
+    pub configured: String,
+    /// `None` leaves the choice to SAPI, which only happens when no voices could be listed.
+    pub voice: Option<InstalledVoice>,
+    pub system_locale: Option<String>,
+    /// The configured voice isn't installed, so one matching the system locale was picked.
+    pub fell_back: bool,
+}
+
+/// Installed voices and the UI culture, queried once per session.
+struct SapiVoices {
+    voices: Vec<InstalledVoice>,
+    locale: Option<String>,
+}
+
+static SAPI_VOICES: std::sync::OnceLock<SapiVoices> = std::sync::OnceLock::new();
+
 impl Default for TtsConfig {
     fn default() -> Self {
         Self {
@@ -41,19 +144,32 @@ impl Default for TtsConfig {
     }
 }
 
-// STT using Windows Speech Recognition API with enhanced debugging
+// STT using Windows Speech Recognition API with enhanced debugging.
+// Records for `duration_secs` (5s by default, at most 120s) so hold-to-talk can match how long the user spoke.
 #[command]
-pub async fn run_vosk_stt(mic_on: bool) -> Result<SttResult, String> {
+pub async fn run_vosk_stt(mic_on: bool, duration_secs: Option<u64>) -> Result<SttResult, String> {
     if !mic_on {
         info!("STT called with mic_on=false, returning empty result");
         return Ok(SttResult {
             text: "".into(),
             confidence: 0.0,
             success: false,
+            hit_max_length: false,
+            audio_format: None,
         });
     }
 
-    info!("🎤 Starting STT with audio recording...");
+    let requested_secs = duration_secs.unwrap_or(RECORDING_DURATION);
+    if requested_secs == 0 {
+        return Err("Recording duration must be at least 1 second".to_string());
+    }
+    let capped = requested_secs > MAX_STT_DURATION_SECS;
+    let duration_secs = requested_secs.min(MAX_STT_DURATION_SECS);
+    if capped {
+        warn!("⚠️ Requested {}s recording exceeds the {}s limit, capping", requested_secs, MAX_STT_DURATION_SECS);
+    }
+
+    info!("🎤 Starting STT with a {}s audio recording...", duration_secs);
 
     // Use a more specific temp file path
     let temp_dir = std::env::temp_dir();
@@ -63,8 +179,9 @@ pub async fn run_vosk_stt(mic_on: bool) -> Result<SttResult, String> {
     info!("📁 Using temp audio file: {}", temp_audio_str);
 
     // Record audio using cpal
-    match record_audio_to_file(&temp_audio_str, RECORDING_DURATION).await {
-        Ok(_) => {
+    match record_audio_to_file(&temp_audio_str, duration_secs, None).await {
+        Ok(hit_max_length) => {
+            let hit_max_length = hit_max_length || capped;
             info!("✅ Audio recording completed successfully");
 
             // Check if file was actually created and has content
@@ -85,27 +202,7 @@ pub async fn run_vosk_stt(mic_on: bool) -> Result<SttResult, String> {
             info!("🔄 Starting transcription process...");
 
             // Try multiple transcription methods
-            let transcription = match process_audio_with_speech_api(&temp_audio_str).await {
-                Ok(text) => {
-                    info!("✅ Speech recognition successful: {}", text);
-                    text
-                }
-                Err(e) => {
-                    error!("❌ Windows Speech Recognition failed: {}", e);
-
-                    // Try a simpler fallback method
-                    match simple_speech_recognition_fallback(&temp_audio_str).await {
-                        Ok(text) => {
-                            info!("✅ Fallback speech recognition successful: {}", text);
-                            text
-                        }
-                        Err(fallback_error) => {
-                            error!("❌ All speech recognition methods failed. Primary: {}, Fallback: {}", e, fallback_error);
-                            format!("Speech recognition failed. Primary error: {}. Fallback error: {}", e, fallback_error)
-                        }
-                    }
-                }
-            };
+            let transcription = transcribe_recording(&temp_audio_str).await.unwrap_or_else(|e| e);
 
             // Clean up temp file
             if let Err(e) = std::fs::remove_file(&temp_audio_str) {
@@ -126,6 +223,8 @@ pub async fn run_vosk_stt(mic_on: bool) -> Result<SttResult, String> {
                 text: transcription,
                 confidence: if success { 0.85 } else { 0.0 },
                 success,
+                hit_max_length,
+                audio_format: None,
             })
         }
         Err(e) => {
@@ -168,14 +267,78 @@ pub async fn run_piper_tts(text: String) -> Result<(), String> {
     }
 }
 
+// Synthesize to a temp WAV and report on it without playing anything
+#[command]
+pub async fn test_tts_render(text: String) -> Result<TtsRenderTest, String> {
+    if text.trim().is_empty() {
+        return Err("No text provided for TTS".into());
+    }
+    info!("🧪 Rendering TTS test without playback ({} chars)", text.len());
+
+    let audio_file = generate_speech_with_piper(&text).await?;
+    let rendered = hound::WavReader::open(&audio_file)
+        .map_err(|e| format!("TTS produced an unreadable WAV file: {}", e))
+        .map(|reader| {
+            let spec = reader.spec();
+            TtsRenderTest {
+                duration_ms: reader.duration() as u64 * 1000 / spec.sample_rate.max(1) as u64,
+                sample_count: reader.len(),
+                sample_rate: spec.sample_rate,
+                channels: spec.channels,
+                voice: selected_tts_voice().voice.map(|voice| voice.name),
+            }
+        });
+    let _ = std::fs::remove_file(&audio_file);
+
+    let rendered = rendered?;
+    if rendered.sample_count == 0 {
+        return Err("TTS produced an empty audio file".to_string());
+    }
+    info!("✅ TTS rendered {}ms of audio at {}Hz", rendered.duration_ms, rendered.sample_rate);
+    Ok(rendered)
+}
+
 // Helper functions
-async fn record_audio_to_file(filename: &str, duration: u64) -> Result<(), String> {
+
+// The cpal stream is not Send, so it lives on a dedicated thread and the async
+// caller just awaits the outcome instead of blocking a runtime worker.
+// Returns whether the recording was cut short by the max recording length.
+/// Record for `duration` seconds, or until `stop` is cancelled if given.
+async fn record_audio_to_file(
+    filename: &str,
+    duration: u64,
+    stop: Option<Arc<crate::cancellation::CancellationToken>>,
+) -> Result<bool, String> {
+    let filename = filename.to_string();
+    let (done_tx, done_rx) = tokio::sync::oneshot::channel();
+
+    thread::spawn(move || {
+        let result = record_audio_blocking(&filename, duration, stop.as_deref());
+        let _ = done_tx.send(result);
+    });
+
+    done_rx
+        .await
+        .map_err(|_| "Recording thread exited unexpectedly".to_string())?
+}
+
+fn record_audio_blocking(
+    filename: &str,
+    duration: u64,
+    stop: Option<&crate::cancellation::CancellationToken>,
+) -> Result<bool, String> {
+    let max_secs = max_recording_secs();
+    let mut hit_max_length = duration > max_secs;
+    let duration = duration.min(max_secs);
+    if hit_max_length {
+        warn!("⚠️ Requested recording length exceeds the {}s limit, capping", max_secs);
+    }
     info!("🎤 Recording audio to '{}' for {} seconds", filename, duration);
 
     // Get the default input device
     let host = cpal::default_host();
     let device = host.default_input_device()
-        .ok_or_else(|| "No input device available".to_string())?;
+        .ok_or_else(|| format!("No input device available: {}", diagnose_audio_devices().problem.unwrap_or_default()))?;
 
     let device_name = device.name().unwrap_or_else(|_| "Unknown".to_string());
     info!("🎧 Using input device: {}", device_name);
@@ -197,102 +360,260 @@ async fn record_audio_to_file(filename: &str, duration: u64) -> Result<(), Strin
     let writer = WavWriter::create(filename, spec)
         .map_err(|e| format!("Failed to create WAV writer: {}", e))?;
 
-    let writer = Arc::new(Mutex::new(Some(writer)));
+    // Safety net independent of the timing loop: stop writing once the cap's worth
+    // of samples is on disk, so a stalled loop can't grow the file without bound
+    let max_samples = max_secs * SAMPLE_RATE as u64 * CHANNELS as u64;
+    let gain = input_gain();
+    if gain != DEFAULT_INPUT_GAIN {
+        info!("🎚️ Applying input gain x{:.2}", gain);
+    }
+    INPUT_PEAK.store(0, std::sync::atomic::Ordering::Relaxed);
+    let writer = Arc::new(Mutex::new(Some(CappedWavWriter { writer, remaining: max_samples, gain })));
     let writer_clone = Arc::clone(&writer);
 
     // Build the input stream
+    let stream = build_i16_input_stream(&device, config.sample_format(), move |data| {
+        if let Ok(mut guard) = writer_clone.lock() {
+            if let Some(ref mut writer) = guard.as_mut() {
+                for &sample in data {
+                    writer.write_sample(sample);
+                }
+            }
+        }
+    })?;
+
+    // Start recording
+    stream.play().map_err(|e| format!("Failed to start recording: {}", e))?;
+    info!("🔴 Recording started...");
+
+    // Record for the specified duration, checking for an early stop between ticks
+    let tick = Duration::from_millis(100);
+    let started = std::time::Instant::now();
+    let mut logged_secs = 0;
+    while started.elapsed() < Duration::from_secs(duration) {
+        if stop.map_or(false, |stop| stop.is_cancelled()) {
+            info!("⏹️ Recording stopped early after {:.1}s", started.elapsed().as_secs_f32());
+            break;
+        }
+        thread::sleep(tick);
+        let secs = started.elapsed().as_secs();
+        if secs > logged_secs {
+            logged_secs = secs;
+            info!("🎤 Recording... {}s/{}", secs, duration);
+        }
+    }
+
+    // Stop recording
+    drop(stream);
+    info!("⏹️ Recording stopped");
+
+    // Finalize the WAV file
+    if let Ok(mut guard) = writer.lock() {
+        if let Some(capped) = guard.take() {
+            if capped.remaining == 0 {
+                warn!("⚠️ Recording hit the {}s maximum length", max_secs);
+                hit_max_length = true;
+            }
+            capped.writer.finalize().map_err(|e| format!("Failed to finalize WAV file: {}", e))?;
+            info!("💾 WAV file finalized successfully");
+        }
+    }
+
+    // Check if file was created and has content
+    match std::fs::metadata(filename) {
+        Ok(metadata) => {
+            info!("✅ Audio recording completed successfully - File size: {} bytes", metadata.len());
+            if metadata.len() == 0 {
+                return Err("Audio file is empty - no audio was recorded".to_string());
+            }
+        }
+        Err(e) => {
+            return Err(format!("Audio file was not created: {}", e));
+        }
+    }
+
+    Ok(hit_max_length)
+}
+
+/// Sample formats `build_i16_input_stream` can convert.
+fn is_supported_input_format(format: SampleFormat) -> bool {
+    matches!(format, SampleFormat::F32 | SampleFormat::I32 | SampleFormat::I16 | SampleFormat::U16 | SampleFormat::I8)
+}
+
+/// Open `device` at 16kHz mono, converting every buffer to i16 before handing it to `on_samples`.
+fn build_i16_input_stream<F>(device: &cpal::Device, sample_format: SampleFormat, mut on_samples: F) -> Result<cpal::Stream, String>
+where
+    F: FnMut(&[i16]) + Send + 'static,
+{
     let stream_config = StreamConfig {
         channels: CHANNELS,
         sample_rate: SampleRate(SAMPLE_RATE),
         buffer_size: cpal::BufferSize::Default,
     };
 
-    let stream = match config.sample_format() {
+    match sample_format {
         SampleFormat::F32 => device.build_input_stream(
             &stream_config,
             move |data: &[f32], _: &cpal::InputCallbackInfo| {
-                if let Ok(mut guard) = writer_clone.lock() {
-                    if let Some(ref mut writer) = guard.as_mut() {
-                        for &sample in data {
-                            let sample = (sample * i16::MAX as f32) as i16;
-                            let _ = writer.write_sample(sample);
-                        }
-                    }
-                }
+                let samples: Vec<i16> = data.iter().map(|&sample| (sample * i16::MAX as f32) as i16).collect();
+                on_samples(&samples);
             },
             |err| error!("An error occurred on the input audio stream: {}", err),
             None,
         ),
         SampleFormat::I16 => device.build_input_stream(
             &stream_config,
-            move |data: &[i16], _: &cpal::InputCallbackInfo| {
-                if let Ok(mut guard) = writer_clone.lock() {
-                    if let Some(ref mut writer) = guard.as_mut() {
-                        for &sample in data {
-                            let _ = writer.write_sample(sample);
-                        }
-                    }
-                }
-            },
+            move |data: &[i16], _: &cpal::InputCallbackInfo| on_samples(data),
             |err| error!("An error occurred on the input audio stream: {}", err),
             None,
         ),
         SampleFormat::U16 => device.build_input_stream(
             &stream_config,
             move |data: &[u16], _: &cpal::InputCallbackInfo| {
-                if let Ok(mut guard) = writer_clone.lock() {
-                    if let Some(ref mut writer) = guard.as_mut() {
-                        for &sample in data {
-                            let sample = (sample as i32 - 32768) as i16;
-                            let _ = writer.write_sample(sample);
-                        }
-                    }
-                }
+                let samples: Vec<i16> = data.iter().map(|&sample| (sample as i32 - 32768) as i16).collect();
+                on_samples(&samples);
+            },
+            |err| error!("An error occurred on the input audio stream: {}", err),
+            None,
+        ),
+        // Pro audio interfaces often capture at 32 bits; keep the top 16
+        SampleFormat::I32 => device.build_input_stream(
+            &stream_config,
+            move |data: &[i32], _: &cpal::InputCallbackInfo| {
+                let samples: Vec<i16> = data.iter().map(|&sample| (sample >> 16) as i16).collect();
+                on_samples(&samples);
+            },
+            |err| error!("An error occurred on the input audio stream: {}", err),
+            None,
+        ),
+        SampleFormat::I8 => device.build_input_stream(
+            &stream_config,
+            move |data: &[i8], _: &cpal::InputCallbackInfo| {
+                let samples: Vec<i16> = data.iter().map(|&sample| (sample as i16) << 8).collect();
+                on_samples(&samples);
             },
             |err| error!("An error occurred on the input audio stream: {}", err),
             None,
         ),
-        _ => {
-            return Err("Unsupported sample format".to_string());
+        other => {
+            let device_name = device.name().unwrap_or_else(|_| "Unknown".to_string());
+            error!("❌ Input device '{}' uses unsupported sample format {:?}", device_name, other);
+            return Err(format!(
+                "Unsupported sample format {:?} on input device '{}'. Supported formats are F32, I32, I16, U16 and I8; select a different input device or change its format in the system sound settings.",
+                other, device_name
+            ));
         }
-    }.map_err(|e| format!("Failed to build input stream: {}", e))?;
+    }.map_err(|e| format!("Failed to build input stream: {}", e))
+}
 
-    // Start recording
-    stream.play().map_err(|e| format!("Failed to start recording: {}", e))?;
-    info!("🔴 Recording started...");
+struct CappedWavWriter {
+    writer: WavWriter<std::io::BufWriter<File>>,
+    remaining: u64,
+    gain: f32,
+}
 
-    // Record for the specified duration
-    for i in 1..=duration {
-        thread::sleep(Duration::from_secs(1));
-        info!("🎤 Recording... {}s/{}", i, duration);
+impl CappedWavWriter {
+    fn write_sample(&mut self, sample: i16) {
+        if self.remaining == 0 {
+            return;
+        }
+        self.remaining -= 1;
+        INPUT_PEAK.fetch_max(sample.unsigned_abs() as u32, std::sync::atomic::Ordering::Relaxed);
+        let _ = self.writer.write_sample(apply_gain(sample, self.gain));
     }
+}
 
-    // Stop recording
-    drop(stream);
-    info!("⏹️ Recording stopped");
+/// Scale a sample by `gain`, saturating at the i16 range instead of wrapping.
+fn apply_gain(sample: i16, gain: f32) -> i16 {
+    (sample as f32 * gain).round().clamp(i16::MIN as f32, i16::MAX as f32) as i16
+}
 
-    // Finalize the WAV file
-    if let Ok(mut guard) = writer.lock() {
-        if let Some(writer) = guard.take() {
-            writer.finalize().map_err(|e| format!("Failed to finalize WAV file: {}", e))?;
-            info!("💾 WAV file finalized successfully");
-        }
+/// Software gain applied to recorded samples, from settings (default 1.0).
+pub fn input_gain() -> f32 {
+    crate::settings::get()
+        .input_gain
+        .unwrap_or(DEFAULT_INPUT_GAIN)
+        .clamp(MIN_INPUT_GAIN, MAX_INPUT_GAIN)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InputGain {
+    pub gain: f32,
+    /// Peak of the current or last recording before gain, from 0.0 to 1.0.
+    pub peak_level: f32,
+    /// Gain that would bring that peak to 90% of full scale, if anything was recorded.
+    pub suggested_gain: Option<f32>,
+}
+
+#[command]
+pub async fn get_input_gain() -> Result<InputGain, String> {
+    let peak_level = INPUT_PEAK.load(std::sync::atomic::Ordering::Relaxed) as f32 / i16::MAX as f32;
+    let suggested_gain = if peak_level > 0.0 {
+        Some((TARGET_INPUT_PEAK / peak_level).clamp(MIN_INPUT_GAIN, MAX_INPUT_GAIN))
+    } else {
+        None
+    };
+
+    Ok(InputGain {
+        gain: input_gain(),
+        peak_level: peak_level.min(1.0),
+        suggested_gain,
+    })
+}
+
+#[command]
+pub async fn set_input_gain(level: f32) -> Result<(), String> {
+    if !(MIN_INPUT_GAIN..=MAX_INPUT_GAIN).contains(&level) {
+        return Err(format!("Input gain must be between {} and {}", MIN_INPUT_GAIN, MAX_INPUT_GAIN));
     }
 
-    // Check if file was created and has content
-    match std::fs::metadata(filename) {
-        Ok(metadata) => {
-            info!("✅ Audio recording completed successfully - File size: {} bytes", metadata.len());
-            if metadata.len() == 0 {
-                return Err("Audio file is empty - no audio was recorded".to_string());
-            }
+    info!("🎚️ Input gain set to x{:.2}", level);
+    crate::settings::update(|settings| settings.input_gain = Some(level))?;
+    Ok(())
+}
+
+/// Longest recording allowed, from settings (default 300s), never above the hard cap.
+pub fn max_recording_secs() -> u64 {
+    crate::settings::get()
+        .max_recording_secs
+        .unwrap_or(DEFAULT_MAX_RECORDING_SECS)
+        .clamp(1, HARD_MAX_RECORDING_SECS)
+}
+
+#[command]
+pub async fn set_max_recording_secs(secs: u64) -> Result<(), String> {
+    if secs == 0 || secs > HARD_MAX_RECORDING_SECS {
+        return Err(format!("Max recording length must be between 1 and {} seconds", HARD_MAX_RECORDING_SECS));
+    }
+
+    info!("⚙️ Max recording length set to {}s", secs);
+    crate::settings::update(|settings| settings.max_recording_secs = Some(secs))?;
+    Ok(())
+}
+
+/// Transcribe a recorded WAV, trying the fallback recognizer if the primary one fails.
+async fn transcribe_recording(audio_file: &str) -> Result<String, String> {
+    match process_audio_with_speech_api(audio_file).await {
+        Ok(text) => {
+            info!("✅ Speech recognition successful: {}", text);
+            Ok(text)
         }
         Err(e) => {
-            return Err(format!("Audio file was not created: {}", e));
+            error!("❌ Windows Speech Recognition failed: {}", e);
+
+            // Try a simpler fallback method
+            match simple_speech_recognition_fallback(audio_file).await {
+                Ok(text) => {
+                    info!("✅ Fallback speech recognition successful: {}", text);
+                    Ok(text)
+                }
+                Err(fallback_error) => {
+                    error!("❌ All speech recognition methods failed. Primary: {}, Fallback: {}", e, fallback_error);
+                    Err(format!("Speech recognition failed. Primary error: {}. Fallback error: {}", e, fallback_error))
+                }
+            }
         }
     }
-
-    Ok(())
 }
 
 // Simple fallback speech recognition method using different approach
@@ -507,10 +828,96 @@ async fn process_audio_with_speech_api(audio_file: &str) -> Result<String, Strin
     }
 }
 
+fn sapi_voices() -> &'static SapiVoices {
+    SAPI_VOICES.get_or_init(|| {
+        let output = Command::new("powershell")
+            .arg("-Command")
+            .arg(
+                "Add-Type -AssemblyName System.Speech; \
+                [System.Globalization.CultureInfo]::CurrentUICulture.Name; \
+                $synth = New-Object System.Speech.Synthesis.SpeechSynthesizer; \
+                $synth.GetInstalledVoices() | Where-Object { $_.Enabled } | \
+                    ForEach-Object { $_.VoiceInfo.Name + '|' + $_.VoiceInfo.Culture.Name }; \
+                $synth.Dispose()",
+            )
+            .output();
+
+        match output {
+            Ok(output) if output.status.success() => {
+                let stdout = String::from_utf8_lossy(&output.stdout);
+                let mut lines = stdout.lines().map(str::trim).filter(|line| !line.is_empty());
+                let locale = lines.next().map(str::to_string);
+                let voices = lines
+                    .filter_map(|line| line.split_once('|'))
+                    .map(|(name, culture)| InstalledVoice { name: name.to_string(), culture: culture.to_string() })
+                    .collect::<Vec<_>>();
+                info!("🔊 Found {} SAPI voice(s), system locale {:?}", voices.len(), locale);
+                SapiVoices { voices, locale: locale.or_else(env_locale) }
+            }
+            _ => {
+                warn!("⚠️ Could not list SAPI voices; SAPI will pick its default");
+                SapiVoices { voices: Vec::new(), locale: env_locale() }
+            }
+        }
+    })
+}
+
+/// The locale from LC_ALL/LANG as a culture name ("en_US.UTF-8" -> "en-US").
+fn env_locale() -> Option<String> {
+    ["LC_ALL", "LANG"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .map(|value| value.split(['.', '@']).next().unwrap_or_default().replace('_', "-"))
+        .find(|locale| !locale.is_empty() && locale != "C" && locale != "POSIX")
+}
+
+/// The configured voice if installed, otherwise the voice whose culture best
+/// matches `locale`: same culture, then same language, then the first voice.
+fn pick_tts_voice(configured: &str, voices: &[InstalledVoice], locale: Option<&str>) -> (Option<InstalledVoice>, bool) {
+    if let Some(voice) = voices.iter().find(|voice| voice.name.eq_ignore_ascii_case(configured)) {
+        return (Some(voice.clone()), false);
+    }
+
+    let language = |culture: &str| culture.split('-').next().unwrap_or_default().to_ascii_lowercase();
+    let best = locale
+        .and_then(|locale| {
+            voices
+                .iter()
+                .find(|voice| voice.culture.eq_ignore_ascii_case(locale))
+                .or_else(|| voices.iter().find(|voice| language(&voice.culture) == language(locale)))
+        })
+        .or_else(|| voices.first());
+    (best.cloned(), best.is_some())
+}
+
+fn selected_tts_voice() -> SelectedTtsVoice {
+    let configured = crate::settings::get().tts.unwrap_or_default().voice_model;
+    let sapi = sapi_voices();
+    let (voice, fell_back) = pick_tts_voice(&configured, &sapi.voices, sapi.locale.as_deref());
+    if let (true, Some(voice)) = (fell_back, &voice) {
+        warn!(
+            "⚠️ TTS voice '{}' is not installed, using '{}' ({}) to match locale {:?}",
+            configured, voice.name, voice.culture, sapi.locale
+        );
+    }
+
+    SelectedTtsVoice { configured, voice, system_locale: sapi.locale.clone(), fell_back }
+}
+
+// The voice spoken replies will actually use, after any locale fallback
+#[command]
+pub async fn get_selected_tts_voice() -> Result<SelectedTtsVoice, String> {
+    Ok(selected_tts_voice())
+}
+
 async fn generate_speech_with_piper(text: &str) -> Result<String, String> {
     info!("Generating speech with Piper");
     
     let output_file = "temp_tts_output.wav";
+    let select_voice = match selected_tts_voice().voice {
+        Some(voice) => format!("$synth.SelectVoice('{}'); ", voice.name.replace("'", "''")),
+        None => String::new(),
+    };
     
     // For Windows, we can use built-in SAPI for now
     // In production, you'd use Piper
@@ -519,10 +926,10 @@ async fn generate_speech_with_piper(text: &str) -> Result<String, String> {
         .arg(format!(
             "Add-Type -AssemblyName System.Speech; \
             $synth = New-Object System.Speech.Synthesis.SpeechSynthesizer; \
-            $synth.SetOutputToWaveFile('{}'); \
+            {}$synth.SetOutputToWaveFile('{}'); \
             $synth.Speak('{}'); \
             $synth.Dispose()",
-            output_file, text.replace("'", "''")
+            select_voice, output_file, text.replace("'", "''")
         ))
         .output()
         .map_err(|e| format!("Failed to execute TTS command: {}", e))?;
@@ -561,13 +968,13 @@ async fn play_audio_file(audio_file: &str) -> Result<(), String> {
 // Configuration commands
 #[command]
 pub async fn get_tts_config() -> Result<TtsConfig, String> {
-    Ok(TtsConfig::default())
+    Ok(crate::settings::get().tts.unwrap_or_default())
 }
 
 #[command]
 pub async fn set_tts_config(config: TtsConfig) -> Result<(), String> {
     info!("TTS config updated: {:?}", config);
-    // In a real implementation, you'd save this to a config file
+    crate::settings::update(|settings| settings.tts = Some(config))?;
     Ok(())
 }
 
@@ -590,6 +997,216 @@ pub async fn test_audio_devices() -> Result<String, String> {
     Ok(result)
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AudioHostReport {
+    pub host: String,
+    pub is_default: bool,
+    pub input_devices: Vec<String>,
+    pub output_devices: Vec<String>,
+    pub default_input: Option<String>,
+    pub default_output: Option<String>,
+    /// Why this host could not be opened or enumerated, if it failed.
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AudioDiagnosis {
+    pub default_host: String,
+    pub hosts: Vec<AudioHostReport>,
+    pub input_available: bool,
+    pub output_available: bool,
+    /// Sample format of the default microphone, e.g. "f32" or "i32".
+    pub input_sample_format: Option<String>,
+    /// Whether recording can convert that format; false means the microphone can't be used.
+    pub input_format_supported: Option<bool>,
+    /// What is wrong, when no usable input or output device was found.
+    pub problem: Option<String>,
+}
+
+// Enumerate every audio host so "no input device" comes with a reason
+#[command]
+pub async fn diagnose_audio() -> Result<AudioDiagnosis, String> {
+    info!("🧪 Diagnosing audio hosts and devices");
+    let diagnosis = tokio::task::spawn_blocking(diagnose_audio_devices)
+        .await
+        .map_err(|e| format!("Audio diagnosis failed: {}", e))?;
+
+    match &diagnosis.problem {
+        Some(problem) => warn!("⚠️ Audio problem: {}", problem),
+        None => info!("✅ Audio devices available on {} host(s)", diagnosis.hosts.len()),
+    }
+    Ok(diagnosis)
+}
+
+fn diagnose_audio_devices() -> AudioDiagnosis {
+    let default_host = cpal::default_host().id();
+    let device_names = |devices: Result<Vec<cpal::Device>, cpal::DevicesError>| {
+        devices.map(|devices| devices.iter().filter_map(|d| d.name().ok()).collect::<Vec<_>>())
+    };
+
+    let hosts: Vec<AudioHostReport> = cpal::available_hosts()
+        .into_iter()
+        .map(|id| {
+            let mut report = AudioHostReport {
+                host: id.name().to_string(),
+                is_default: id == default_host,
+                input_devices: Vec::new(),
+                output_devices: Vec::new(),
+                default_input: None,
+                default_output: None,
+                error: None,
+            };
+
+            let host = match cpal::host_from_id(id) {
+                Ok(host) => host,
+                Err(e) => {
+                    report.error = Some(format!("Host unavailable: {}", e));
+                    return report;
+                }
+            };
+
+            match device_names(host.input_devices().map(|d| d.collect())) {
+                Ok(names) => report.input_devices = names,
+                Err(e) => report.error = Some(format!("Cannot list input devices: {}", e)),
+            }
+            match device_names(host.output_devices().map(|d| d.collect())) {
+                Ok(names) => report.output_devices = names,
+                Err(e) => report.error = Some(format!("Cannot list output devices: {}", e)),
+            }
+            report.default_input = host.default_input_device().and_then(|d| d.name().ok());
+            report.default_output = host.default_output_device().and_then(|d| d.name().ok());
+            report
+        })
+        .collect();
+
+    let input_available = hosts.iter().any(|h| !h.input_devices.is_empty());
+    let output_available = hosts.iter().any(|h| !h.output_devices.is_empty());
+    let default_report = hosts.iter().find(|h| h.is_default);
+    let input_sample_format = cpal::default_host()
+        .default_input_device()
+        .and_then(|device| device.default_input_config().ok())
+        .map(|config| config.sample_format());
+    let input_format_supported = input_sample_format.map(is_supported_input_format);
+
+    let problem = if hosts.is_empty() {
+        Some(format!(
+            "No audio backend is available ({} on this platform); check that the audio system is installed and running",
+            audio_backend_hint()
+        ))
+    } else if !input_available && !output_available {
+        Some("Audio backends are present but report no devices; on a headless or remote system there may be no sound hardware".to_string())
+    } else if !input_available {
+        Some("No microphone was found on any audio host; connect one or check that it isn't disabled".to_string())
+    } else if default_report.map_or(true, |h| h.default_input.is_none()) {
+        Some(format!(
+            "The default audio host ({}) has no default microphone; select one in the system sound settings",
+            default_host.name()
+        ))
+    } else if let (Some(format), Some(false)) = (input_sample_format, input_format_supported) {
+        Some(format!(
+            "The default microphone records in the unsupported {} sample format; select a different input device or change its format in the system sound settings",
+            format
+        ))
+    } else if !output_available {
+        Some("No speakers or headphones were found, so spoken replies cannot be played".to_string())
+    } else {
+        None
+    };
+
+    AudioDiagnosis {
+        default_host: default_host.name().to_string(),
+        hosts,
+        input_available,
+        output_available,
+        input_sample_format: input_sample_format.map(|format| format.to_string()),
+        input_format_supported,
+        problem,
+    }
+}
+
+fn audio_backend_hint() -> &'static str {
+    if cfg!(target_os = "windows") {
+        "WASAPI"
+    } else if cfg!(target_os = "macos") {
+        "CoreAudio"
+    } else {
+        "ALSA"
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TempAudioCleanup {
+    pub files_deleted: u32,
+    pub bytes_freed: u64,
+}
+
+// Remove temp audio files left behind by failed STT cleanups
+#[command]
+pub async fn cleanup_temp_audio(older_than_secs: Option<u64>) -> Result<TempAudioCleanup, String> {
+    let max_age = Duration::from_secs(older_than_secs.unwrap_or(TEMP_AUDIO_MAX_AGE_SECS));
+    Ok(cleanup_temp_audio_files(max_age))
+}
+
+pub fn cleanup_temp_audio_files(max_age: Duration) -> TempAudioCleanup {
+    let temp_dir = std::env::temp_dir();
+    info!("🧹 Scanning {:?} for temp audio files older than {}s", temp_dir, max_age.as_secs());
+
+    let mut result = TempAudioCleanup {
+        files_deleted: 0,
+        bytes_freed: 0,
+    };
+
+    let entries = match std::fs::read_dir(&temp_dir) {
+        Ok(entries) => entries,
+        Err(e) => {
+            warn!("⚠️ Cannot read temp directory: {}", e);
+            return result;
+        }
+    };
+
+    for entry in entries.flatten() {
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        if !is_temp_audio_file(&file_name) {
+            continue;
+        }
+
+        let metadata = match entry.metadata() {
+            Ok(metadata) if metadata.is_file() => metadata,
+            _ => continue,
+        };
+
+        let age = metadata
+            .modified()
+            .ok()
+            .and_then(|modified| modified.elapsed().ok())
+            .unwrap_or_default();
+        if age < max_age {
+            continue;
+        }
+
+        match std::fs::remove_file(entry.path()) {
+            Ok(_) => {
+                result.files_deleted += 1;
+                result.bytes_freed += metadata.len();
+            }
+            Err(e) => warn!("⚠️ Failed to remove temp audio file {}: {}", file_name, e),
+        }
+    }
+
+    info!("🧹 Removed {} temp audio files ({} bytes)", result.files_deleted, result.bytes_freed);
+    result
+}
+
+// Files created by run_vosk_stt and process_audio_data
+fn is_temp_audio_file(file_name: &str) -> bool {
+    if file_name == "privacy_ai_assistant_audio.wav" {
+        return true;
+    }
+
+    file_name.starts_with("voice_recording_")
+        && [".wav", ".webm", ".ogg", ".mp3"].iter().any(|ext| file_name.ends_with(ext))
+}
+
 // Process audio data directly from frontend
 #[command]
 pub async fn process_audio_data(audio_data: String, mime_type: String) -> Result<SttResult, String> {
@@ -659,6 +1276,8 @@ pub async fn process_audio_data(audio_data: String, mime_type: String) -> Result
                 text,
                 confidence: 0.85,
                 success: true,
+                hit_max_length: false,
+                audio_format: None,
             }
         }
         Err(e) => {
@@ -672,6 +1291,8 @@ pub async fn process_audio_data(audio_data: String, mime_type: String) -> Result
                         text,
                         confidence: 0.70,
                         success: true,
+                        hit_max_length: false,
+                        audio_format: None,
                     }
                 }
                 Err(fallback_error) => {
@@ -690,6 +1311,8 @@ pub async fn process_audio_data(audio_data: String, mime_type: String) -> Result
                         text: error_message.to_string(),
                         confidence: 0.0,
                         success: false,
+                        hit_max_length: false,
+                        audio_format: None,
                     }
                 }
             }
@@ -750,6 +1373,8 @@ pub async fn process_audio_file(filename: String, cleanup: bool) -> Result<SttRe
                 text,
                 confidence: 0.85,
                 success: true,
+                hit_max_length: false,
+                audio_format: None,
             }
         }
         Err(e) => {
@@ -763,6 +1388,8 @@ pub async fn process_audio_file(filename: String, cleanup: bool) -> Result<SttRe
                         text,
                         confidence: 0.70,
                         success: true,
+                        hit_max_length: false,
+                        audio_format: None,
                     }
                 }
                 Err(fallback_error) => {
@@ -771,6 +1398,8 @@ pub async fn process_audio_file(filename: String, cleanup: bool) -> Result<SttRe
                         text: format!("Speech recognition failed: {}", e),
                         confidence: 0.0,
                         success: false,
+                        hit_max_length: false,
+                        audio_format: None,
                     }
                 }
             }
@@ -873,12 +1502,75 @@ async fn try_ffmpeg_conversion(input_path: &str, output_path: &str) -> Result<()
     }
 }
 
-// Create a basic WAV file as fallback (simplified approach)
-async fn create_basic_wav_file(input_path: &str, output_path: &str) -> Result<(), String> {
-    info!("🔄 Creating basic WAV file as fallback...");
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FfmpegStatus {
+    pub available: bool,
+    pub version: Option<String>,
+    pub path: Option<String>,
+    /// Setup advice when FFmpeg is missing.
+    pub hint: Option<String>,
+}
 
-    // Read the input file
-    let input_data = std::fs::read(input_path)
+// WebM recordings only convert cleanly when FFmpeg is installed
+#[command]
+pub async fn check_ffmpeg() -> Result<FfmpegStatus, String> {
+    let status = tokio::task::spawn_blocking(probe_ffmpeg)
+        .await
+        .map_err(|e| format!("FFmpeg probe failed: {}", e))?;
+    Ok(status)
+}
+
+pub fn probe_ffmpeg() -> FfmpegStatus {
+    let missing = |reason: String| {
+        warn!("⚠️ FFmpeg unavailable: {}", reason);
+        FfmpegStatus {
+            available: false,
+            version: None,
+            path: None,
+            hint: Some(
+                "FFmpeg was not found. Install it and make sure `ffmpeg` is on your PATH so recorded WebM audio can be converted for speech recognition.".to_string(),
+            ),
+        }
+    };
+
+    let output = match Command::new("ffmpeg").arg("-version").output() {
+        Ok(output) if output.status.success() => output,
+        Ok(output) => return missing(format!("`ffmpeg -version` exited with {}", output.status)),
+        Err(e) => return missing(e.to_string()),
+    };
+
+    // First line looks like "ffmpeg version 6.1.1 Copyright (c) ..."
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let version = stdout
+        .lines()
+        .next()
+        .and_then(|line| line.strip_prefix("ffmpeg version "))
+        .and_then(|rest| rest.split_whitespace().next())
+        .map(|version| version.to_string());
+
+    let binary = if cfg!(windows) { "ffmpeg.exe" } else { "ffmpeg" };
+    let path = std::env::var_os("PATH").and_then(|paths| {
+        std::env::split_paths(&paths)
+            .map(|dir| dir.join(binary))
+            .find(|candidate| candidate.is_file())
+            .map(|found| found.to_string_lossy().to_string())
+    });
+
+    info!("✅ FFmpeg {:?} found at {:?}", version, path);
+    FfmpegStatus {
+        available: true,
+        version,
+        path,
+        hint: None,
+    }
+}
+
+// Create a basic WAV file as fallback (simplified approach)
+async fn create_basic_wav_file(input_path: &str, output_path: &str) -> Result<(), String> {
+    info!("🔄 Creating basic WAV file as fallback...");
+
+    // Read the input file
+    let input_data = std::fs::read(input_path)
         .map_err(|e| format!("Failed to read input file: {}", e))?;
 
     if input_data.is_empty() {
@@ -950,6 +1642,98 @@ pub async fn test_stt_debug() -> Result<String, String> {
     Ok(result)
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SttWarmUp {
+    /// Time to load System.Speech and build a dictation recognizer; None when not on Windows.
+    pub windows_speech_ms: Option<u64>,
+    /// Time to read the Vosk model into the OS file cache; None when no model is installed.
+    pub vosk_model_ms: Option<u64>,
+    pub total_ms: u64,
+    pub errors: Vec<String>,
+}
+
+// Pay the one-off recognizer and model loading cost up front so the first real
+// transcription isn't slow. Each transcription still runs in its own process; what
+// carries over is the OS's cached assemblies and model files.
+#[command]
+pub async fn warm_up_stt(app_handle: tauri::AppHandle) -> Result<SttWarmUp, String> {
+    info!("🔥 Warming up STT engines");
+    let started = std::time::Instant::now();
+    let mut errors = Vec::new();
+
+    let windows_speech_ms = if cfg!(target_os = "windows") {
+        let step = std::time::Instant::now();
+        // Same init as a real transcription, minus the audio
+        let script = r#"
+            try {
+                Add-Type -AssemblyName System.Speech
+                $recognizer = New-Object System.Speech.Recognition.SpeechRecognitionEngine
+                $recognizer.LoadGrammar((New-Object System.Speech.Recognition.DictationGrammar))
+                $recognizer.Dispose()
+                "ok"
+            } catch {
+                "Error: $($_.Exception.Message)"
+            }
+        "#;
+        match tokio::task::spawn_blocking(move || {
+            Command::new("powershell").arg("-ExecutionPolicy").arg("Bypass").arg("-Command").arg(script).output()
+        })
+        .await
+        {
+            Ok(Ok(output)) => {
+                let result = String::from_utf8_lossy(&output.stdout).trim().to_string();
+                if result != "ok" {
+                    errors.push(format!("Windows Speech Recognition: {}", result));
+                }
+            }
+            Ok(Err(e)) => errors.push(format!("Failed to run PowerShell: {}", e)),
+            Err(e) => errors.push(format!("Warm-up task failed: {}", e)),
+        }
+        Some(step.elapsed().as_millis() as u64)
+    } else {
+        None
+    };
+
+    let model_dir = find_vosk_file(&vosk_search_dirs(&app_handle), DEFAULT_VOSK_MODEL, "Vosk model").ok();
+    let vosk_model_ms = if let Some(model_dir) = model_dir.filter(|dir| dir.is_dir()) {
+        let step = std::time::Instant::now();
+        match tokio::task::spawn_blocking(move || read_dir_into_cache(&model_dir)).await {
+            Ok(bytes) => info!("🔥 Read {} bytes of Vosk model", bytes),
+            Err(e) => errors.push(format!("Failed to read Vosk model: {}", e)),
+        }
+        Some(step.elapsed().as_millis() as u64)
+    } else {
+        None
+    };
+
+    let warm_up = SttWarmUp {
+        windows_speech_ms,
+        vosk_model_ms,
+        total_ms: started.elapsed().as_millis() as u64,
+        errors,
+    };
+    info!("✅ STT warm-up finished in {}ms ({} errors)", warm_up.total_ms, warm_up.errors.len());
+    Ok(warm_up)
+}
+
+/// Read every file under `path` so later loads come from the OS page cache.
+fn read_dir_into_cache(path: &std::path::Path) -> u64 {
+    let Ok(entries) = std::fs::read_dir(path) else { return 0 };
+    entries
+        .flatten()
+        .map(|entry| {
+            let path = entry.path();
+            if path.is_dir() {
+                read_dir_into_cache(&path)
+            } else {
+                File::open(&path)
+                    .and_then(|mut file| std::io::copy(&mut file, &mut std::io::sink()))
+                    .unwrap_or(0)
+            }
+        })
+        .sum()
+}
+
 // Test command for debugging STT with static file
 #[command]
 pub async fn test_static_file_stt(file_path: Option<String>) -> Result<SttResult, String> {
@@ -1038,15 +1822,31 @@ async fn test_static_file_stt_internal(file_path: &str) -> Result<SttResult, Str
         }
     }
 
+    // Sample files come in any format; recognition only copes with the recorder's own
+    let (recognition_path, audio_format) = prepare_static_file_audio(&absolute_path_str).await?;
+    let result = recognize_static_file(&absolute_path_str, &recognition_path, audio_format).await;
+    if recognition_path != absolute_path_str {
+        let _ = std::fs::remove_file(&recognition_path);
+    }
+    result
+}
+
+async fn recognize_static_file(
+    absolute_path_str: &str,
+    recognition_path: &str,
+    audio_format: AudioFormatCheck,
+) -> Result<SttResult, String> {
     // Test multiple STT methods
     info!("🔄 Testing Windows Speech Recognition API...");
-    match process_audio_with_speech_api(&absolute_path_str).await {
+    match process_audio_with_speech_api(recognition_path).await {
         Ok(text) => {
             info!("✅ Windows Speech Recognition successful: {}", text);
             return Ok(SttResult {
                 text,
                 confidence: 0.90,
                 success: true,
+                hit_max_length: false,
+                audio_format: Some(audio_format.clone()),
             });
         }
         Err(e) => {
@@ -1054,13 +1854,15 @@ async fn test_static_file_stt_internal(file_path: &str) -> Result<SttResult, Str
             info!("🔄 Trying fallback method...");
 
             // Try fallback method
-            match simple_speech_recognition_fallback(&absolute_path_str).await {
+            match simple_speech_recognition_fallback(recognition_path).await {
                 Ok(text) => {
                     info!("✅ Fallback speech recognition successful: {}", text);
                     return Ok(SttResult {
                         text,
                         confidence: 0.75,
                         success: true,
+                        hit_max_length: false,
+                        audio_format: Some(audio_format.clone()),
                     });
                 }
                 Err(fallback_error) => {
@@ -1071,12 +1873,14 @@ async fn test_static_file_stt_internal(file_path: &str) -> Result<SttResult, Str
                         text: format!(
                             "STT Test Failed:\n\nFile: {}\nSize: {} bytes\n\nPrimary Error: {}\nFallback Error: {}\n\nThis indicates an issue with the Windows Speech Recognition setup or audio file format.",
                             absolute_path_str,
-                            std::fs::metadata(&absolute_path).map(|m| m.len()).unwrap_or(0),
+                            std::fs::metadata(absolute_path_str).map(|m| m.len()).unwrap_or(0),
                             e,
                             fallback_error
                         ),
                         confidence: 0.0,
                         success: false,
+                        hit_max_length: false,
+                        audio_format: Some(audio_format),
                     });
                 }
             }
@@ -1084,6 +1888,155 @@ async fn test_static_file_stt_internal(file_path: &str) -> Result<SttResult, Str
     }
 }
 
+/// Get `path` into 16kHz mono 16-bit PCM for recognition, converting into a temp
+/// file when it isn't. FFmpeg handles any input; WAVs fall back to in-process resampling.
+async fn prepare_static_file_audio(path: &str) -> Result<(String, AudioFormatCheck), String> {
+    let original = WavFormat::read(path);
+    if original.as_ref().map_or(false, WavFormat::is_recognizer_ready) {
+        return Ok((path.to_string(), AudioFormatCheck { original, converted: None }));
+    }
+
+    info!("🔄 Converting {} ({:?}) to 16kHz mono 16-bit PCM", path, original);
+    let output = std::env::temp_dir().join(format!("privacy_ai_assistant_stt_test_{}.wav", uuid::Uuid::new_v4()));
+    let output = output.to_string_lossy().to_string();
+
+    if let Err(ffmpeg_error) = try_ffmpeg_conversion(path, &output).await {
+        if original.is_none() {
+            return Err(format!("{} is not a WAV file and FFmpeg could not convert it: {}", path, ffmpeg_error));
+        }
+        warn!("⚠️ {}; resampling in-process instead", ffmpeg_error);
+        let (input, resampled) = (path.to_string(), output.clone());
+        tokio::task::spawn_blocking(move || resample_wav(&input, &resampled))
+            .await
+            .map_err(|e| format!("Audio conversion task failed: {}", e))??;
+    }
+
+    let converted = WavFormat::read(&output);
+    info!("✅ Converted test file to {:?}", converted);
+    Ok((output, AudioFormatCheck { original, converted }))
+}
+
+/// Downmix a WAV to mono and linearly resample it to the recognizer's format.
+fn resample_wav(input: &str, output: &str) -> Result<(), String> {
+    let mut reader = hound::WavReader::open(input).map_err(|e| format!("Failed to read WAV: {}", e))?;
+    let spec = reader.spec();
+    let samples: Vec<f32> = match spec.sample_format {
+        hound::SampleFormat::Float => reader.samples::<f32>().collect::<Result<_, _>>(),
+        hound::SampleFormat::Int => {
+            let scale = (1i64 << (spec.bits_per_sample - 1)) as f32;
+            reader.samples::<i32>().map(|s| s.map(|s| s as f32 / scale)).collect::<Result<_, _>>()
+        }
+    }
+    .map_err(|e| format!("Failed to decode WAV samples: {}", e))?;
+
+    let channels = spec.channels.max(1) as usize;
+    let mono: Vec<f32> = samples
+        .chunks(channels)
+        .map(|frame| frame.iter().sum::<f32>() / frame.len() as f32)
+        .collect();
+
+    let out_spec = WavSpec {
+        channels: CHANNELS,
+        sample_rate: SAMPLE_RATE,
+        bits_per_sample: 16,
+        sample_format: hound::SampleFormat::Int,
+    };
+    let mut writer = WavWriter::create(output, out_spec)
+        .map_err(|e| format!("Failed to create WAV writer: {}", e))?;
+
+    let step = spec.sample_rate as f64 / SAMPLE_RATE as f64;
+    let out_len = (mono.len() as f64 / step) as usize;
+    for i in 0..out_len {
+        let position = i as f64 * step;
+        let index = position as usize;
+        let current = mono[index];
+        let next = mono.get(index + 1).copied().unwrap_or(current);
+        let sample = current + (next - current) * (position - index as f64) as f32;
+        writer
+            .write_sample((sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16)
+            .map_err(|e| format!("Failed to write sample: {}", e))?;
+    }
+    writer.finalize().map_err(|e| format!("Failed to finalize WAV: {}", e))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SttBenchmark {
+    pub engine: String,
+    pub transcript: String,
+    pub reference_text: String,
+    pub word_error_rate: f64,
+    pub word_errors: usize,
+    pub reference_words: usize,
+    pub latency_ms: u64,
+}
+
+// Transcribe a file and score it against a known transcript
+#[command]
+pub async fn benchmark_stt(reference_text: String, audio_path: String) -> Result<SttBenchmark, String> {
+    info!("⏱️ Benchmarking STT on {}", audio_path);
+
+    if reference_text.trim().is_empty() {
+        return Err("Reference text cannot be empty".to_string());
+    }
+    let absolute_path = std::fs::canonicalize(&audio_path)
+        .map_err(|e| format!("Failed to access file '{}': {}", audio_path, e))?;
+    let wav_path = convert_to_wav(&absolute_path.to_string_lossy()).await?;
+
+    // Same engine order as the file recognition pipeline
+    let started = std::time::Instant::now();
+    let (engine, transcript) = match process_audio_with_speech_api(&wav_path).await {
+        Ok(text) => ("windows_speech", text),
+        Err(e) => {
+            warn!("⚠️ Windows Speech Recognition failed, benchmarking fallback: {}", e);
+            let text = simple_speech_recognition_fallback(&wav_path)
+                .await
+                .map_err(|fallback_error| format!("All STT methods failed. Primary: {}, Fallback: {}", e, fallback_error))?;
+            ("fallback", text)
+        }
+    };
+    let latency_ms = started.elapsed().as_millis() as u64;
+
+    let reference_words = normalize_words(&reference_text);
+    let word_errors = word_edit_distance(&reference_words, &normalize_words(&transcript));
+    let word_error_rate = word_errors as f64 / reference_words.len().max(1) as f64;
+
+    info!("✅ STT benchmark ({}): WER {:.2} in {}ms", engine, word_error_rate, latency_ms);
+    Ok(SttBenchmark {
+        engine: engine.to_string(),
+        transcript,
+        reference_text,
+        word_error_rate,
+        word_errors,
+        reference_words: reference_words.len(),
+        latency_ms,
+    })
+}
+
+// Lowercased words with surrounding punctuation stripped, for WER scoring
+fn normalize_words(text: &str) -> Vec<String> {
+    text.split_whitespace()
+        .map(|word| word.trim_matches(|c: char| !c.is_alphanumeric() && c != '\'').to_lowercase())
+        .filter(|word| !word.is_empty())
+        .collect()
+}
+
+// Substitutions + insertions + deletions to turn `reference` into `hypothesis`
+fn word_edit_distance(reference: &[String], hypothesis: &[String]) -> usize {
+    let mut previous: Vec<usize> = (0..=hypothesis.len()).collect();
+    let mut current = vec![0; hypothesis.len() + 1];
+
+    for (i, ref_word) in reference.iter().enumerate() {
+        current[0] = i + 1;
+        for (j, hyp_word) in hypothesis.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ref_word != hyp_word);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    previous[hypothesis.len()]
+}
+
 // Test command for debugging path escaping
 #[command]
 pub async fn test_path_escaping(test_path: String) -> Result<String, String> {
@@ -1123,35 +2076,16 @@ pub async fn test_path_escaping(test_path: String) -> Result<String, String> {
 
 // 🎤 Vosk Real-time STT Command
 #[tauri::command]
-pub async fn vosk_transcribe(duration: f64) -> Result<SttResult, String> {
-    info!("🎤 Starting Vosk transcription for {} seconds", duration);
-
-    // Get the project root directory (parent of src-tauri)
-    let current_dir = std::env::current_dir()
-        .map_err(|e| format!("Failed to get current directory: {}", e))?;
-
-    // If we're in src-tauri, go up one level to project root
-    let project_root = if current_dir.file_name().and_then(|n| n.to_str()) == Some("src-tauri") {
-        current_dir.parent().unwrap_or(&current_dir).to_path_buf()
-    } else {
-        current_dir.clone()
-    };
-
-    info!("📁 Project root directory: {:?}", project_root);
-
-    // Path to the Vosk integration script (in project root)
-    let script_path = project_root.join("tauri_vosk_integration.py");
-
-    if !script_path.exists() {
-        return Err(format!("Vosk integration script not found: {:?}", script_path));
+pub async fn vosk_transcribe(app_handle: tauri::AppHandle, duration: f64) -> Result<SttResult, String> {
+    let max_secs = max_recording_secs() as f64;
+    let hit_max_length = duration > max_secs;
+    let duration = duration.min(max_secs);
+    if hit_max_length {
+        warn!("⚠️ Requested Vosk recording exceeds the {}s limit, capping", max_secs);
     }
+    info!("🎤 Starting Vosk transcription for {} seconds", duration);
 
-    // Path to Vosk model (in project root)
-    let model_path = project_root.join("vosk-model-small-en-us-0.15");
-
-    if !model_path.exists() {
-        return Err(format!("Vosk model not found: {:?}", model_path));
-    }
+    let VoskResources { script_path, model_path } = resolve_vosk_resources(&app_handle)?;
 
     info!("🐍 Running Vosk script: {:?}", script_path);
     info!("🎤 Using Vosk model: {:?}", model_path);
@@ -1161,7 +2095,6 @@ pub async fn vosk_transcribe(duration: f64) -> Result<SttResult, String> {
         .arg(&script_path)
         .arg(&model_path)
         .arg(duration.to_string())
-        .current_dir(&project_root)
         .output()
         .map_err(|e| format!("Failed to run Vosk script: {}", e))?;
 
@@ -1186,6 +2119,8 @@ pub async fn vosk_transcribe(duration: f64) -> Result<SttResult, String> {
             text: transcript,
             confidence: 1.0, // Vosk doesn't provide confidence scores
             success: true,
+            hit_max_length,
+            audio_format: None,
         })
     } else {
         let error = result["error"].as_str().unwrap_or("Unknown error").to_string();
@@ -1195,26 +2130,588 @@ pub async fn vosk_transcribe(duration: f64) -> Result<SttResult, String> {
             text: String::new(),
             confidence: 0.0,
             success: false,
+            hit_max_length,
+            audio_format: None,
         })
     }
 }
 
-// 🧪 Test Vosk Installation
+/// Directory Vosk models are stored in: the repository root in debug builds, the app data dir otherwise.
+fn vosk_models_dir(app_handle: &tauri::AppHandle) -> Result<std::path::PathBuf, String> {
+    use tauri::Manager;
+
+    if cfg!(debug_assertions) {
+        if let Some(dev_root) = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).parent() {
+            return Ok(dev_root.to_path_buf());
+        }
+    }
+    app_handle.path().app_data_dir()
+        .map_err(|e| format!("Failed to resolve app data directory: {}", e))
+}
+
+/// Directories searched for the Vosk script and models, in priority order.
+fn vosk_search_dirs(app_handle: &tauri::AppHandle) -> Vec<std::path::PathBuf> {
+    use tauri::Manager;
+
+    let mut dirs = Vec::new();
+    if let Ok(models_dir) = vosk_models_dir(app_handle) {
+        dirs.push(models_dir);
+    }
+    if let Ok(resource_dir) = app_handle.path().resource_dir() {
+        if !dirs.contains(&resource_dir) {
+            dirs.push(resource_dir);
+        }
+    }
+    dirs
+}
+
+/// First of `dirs` holding `name`, or an error naming every place that was checked.
+fn find_vosk_file(dirs: &[std::path::PathBuf], name: &str, what: &str) -> Result<std::path::PathBuf, String> {
+    dirs.iter()
+        .map(|dir| dir.join(name))
+        .find(|path| path.exists())
+        .ok_or_else(|| {
+            let expected: Vec<String> = dirs.iter().map(|dir| dir.join(name).to_string_lossy().to_string()).collect();
+            format!("{} not found, expected it at {}", what, expected.join(" or "))
+        })
+}
+
+#[derive(Debug, Clone)]
+struct VoskResources {
+    script_path: std::path::PathBuf,
+    model_path: std::path::PathBuf,
+}
+
+/// Locate the Vosk integration script and default model.
+fn resolve_vosk_resources(app_handle: &tauri::AppHandle) -> Result<VoskResources, String> {
+    let dirs = vosk_search_dirs(app_handle);
+    let script_path = find_vosk_file(&dirs, VOSK_SCRIPT_NAME, "Vosk integration script")?;
+    let model_path = find_vosk_file(&dirs, DEFAULT_VOSK_MODEL, "Vosk model")?;
+    Ok(VoskResources { script_path, model_path })
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VoskPaths {
+    pub models_dir: String,
+    pub search_dirs: Vec<String>,
+    pub script_path: Option<String>,
+    pub model_dir: Option<String>,
+    pub error: Option<String>,
+}
+
+// Where vosk_transcribe looks for its script and model, so setup problems can be shown precisely
 #[tauri::command]
-pub async fn test_vosk_installation() -> Result<String, String> {
-    info!("🧪 Testing Vosk installation");
+pub async fn get_vosk_paths(app_handle: tauri::AppHandle) -> Result<VoskPaths, String> {
+    let models_dir = vosk_models_dir(&app_handle)?;
+    let search_dirs = vosk_search_dirs(&app_handle);
+    let (script_path, model_dir, error) = match resolve_vosk_resources(&app_handle) {
+        Ok(resources) => (Some(resources.script_path), Some(resources.model_path), None),
+        Err(e) => (
+            find_vosk_file(&search_dirs, VOSK_SCRIPT_NAME, "Vosk integration script").ok(),
+            find_vosk_file(&search_dirs, DEFAULT_VOSK_MODEL, "Vosk model").ok(),
+            Some(e),
+        ),
+    };
+
+    Ok(VoskPaths {
+        models_dir: models_dir.to_string_lossy().to_string(),
+        search_dirs: search_dirs.iter().map(|dir| dir.to_string_lossy().to_string()).collect(),
+        script_path: script_path.map(|path| path.to_string_lossy().to_string()),
+        model_dir: model_dir.map(|path| path.to_string_lossy().to_string()),
+        error,
+    })
+}
+
+/// Payload of the `voice-partial`, `voice-final` and `voice-error` events from a Vosk stream.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VoiceTranscript {
+    pub stream_id: String,
+    pub text: String,
+}
+
+// Live transcription from the default mic through one long-lived Vosk recognizer:
+// `voice-partial` while the user speaks, `voice-final` per utterance, until stop_vosk_stream.
+// Built with `vosk-native` the recognizer runs in-process, falling back to the Python script
+// when libvosk can't load the model.
+#[tauri::command]
+pub async fn start_vosk_stream(app_handle: tauri::AppHandle, stream_id: String) -> Result<(), String> {
+    #[cfg(feature = "vosk-native")]
+    match load_native_vosk(&app_handle).await {
+        Ok(recognizer) => return start_native_vosk_stream(app_handle, stream_id, recognizer).await,
+        Err(e) => warn!("⚠️ Native Vosk recognizer unavailable ({}), using the Python script", e),
+    }
+
+    start_python_vosk_stream(app_handle, stream_id).await
+}
+
+#[tauri::command]
+pub async fn stop_vosk_stream(stream_id: String) -> Result<bool, String> {
+    Ok(crate::cancellation::cancel(&format!("{}{}", VOSK_STREAM_TASK_PREFIX, stream_id)))
+}
+
+fn emit_voice_transcript(app_handle: &tauri::AppHandle, event: &str, stream_id: &str, text: &str) {
+    use tauri::Emitter;
+
+    let transcript = VoiceTranscript {
+        stream_id: stream_id.to_string(),
+        text: text.to_string(),
+    };
+    if let Err(e) = app_handle.emit(event, &transcript) {
+        warn!("⚠️ Failed to emit {}: {}", event, e);
+    }
+}
+
+/// Load the default Vosk model and a recognizer for it through libvosk.
+#[cfg(feature = "vosk-native")]
+async fn load_native_vosk(app_handle: &tauri::AppHandle) -> Result<vosk::Recognizer, String> {
+    let model_path = find_vosk_file(&vosk_search_dirs(app_handle), DEFAULT_VOSK_MODEL, "Vosk model")?;
+    info!("🎤 Loading Vosk model {:?} in-process", model_path);
+
+    // Loading a model reads hundreds of megabytes
+    tokio::task::spawn_blocking(move || {
+        vosk::set_log_level(vosk::LogLevel::Error);
+        let model = vosk::Model::new(model_path.to_string_lossy())
+            .ok_or_else(|| format!("libvosk could not load {:?}", model_path))?;
+        vosk::Recognizer::new(&model, SAMPLE_RATE as f32)
+            .ok_or_else(|| "libvosk could not create a recognizer".to_string())
+    })
+    .await
+    .map_err(|e| format!("Vosk model loading failed: {}", e))?
+}
+
+#[cfg(feature = "vosk-native")]
+async fn start_native_vosk_stream(
+    app_handle: tauri::AppHandle,
+    stream_id: String,
+    mut recognizer: vosk::Recognizer,
+) -> Result<(), String> {
+    let task_key = format!("{}{}", VOSK_STREAM_TASK_PREFIX, stream_id);
+    let token = crate::cancellation::register(&task_key);
+
+    let (started_tx, started_rx) = tokio::sync::oneshot::channel();
+    thread::spawn(move || {
+        let mut last_partial = String::new();
+        capture_mic_until_stopped(&token, started_tx, |samples| {
+            match recognizer.accept_waveform(samples) {
+                Ok(vosk::DecodingState::Finalized) => {
+                    last_partial.clear();
+                    let text = recognizer.result().single().map(|result| result.text.trim().to_string()).unwrap_or_default();
+                    if !text.is_empty() {
+                        emit_voice_transcript(&app_handle, "voice-final", &stream_id, &text);
+                    }
+                }
+                Ok(vosk::DecodingState::Running) => {
+                    let partial = recognizer.partial_result().partial.trim().to_string();
+                    // Vosk repeats the same partial for every chunk of silence
+                    if !partial.is_empty() && partial != last_partial {
+                        emit_voice_transcript(&app_handle, "voice-partial", &stream_id, &partial);
+                        last_partial = partial;
+                    }
+                }
+                Ok(vosk::DecodingState::Failed) | Err(_) => {
+                    emit_voice_transcript(&app_handle, "voice-error", &stream_id, "Vosk failed to decode the audio");
+                    return false;
+                }
+            }
+            true
+        });
+
+        let text = recognizer.final_result().single().map(|result| result.text.trim().to_string()).unwrap_or_default();
+        if !text.is_empty() {
+            emit_voice_transcript(&app_handle, "voice-final", &stream_id, &text);
+        }
+        crate::cancellation::unregister(&task_key, &token);
+        info!("⏹️ Vosk stream {} finished", stream_id);
+    });
+
+    started_rx
+        .await
+        .map_err(|_| "Audio capture thread exited unexpectedly".to_string())?
+}
+
+async fn start_python_vosk_stream(app_handle: tauri::AppHandle, stream_id: String) -> Result<(), String> {
+    use std::io::{Read, Write};
+    use std::process::Stdio;
+
+    let VoskResources { script_path, model_path } = resolve_vosk_resources(&app_handle)?;
+    info!("🎤 Starting Vosk stream {} with model {:?}", stream_id, model_path);
+
+    let mut child = Command::new("python")
+        .arg(&script_path)
+        .arg(&model_path)
+        .arg("--stdin")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to start Vosk recognizer: {}", e))?;
+    let (Some(mut recognizer_input), Some(recognizer_output), Some(mut recognizer_errors)) =
+        (child.stdin.take(), child.stdout.take(), child.stderr.take())
+    else {
+        let _ = child.kill();
+        return Err("Failed to connect to the Vosk recognizer".to_string());
+    };
+
+    let task_key = format!("{}{}", VOSK_STREAM_TASK_PREFIX, stream_id);
+    let token = crate::cancellation::register(&task_key);
+
+    // The cpal stream is not Send, so capture runs on its own thread like record_audio_to_file.
+    // Closing the recognizer's stdin on return makes it flush a final result and exit.
+    let (started_tx, started_rx) = tokio::sync::oneshot::channel();
+    let capture_token = token.clone();
+    thread::spawn(move || {
+        capture_mic_until_stopped(&capture_token, started_tx, |samples| {
+            let bytes: Vec<u8> = samples.iter().flat_map(|sample| sample.to_le_bytes()).collect();
+            match recognizer_input.write_all(&bytes) {
+                Ok(()) => true,
+                Err(e) => {
+                    warn!("⚠️ Vosk recognizer stopped accepting audio: {}", e);
+                    false
+                }
+            }
+        })
+    });
+
+    // Import errors and model load failures only show up as a traceback here
+    let stderr_reader = thread::spawn(move || {
+        let mut errors = String::new();
+        let _ = recognizer_errors.read_to_string(&mut errors);
+        errors
+    });
+
+    thread::spawn(move || {
+        use std::io::BufRead;
+
+        for line in std::io::BufReader::new(recognizer_output).lines().map_while(Result::ok) {
+            let Ok(result) = serde_json::from_str::<serde_json::Value>(&line) else {
+                continue;
+            };
+            let event = match result["type"].as_str() {
+                Some("partial") => "voice-partial",
+                Some("final") => "voice-final",
+                Some("error") => "voice-error",
+                _ => continue,
+            };
+            emit_voice_transcript(&app_handle, event, &stream_id, result["text"].as_str().unwrap_or_default());
+        }
+
+        let errors = stderr_reader.join().unwrap_or_default();
+        match child.wait() {
+            Ok(status) if !status.success() => {
+                let detail = errors.trim().lines().last().unwrap_or_default().to_string();
+                error!("❌ Vosk recognizer for {} exited with {}: {}", stream_id, status, errors.trim());
+                let message = if detail.is_empty() {
+                    format!("Vosk recognizer exited with {}", status)
+                } else {
+                    format!("Vosk recognizer failed: {}", detail)
+                };
+                emit_voice_transcript(&app_handle, "voice-error", &stream_id, &message);
+            }
+            Ok(_) => {}
+            Err(e) => warn!("⚠️ Failed to wait for the Vosk recognizer: {}", e),
+        }
+        crate::cancellation::unregister(&task_key, &token);
+        info!("⏹️ Vosk stream {} finished", stream_id);
+    });
+
+    started_rx
+        .await
+        .map_err(|_| "Audio capture thread exited unexpectedly".to_string())?
+}
+
+/// Hand the default mic's audio, with input gain applied, to `on_samples` until `stop` is
+/// cancelled or `on_samples` returns false.
+fn capture_mic_until_stopped<F>(
+    stop: &crate::cancellation::CancellationToken,
+    started: tokio::sync::oneshot::Sender<Result<(), String>>,
+    mut on_samples: F,
+) where
+    F: FnMut(&[i16]) -> bool,
+{
+    use std::sync::mpsc::RecvTimeoutError;
+
+    let (samples_tx, samples_rx) = std::sync::mpsc::channel::<Vec<i16>>();
+    let stream = cpal::default_host()
+        .default_input_device()
+        .ok_or_else(|| format!("No input device available: {}", diagnose_audio_devices().problem.unwrap_or_default()))
+        .and_then(|device| {
+            let config = device.default_input_config()
+                .map_err(|e| format!("Failed to get default input config: {}", e))?;
+            build_i16_input_stream(&device, config.sample_format(), move |data| {
+                let _ = samples_tx.send(data.to_vec());
+            })
+        })
+        .and_then(|stream| stream.play().map(|_| stream).map_err(|e| format!("Failed to start recording: {}", e)));
+    let stream = match stream {
+        Ok(stream) => stream,
+        Err(e) => {
+            error!("❌ {}", e);
+            let _ = started.send(Err(e));
+            return;
+        }
+    };
+    let _ = started.send(Ok(()));
+
+    let gain = input_gain();
+    INPUT_PEAK.store(0, std::sync::atomic::Ordering::Relaxed);
+    while !stop.is_cancelled() {
+        match samples_rx.recv_timeout(Duration::from_millis(100)) {
+            Ok(samples) => {
+                let gained: Vec<i16> = samples
+                    .into_iter()
+                    .map(|sample| {
+                        INPUT_PEAK.fetch_max(sample.unsigned_abs() as u32, std::sync::atomic::Ordering::Relaxed);
+                        apply_gain(sample, gain)
+                    })
+                    .collect();
+                if !on_samples(&gained) {
+                    break;
+                }
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+    }
+    drop(stream);
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VoskModelInfo {
+    pub name: String,
+    pub path: String,
+    pub size_bytes: u64,
+    pub language: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct VoskDownloadProgress {
+    pub name: String,
+    pub stage: String, // "downloading", "extracting", "done"
+    pub downloaded_bytes: u64,
+    pub total_bytes: Option<u64>,
+}
+
+// Models are folders named like vosk-model-small-en-us-0.15 in the models directory
+#[tauri::command]
+pub async fn list_vosk_models(app_handle: tauri::AppHandle) -> Result<Vec<VoskModelInfo>, String> {
+    let root = vosk_models_dir(&app_handle)?;
+    if !root.is_dir() {
+        return Ok(Vec::new());
+    }
+    let entries = std::fs::read_dir(&root)
+        .map_err(|e| format!("Failed to read {:?}: {}", root, e))?;
+
+    let mut models: Vec<VoskModelInfo> = entries
+        .flatten()
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| {
+            let name = entry.file_name().to_str()?.to_string();
+            if !name.starts_with(VOSK_MODEL_PREFIX) {
+                return None;
+            }
+            Some(VoskModelInfo {
+                language: vosk_model_language(&name),
+                size_bytes: directory_size(&entry.path()),
+                path: entry.path().to_string_lossy().to_string(),
+                name,
+            })
+        })
+        .collect();
+
+    models.sort_by(|a, b| a.name.cmp(&b.name));
+    info!("📦 Found {} Vosk models in {:?}", models.len(), root);
+    Ok(models)
+}
+
+#[tauri::command]
+pub async fn download_vosk_model(app_handle: tauri::AppHandle, url: String, name: String) -> Result<VoskModelInfo, String> {
+    use futures_util::StreamExt;
+    use std::io::Write;
+    use tauri::Emitter;
+
+    validate_vosk_model_name(&name)?;
+    let root = vosk_models_dir(&app_handle)?;
+    let model_dir = root.join(&name);
+    if model_dir.exists() {
+        return Err(format!("Vosk model {} is already installed", name));
+    }
+    std::fs::create_dir_all(&root)
+        .map_err(|e| format!("Failed to create {:?}: {}", root, e))?;
+
+    info!("⬇️ Downloading Vosk model {} from {}", name, url);
+    let client = crate::network::build_http_client(&url, "vosk model download", None)?;
+    let response = client
+        .get(&url)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to download Vosk model: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!("Vosk model download failed with status {}", response.status()));
+    }
+
+    let emit_progress = |stage: &str, downloaded_bytes: u64, total_bytes: Option<u64>| {
+        let progress = VoskDownloadProgress {
+            name: name.clone(),
+            stage: stage.to_string(),
+            downloaded_bytes,
+            total_bytes,
+        };
+        if let Err(e) = app_handle.emit("vosk-model-download-progress", &progress) {
+            warn!("⚠️ Failed to emit download progress: {}", e);
+        }
+    };
+
+    // Download next to the destination so the final rename stays on one filesystem
+    let archive_path = root.join(format!(".{}.zip.partial", name));
+    let staging_dir = root.join(format!(".{}.partial", name));
+    let total_bytes = response.content_length();
+    let mut downloaded_bytes = 0u64;
+    let mut last_reported = 0u64;
+
+    let result = async {
+        let mut archive = File::create(&archive_path)
+            .map_err(|e| format!("Failed to create {:?}: {}", archive_path, e))?;
+        let mut stream = response.bytes_stream();
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk.map_err(|e| format!("Vosk model download interrupted: {}", e))?;
+            archive
+                .write_all(&chunk)
+                .map_err(|e| format!("Failed to write {:?}: {}", archive_path, e))?;
+            downloaded_bytes += chunk.len() as u64;
+
+            // Report roughly every megabyte rather than per network chunk
+            if downloaded_bytes - last_reported >= 1024 * 1024 {
+                last_reported = downloaded_bytes;
+                emit_progress("downloading", downloaded_bytes, total_bytes);
+            }
+        }
+        drop(archive);
+
+        emit_progress("extracting", downloaded_bytes, total_bytes);
+        let (archive_path, staging_dir, model_dir) = (archive_path.clone(), staging_dir.clone(), model_dir.clone());
+        tokio::task::spawn_blocking(move || extract_vosk_archive(&archive_path, &staging_dir, &model_dir))
+            .await
+            .map_err(|e| format!("Extraction task failed: {}", e))?
+    }
+    .await;
+
+    let _ = std::fs::remove_file(&archive_path);
+    if let Err(e) = result {
+        let _ = std::fs::remove_dir_all(&staging_dir);
+        error!("❌ Failed to install Vosk model {}: {}", name, e);
+        return Err(e);
+    }
+
+    emit_progress("done", downloaded_bytes, total_bytes);
+    info!("✅ Installed Vosk model {} at {:?}", name, model_dir);
+    Ok(VoskModelInfo {
+        language: vosk_model_language(&name),
+        size_bytes: directory_size(&model_dir),
+        path: model_dir.to_string_lossy().to_string(),
+        name,
+    })
+}
 
-    // Get the project root directory (parent of src-tauri)
-    let current_dir = std::env::current_dir()
-        .map_err(|e| format!("Failed to get current directory: {}", e))?;
+#[tauri::command]
+pub async fn delete_vosk_model(app_handle: tauri::AppHandle, name: String) -> Result<(), String> {
+    validate_vosk_model_name(&name)?;
+    let model_dir = vosk_models_dir(&app_handle)?.join(&name);
+    if !model_dir.is_dir() {
+        return Err(format!("Vosk model not found: {}", name));
+    }
 
-    let project_root = if current_dir.file_name().and_then(|n| n.to_str()) == Some("src-tauri") {
-        current_dir.parent().unwrap_or(&current_dir).to_path_buf()
+    std::fs::remove_dir_all(&model_dir)
+        .map_err(|e| format!("Failed to delete Vosk model {}: {}", name, e))?;
+    info!("🗑️ Deleted Vosk model {}", name);
+    Ok(())
+}
+
+// Model names become directory names, so refuse anything that could escape the models directory
+fn validate_vosk_model_name(name: &str) -> Result<(), String> {
+    let valid_chars = name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.');
+    if !name.starts_with(VOSK_MODEL_PREFIX) || !valid_chars || name.contains("..") {
+        return Err(format!("Invalid Vosk model name: {} (expected {}<name>)", name, VOSK_MODEL_PREFIX));
+    }
+    Ok(())
+}
+
+/// Language code from a model name, e.g. "en-us" for vosk-model-small-en-us-0.15.
+fn vosk_model_language(name: &str) -> Option<String> {
+    let rest = name.strip_prefix(VOSK_MODEL_PREFIX)?;
+    let rest = rest.strip_prefix("small-").unwrap_or(rest);
+    let parts: Vec<&str> = rest
+        .split('-')
+        .take_while(|part| !part.starts_with(|c: char| c.is_ascii_digit()))
+        .collect();
+    if parts.is_empty() {
+        None
     } else {
-        current_dir.clone()
+        Some(parts.join("-"))
+    }
+}
+
+fn directory_size(path: &std::path::Path) -> u64 {
+    std::fs::read_dir(path)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|entry| match entry.metadata() {
+                    Ok(meta) if meta.is_dir() => directory_size(&entry.path()),
+                    Ok(meta) => meta.len(),
+                    Err(_) => 0,
+                })
+                .sum()
+        })
+        .unwrap_or(0)
+}
+
+// Unzip into a staging dir, then move the model folder into place in one rename
+fn extract_vosk_archive(archive_path: &std::path::Path, staging_dir: &std::path::Path, model_dir: &std::path::Path) -> Result<(), String> {
+    let file = File::open(archive_path)
+        .map_err(|e| format!("Failed to open {:?}: {}", archive_path, e))?;
+    let mut archive = zip::ZipArchive::new(file)
+        .map_err(|e| format!("Downloaded file is not a valid zip archive: {}", e))?;
+
+    let _ = std::fs::remove_dir_all(staging_dir);
+    for i in 0..archive.len() {
+        let mut entry = archive.by_index(i).map_err(|e| format!("Corrupt zip entry: {}", e))?;
+        let relative = entry
+            .enclosed_name()
+            .map(|p| p.to_path_buf())
+            .ok_or_else(|| format!("Refusing unsafe path in archive: {}", entry.name()))?;
+        let target = staging_dir.join(relative);
+
+        if entry.is_dir() {
+            std::fs::create_dir_all(&target).map_err(|e| format!("Failed to create {:?}: {}", target, e))?;
+            continue;
+        }
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| format!("Failed to create {:?}: {}", parent, e))?;
+        }
+        let mut out = File::create(&target).map_err(|e| format!("Failed to create {:?}: {}", target, e))?;
+        std::io::copy(&mut entry, &mut out).map_err(|e| format!("Failed to extract {:?}: {}", target, e))?;
+    }
+
+    // Official archives wrap everything in a single top-level folder
+    let top_level: Vec<_> = std::fs::read_dir(staging_dir)
+        .map_err(|e| format!("Failed to read {:?}: {}", staging_dir, e))?
+        .flatten()
+        .collect();
+    let source = match top_level.as_slice() {
+        [only] if only.path().is_dir() => only.path(),
+        _ => staging_dir.to_path_buf(),
     };
 
-    info!("📁 Testing from project root: {:?}", project_root);
+    std::fs::rename(&source, model_dir)
+        .map_err(|e| format!("Failed to move model into {:?}: {}", model_dir, e))?;
+    let _ = std::fs::remove_dir_all(staging_dir);
+    Ok(())
+}
+
+// 🧪 Test Vosk Installation
+#[tauri::command]
+pub async fn test_vosk_installation(app_handle: tauri::AppHandle) -> Result<String, String> {
+    info!("🧪 Testing Vosk installation");
 
     // Check if Python is available
     let python_check = Command::new("python")
@@ -1231,23 +2728,14 @@ pub async fn test_vosk_installation() -> Result<String, String> {
         }
     }
 
-    // Check if Vosk model exists (in project root)
-    let model_path = project_root.join("vosk-model-small-en-us-0.15");
-    if !model_path.exists() {
-        return Err(format!("Vosk model not found: {:?}", model_path));
-    }
-
-    // Check if integration script exists (in project root)
-    let script_path = project_root.join("tauri_vosk_integration.py");
-    if !script_path.exists() {
-        return Err(format!("Vosk integration script not found: {:?}", script_path));
-    }
+    // Check that the Vosk model and integration script can be found
+    let VoskResources { script_path, model_path } = resolve_vosk_resources(&app_handle)?;
+    info!("📁 Found Vosk script {:?} and model {:?}", script_path, model_path);
 
     // Test Vosk import
     let vosk_test = Command::new("python")
         .arg("-c")
         .arg("import vosk; import sounddevice; import numpy; print('Vosk dependencies OK')")
-        .current_dir(&project_root)
         .output()
         .map_err(|e| format!("Failed to test Vosk dependencies: {}", e))?;
 
@@ -1267,6 +2755,12 @@ pub async fn test_vosk_installation() -> Result<String, String> {
 pub async fn start_continuous_voice_chat(stream_id: String) -> Result<String, String> {
     info!("🎤 [Continuous Voice] Starting continuous voice chat with stream ID: {}", stream_id);
 
+    // Only one voice chat runs at a time; starting a new one ends the previous session
+    for key in crate::cancellation::active_keys(VOICE_CHAT_TASK_PREFIX) {
+        info!("⏹️ [Continuous Voice] Stopping previous session {}", key);
+        crate::cancellation::cancel(&key);
+    }
+
     // This is a placeholder implementation
     // In a real implementation, this would:
     // 1. Start continuous audio recording
@@ -1277,6 +2771,8 @@ pub async fn start_continuous_voice_chat(stream_id: String) -> Result<String, St
     // For now, we'll use the existing vosk_transcribe functionality
     // and simulate continuous processing
 
+    let task_key = format!("{}{}", VOICE_CHAT_TASK_PREFIX, stream_id);
+    let token = crate::cancellation::register(&task_key);
     let stream_id_clone = stream_id.clone();
 
     tokio::spawn(async move {
@@ -1286,7 +2782,577 @@ pub async fn start_continuous_voice_chat(stream_id: String) -> Result<String, St
         tokio::time::sleep(Duration::from_millis(100)).await;
 
         info!("✅ [Continuous Voice] Stream {} ready for processing", stream_id_clone);
+
+        // The session stays alive until stopped or replaced
+        token.cancelled().await;
+        crate::cancellation::unregister(&task_key, &token);
+        info!("⏹️ [Continuous Voice] Stream {} stopped", stream_id_clone);
     });
 
     Ok(format!("Continuous voice chat started with stream ID: {}", stream_id))
 }
+
+// End a continuous voice chat session; returns whether one was running
+#[command]
+pub async fn stop_continuous_voice_chat(stream_id: String) -> Result<bool, String> {
+    info!("⏹️ [Continuous Voice] Stopping voice chat stream: {}", stream_id);
+
+    let stopped = crate::cancellation::cancel(&format!("{}{}", VOICE_CHAT_TASK_PREFIX, stream_id));
+    if !stopped {
+        warn!("⚠️ [Continuous Voice] No active voice chat with stream ID: {}", stream_id);
+    }
+    Ok(stopped)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VoiceMessageProgress {
+    pub chat_id: String,
+    /// "recording", "transcribing" or "generating".
+    pub stage: String,
+    pub stream_id: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VoiceMessageResult {
+    pub transcript: String,
+    pub message_id: String,
+    /// The reply streams as `llm-stream-event`s under this ID and is saved to the chat.
+    pub stream_id: String,
+    pub hit_max_length: bool,
+}
+
+// Record, transcribe, add to the chat and stream the reply as a single action.
+// With no duration this is push-to-talk: recording runs until stop_voice_message.
+#[command]
+pub async fn voice_message_to_chat(
+    app_handle: tauri::AppHandle,
+    chat_id: String,
+    duration_secs: Option<u64>,
+) -> Result<VoiceMessageResult, String> {
+    use tauri::Emitter;
+
+    let progress = |stage: &str, stream_id: Option<&str>| {
+        let event = VoiceMessageProgress {
+            chat_id: chat_id.clone(),
+            stage: stage.to_string(),
+            stream_id: stream_id.map(|id| id.to_string()),
+        };
+        if let Err(e) = app_handle.emit("voice-message-progress", &event) {
+            warn!("⚠️ Failed to emit voice message progress: {}", e);
+        }
+    };
+
+    // Fail before recording if the chat doesn't exist
+    crate::chat_store::load_session(&chat_id)?;
+
+    info!("🎤 Voice message for chat {} ({})", chat_id,
+          duration_secs.map_or("push-to-talk".to_string(), |secs| format!("{}s", secs)));
+    progress("recording", None);
+    let task_key = format!("{}{}", VOICE_MESSAGE_TASK_PREFIX, chat_id);
+    let (transcript, hit_max_length) =
+        record_speech(&task_key, duration_secs, || progress("transcribing", None)).await?;
+
+    let mut session = crate::chat_store::load_session(&chat_id)?;
+    let message = crate::chat_store::new_message("user", transcript.clone());
+    let message_id = message.id.clone();
+    session.messages.push(message);
+    crate::chat_store::save_session(&mut session)?;
+
+    let stream_id = format!("voice-{}", uuid::Uuid::new_v4());
+    progress("generating", Some(&stream_id));
+    let stream_id = crate::llm::start_llm_stream(
+        app_handle.clone(),
+        stream_id,
+        crate::chat_store::build_conversation_prompt(&session.messages),
+        crate::chat_store::session_model(&session),
+        None,
+        Some(chat_id.clone()),
+        None,
+    )
+    .await?;
+
+    info!("✅ Voice message added to chat {}, streaming reply {}", chat_id, stream_id);
+    Ok(VoiceMessageResult { transcript, message_id, stream_id, hit_max_length })
+}
+
+// Release push-to-talk for a voice message; returns whether one was recording
+#[command]
+pub async fn stop_voice_message(chat_id: String) -> Result<bool, String> {
+    Ok(crate::cancellation::cancel(&format!("{}{}", VOICE_MESSAGE_TASK_PREFIX, chat_id)))
+}
+
+/// Record and transcribe one utterance, returning the transcript and whether the
+/// length limit cut it off. With no duration, recording runs until `task_key` is cancelled.
+async fn record_speech(
+    task_key: &str,
+    duration_secs: Option<u64>,
+    on_recorded: impl FnOnce(),
+) -> Result<(String, bool), String> {
+    let stop = duration_secs.is_none().then(|| crate::cancellation::register(task_key));
+    let duration = duration_secs.unwrap_or_else(max_recording_secs);
+    let audio_path = std::env::temp_dir().join(format!("privacy_ai_assistant_voice_{}.wav", uuid::Uuid::new_v4()));
+    let audio_path = audio_path.to_string_lossy().to_string();
+
+    let recorded = record_audio_to_file(&audio_path, duration, stop.clone()).await;
+    if let Some(stop) = &stop {
+        crate::cancellation::unregister(task_key, stop);
+    }
+    let hit_max_length = recorded?;
+
+    on_recorded();
+    let transcript = transcribe_recording(&audio_path).await;
+    if let Err(e) = std::fs::remove_file(&audio_path) {
+        warn!("⚠️ Failed to clean up voice recording: {}", e);
+    }
+    let transcript = transcript?;
+    if transcript.trim().is_empty() || transcript == "No speech detected in audio" {
+        return Err("No speech detected in the recording".to_string());
+    }
+    Ok((transcript, hit_max_length))
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DraftText {
+    pub chat_id: String,
+    pub text: String,
+    pub hit_max_length: bool,
+}
+
+// Dictate into a chat's input box: emits `draft-text` and returns the text, but
+// adds no message and starts no generation. No duration means push-to-talk.
+#[command]
+pub async fn transcribe_to_draft(
+    app_handle: tauri::AppHandle,
+    chat_id: String,
+    duration_secs: Option<u64>,
+) -> Result<DraftText, String> {
+    use tauri::Emitter;
+
+    crate::chat_store::load_session(&chat_id)?;
+
+    info!("🎤 Dictating draft for chat {} ({})", chat_id,
+          duration_secs.map_or("push-to-talk".to_string(), |secs| format!("{}s", secs)));
+    let task_key = format!("{}{}", VOICE_DRAFT_TASK_PREFIX, chat_id);
+    let (text, hit_max_length) = record_speech(&task_key, duration_secs, || ()).await?;
+
+    let draft = DraftText { chat_id, text, hit_max_length };
+    if let Err(e) = app_handle.emit("draft-text", &draft) {
+        warn!("⚠️ Failed to emit draft text: {}", e);
+    }
+    info!("✅ Draft dictated for chat {} ({} chars)", draft.chat_id, draft.text.len());
+    Ok(draft)
+}
+
+// Release push-to-talk for dictation; returns whether one was recording
+#[command]
+pub async fn stop_draft_dictation(chat_id: String) -> Result<bool, String> {
+    Ok(crate::cancellation::cancel(&format!("{}{}", VOICE_DRAFT_TASK_PREFIX, chat_id)))
+}
+
+// Generate a reply and speak each sentence as soon as it is complete
+#[command]
+pub async fn speak_llm_reply(prompt: String, model: Option<String>) -> Result<String, String> {
+    use futures_util::StreamExt;
+
+    let model = model.unwrap_or_else(|| crate::llm::LLMConfig::default().model);
+    info!("🗣️ Speaking reply from {} as it generates", model);
+    let mut tokens = crate::llm::stream_tokens(&model, &prompt, None)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let mut reply = String::new();
+    let mut spoken = 0;
+    loop {
+        let finished = match tokens.next().await {
+            Some(Ok(crate::llm::StreamToken::Text(text))) => {
+                reply.push_str(&text);
+                false
+            }
+            Some(Ok(crate::llm::StreamToken::Done(_))) | None => true,
+            Some(Err(e)) => return Err(e.to_string()),
+        };
+
+        // The last chunk may still be mid-sentence until generation finishes
+        let chunks = segment_into_speakable_chunks(&reply);
+        let complete = if finished { chunks.len() } else { chunks.len().saturating_sub(1) };
+        for sentence in chunks.iter().take(complete).skip(spoken) {
+            let audio_file = generate_speech_with_piper(sentence).await?;
+            let played = play_audio_file(&audio_file).await;
+            let _ = std::fs::remove_file(&audio_file);
+            played?;
+        }
+        spoken = spoken.max(complete);
+
+        if finished {
+            break;
+        }
+    }
+
+    info!("✅ Spoke {} sentence(s)", spoken);
+    Ok(reply)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TtsProgress {
+    pub tts_id: String,
+    /// 1-based index of the sentence now being spoken.
+    pub sentence_index: usize,
+    pub sentence_count: usize,
+    /// Characters in the sentences finished before this one.
+    pub chars_done: usize,
+    pub chars_total: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TtsComplete {
+    pub tts_id: String,
+    pub sentences_spoken: usize,
+    pub sentence_count: usize,
+    pub cancelled: bool,
+    pub error: Option<String>,
+}
+
+// Speak long text one sentence at a time, emitting `tts-progress` before each
+// sentence and `tts-complete` at the end
+#[command]
+pub async fn speak_text(app_handle: tauri::AppHandle, text: String, tts_id: String) -> Result<TtsComplete, String> {
+    use tauri::Emitter;
+
+    let sentences = segment_into_speakable_chunks(&text);
+    if sentences.is_empty() {
+        return Err("No text provided for TTS".into());
+    }
+
+    let task_key = format!("{}{}", TTS_TASK_PREFIX, tts_id);
+    let token = crate::cancellation::register(&task_key);
+    let chars_total: usize = sentences.iter().map(|sentence| sentence.chars().count()).sum();
+    info!("🗣️ Speaking {} sentence(s) for {}", sentences.len(), tts_id);
+
+    let mut chars_done = 0;
+    let mut sentences_spoken = 0;
+    let mut error = None;
+    for (index, sentence) in sentences.iter().enumerate() {
+        if token.is_cancelled() {
+            break;
+        }
+
+        let progress = TtsProgress {
+            tts_id: tts_id.clone(),
+            sentence_index: index + 1,
+            sentence_count: sentences.len(),
+            chars_done,
+            chars_total,
+        };
+        if let Err(e) = app_handle.emit("tts-progress", &progress) {
+            warn!("⚠️ Failed to emit TTS progress: {}", e);
+        }
+
+        let spoken = match generate_speech_with_piper(sentence).await {
+            Ok(audio_file) => {
+                let played = play_audio_file(&audio_file).await;
+                let _ = std::fs::remove_file(&audio_file);
+                played.map_err(|e| format!("Audio playback failed: {}", e))
+            }
+            Err(e) => Err(format!("TTS generation failed: {}", e)),
+        };
+        if let Err(e) = spoken {
+            error!("❌ Failed to speak sentence {} of {}: {}", index + 1, sentences.len(), e);
+            error = Some(e);
+            break;
+        }
+        chars_done += sentence.chars().count();
+        sentences_spoken += 1;
+    }
+    crate::cancellation::unregister(&task_key, &token);
+
+    let complete = TtsComplete {
+        tts_id,
+        sentences_spoken,
+        sentence_count: sentences.len(),
+        cancelled: token.is_cancelled(),
+        error,
+    };
+    if let Err(e) = app_handle.emit("tts-complete", &complete) {
+        warn!("⚠️ Failed to emit TTS completion: {}", e);
+    }
+    info!("✅ Spoke {} of {} sentence(s)", complete.sentences_spoken, complete.sentence_count);
+    Ok(complete)
+}
+
+// Stop a speak_text call after the sentence currently playing
+#[command]
+pub async fn stop_speaking(tts_id: String) -> Result<bool, String> {
+    Ok(crate::cancellation::cancel(&format!("{}{}", TTS_TASK_PREFIX, tts_id)))
+}
+
+// Sentence segmentation for speak-while-generating TTS
+#[command]
+pub async fn segment_speakable_text(text: String) -> Result<Vec<String>, String> {
+    Ok(segment_into_speakable_chunks(&text))
+}
+
+/// Split text into sentence-sized chunks suitable for handing to TTS one at a time.
+///
+/// Splits after `.`, `!`, `?` and `…` when followed by whitespace, but not after
+/// abbreviations or initials, inside numbers or URLs, or before a lowercase word.
+/// Fragments shorter than `MIN_SPEAKABLE_CHARS` are merged into a neighbour.
+pub fn segment_into_speakable_chunks(text: &str) -> Vec<String> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut sentences: Vec<String> = Vec::new();
+    let mut start = 0;
+    let mut i = 0;
+
+    while i < chars.len() {
+        if !is_sentence_terminator(chars[i]) {
+            i += 1;
+            continue;
+        }
+
+        // Take the whole run ("?!", "...") plus any closing quotes or brackets
+        let run_start = i;
+        while i < chars.len() && is_sentence_terminator(chars[i]) {
+            i += 1;
+        }
+        let run_len = i - run_start;
+        while i < chars.len() && matches!(chars[i], '"' | '\'' | ')' | ']' | '”' | '’') {
+            i += 1;
+        }
+
+        // Only a boundary when followed by whitespace (rules out decimals and URLs)
+        if i < chars.len() && !chars[i].is_whitespace() {
+            continue;
+        }
+
+        let next_word_start = chars[i..].iter().find(|c| !c.is_whitespace());
+        if next_word_start.map_or(false, |c| c.is_lowercase()) {
+            continue;
+        }
+
+        if chars[run_start] == '.' && run_len == 1 && ends_with_abbreviation(&chars[start..run_start]) {
+            continue;
+        }
+
+        let sentence: String = chars[start..i].iter().collect();
+        if !sentence.trim().is_empty() {
+            sentences.push(sentence.trim().to_string());
+        }
+        start = i;
+    }
+
+    let rest: String = chars[start..].iter().collect();
+    if !rest.trim().is_empty() {
+        sentences.push(rest.trim().to_string());
+    }
+
+    merge_short_fragments(sentences)
+}
+
+fn is_sentence_terminator(c: char) -> bool {
+    matches!(c, '.' | '!' | '?' | '…')
+}
+
+fn ends_with_abbreviation(preceding: &[char]) -> bool {
+    let word: String = preceding
+        .iter()
+        .rev()
+        .take_while(|c| !c.is_whitespace())
+        .collect::<Vec<_>>()
+        .into_iter()
+        .rev()
+        .collect();
+    let word = word.trim_start_matches(|c: char| !c.is_alphanumeric());
+
+    // Single-letter initials like "J. Smith"
+    if word.chars().count() == 1 && word.chars().all(|c| c.is_uppercase()) {
+        return true;
+    }
+
+    ABBREVIATIONS.contains(&word.to_lowercase().as_str())
+}
+
+fn merge_short_fragments(sentences: Vec<String>) -> Vec<String> {
+    let mut chunks: Vec<String> = Vec::new();
+    let mut pending = String::new();
+
+    for sentence in sentences {
+        if !pending.is_empty() {
+            pending.push(' ');
+        }
+        pending.push_str(&sentence);
+
+        if pending.chars().count() >= MIN_SPEAKABLE_CHARS {
+            chunks.push(std::mem::take(&mut pending));
+        }
+    }
+
+    // A short tail joins the previous chunk rather than being spoken alone
+    if !pending.is_empty() {
+        match chunks.last_mut() {
+            Some(last) => {
+                last.push(' ');
+                last.push_str(&pending);
+            }
+            None => chunks.push(pending),
+        }
+    }
+
+    chunks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pick_tts_voice() {
+        let voice = |name: &str, culture: &str| InstalledVoice { name: name.to_string(), culture: culture.to_string() };
+        let voices = vec![
+            voice("Microsoft Hedda Desktop", "de-DE"),
+            voice("Microsoft Hazel Desktop", "en-GB"),
+            voice("Microsoft Zira Desktop", "en-US"),
+        ];
+
+        let (picked, fell_back) = pick_tts_voice("microsoft hazel desktop", &voices, Some("en-US"));
+        assert_eq!(picked.unwrap().name, "Microsoft Hazel Desktop");
+        assert!(!fell_back);
+
+        let (picked, fell_back) = pick_tts_voice("en_US-lessac-medium", &voices, Some("en-US"));
+        assert_eq!(picked.unwrap().name, "Microsoft Zira Desktop");
+        assert!(fell_back);
+
+        // Same language wins over an unrelated first voice
+        let (picked, _) = pick_tts_voice("en_US-lessac-medium", &voices, Some("en-AU"));
+        assert_eq!(picked.unwrap().culture, "en-GB");
+
+        let (picked, _) = pick_tts_voice("en_US-lessac-medium", &voices, Some("fr-FR"));
+        assert_eq!(picked.unwrap().name, "Microsoft Hedda Desktop");
+
+        assert_eq!(pick_tts_voice("en_US-lessac-medium", &[], Some("en-US")), (None, false));
+    }
+
+    #[test]
+    fn test_apply_gain() {
+        assert_eq!(apply_gain(1000, 1.0), 1000);
+        assert_eq!(apply_gain(1000, 2.5), 2500);
+        assert_eq!(apply_gain(-1000, 0.5), -500);
+        assert_eq!(apply_gain(20000, 4.0), i16::MAX);
+        assert_eq!(apply_gain(-20000, 4.0), i16::MIN);
+    }
+
+    #[test]
+    fn test_vosk_model_language() {
+        assert_eq!(vosk_model_language("vosk-model-small-en-us-0.15"), Some("en-us".to_string()));
+        assert_eq!(vosk_model_language("vosk-model-cn-0.22"), Some("cn".to_string()));
+        assert_eq!(vosk_model_language("vosk-model-small-0.1"), None);
+        assert!(validate_vosk_model_name("vosk-model-small-de-0.15").is_ok());
+        assert!(validate_vosk_model_name("vosk-model-../etc").is_err());
+        assert!(validate_vosk_model_name("chats").is_err());
+    }
+
+    #[test]
+    fn test_word_edit_distance() {
+        let reference = normalize_words("The quick brown fox jumps.");
+        assert_eq!(word_edit_distance(&reference, &normalize_words("the quick brown fox jumps")), 0);
+        // One substitution, one deletion
+        assert_eq!(word_edit_distance(&reference, &normalize_words("the quick red fox")), 2);
+        // One insertion
+        assert_eq!(word_edit_distance(&reference, &normalize_words("the very quick brown fox jumps")), 1);
+        assert_eq!(word_edit_distance(&reference, &[]), 5);
+    }
+
+    #[test]
+    fn test_segment_basic_sentences() {
+        let chunks = segment_into_speakable_chunks("The sky is blue. Grass is green! Is water wet?");
+        assert_eq!(chunks, vec!["The sky is blue.", "Grass is green!", "Is water wet?"]);
+    }
+
+    #[test]
+    fn test_segment_keeps_abbreviations_and_initials() {
+        let chunks = segment_into_speakable_chunks(
+            "Dr. Smith met J. Doe at noon. They talked about fruit, e.g. apples. It went well.",
+        );
+        assert_eq!(
+            chunks,
+            vec!["Dr. Smith met J. Doe at noon.", "They talked about fruit, e.g. apples.", "It went well."]
+        );
+    }
+
+    #[test]
+    fn test_segment_decimals_and_urls() {
+        let chunks = segment_into_speakable_chunks(
+            "Pi is roughly 3.14159 today. See https://example.com/docs/v1.2/index.html for more. Thanks for reading.",
+        );
+        assert_eq!(
+            chunks,
+            vec![
+                "Pi is roughly 3.14159 today.",
+                "See https://example.com/docs/v1.2/index.html for more.",
+                "Thanks for reading.",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_segment_ellipses() {
+        // Lowercase continuation stays in the same sentence
+        assert_eq!(
+            segment_into_speakable_chunks("Well... maybe not this time."),
+            vec!["Well... maybe not this time."]
+        );
+        assert_eq!(
+            segment_into_speakable_chunks("I waited for hours... Nobody came back."),
+            vec!["I waited for hours...", "Nobody came back."]
+        );
+        assert_eq!(
+            segment_into_speakable_chunks("It trailed off… Then silence fell."),
+            vec!["It trailed off…", "Then silence fell."]
+        );
+    }
+
+    #[test]
+    fn test_segment_merges_short_fragments() {
+        assert_eq!(
+            segment_into_speakable_chunks("Yes. No. The answer is complicated."),
+            vec!["Yes. No. The answer is complicated."]
+        );
+        assert_eq!(
+            segment_into_speakable_chunks("That is the whole story. Ok."),
+            vec!["That is the whole story. Ok."]
+        );
+    }
+
+    #[test]
+    fn test_segment_quotes_and_trailing_text() {
+        assert_eq!(
+            segment_into_speakable_chunks("She said \"Stop right there!\" Then she left without a word"),
+            vec!["She said \"Stop right there!\"", "Then she left without a word"]
+        );
+        assert!(segment_into_speakable_chunks("   ").is_empty());
+    }
+
+    #[test]
+    fn test_resample_wav_to_recognizer_format() {
+        let dir = std::env::temp_dir();
+        let input = dir.join(format!("resample_in_{}.wav", uuid::Uuid::new_v4())).to_string_lossy().to_string();
+        let output = dir.join(format!("resample_out_{}.wav", uuid::Uuid::new_v4())).to_string_lossy().to_string();
+
+        let spec = WavSpec { channels: 2, sample_rate: 32000, bits_per_sample: 16, sample_format: hound::SampleFormat::Int };
+        let mut writer = WavWriter::create(&input, spec).unwrap();
+        for _ in 0..3200 {
+            writer.write_sample(1000i16).unwrap();
+            writer.write_sample(3000i16).unwrap();
+        }
+        writer.finalize().unwrap();
+        assert!(!WavFormat::read(&input).unwrap().is_recognizer_ready());
+
+        resample_wav(&input, &output).unwrap();
+        assert!(WavFormat::read(&output).unwrap().is_recognizer_ready());
+        let samples: Vec<i16> = hound::WavReader::open(&output).unwrap().samples::<i16>().map(Result::unwrap).collect();
+        // Half the rate, and the two channels averaged
+        assert_eq!(samples.len(), 1600);
+        assert!(samples.iter().all(|s| (s - 2000).abs() <= 1));
+
+        let _ = std::fs::remove_file(&input);
+        let _ = std::fs::remove_file(&output);
+    }
+}
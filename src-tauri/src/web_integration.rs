@@ -1,17 +1,79 @@
 use serde::{Deserialize, Serialize};
-use tauri::command;
+use tauri::{command, AppHandle, Emitter};
+use futures_util::stream::{FuturesUnordered, StreamExt};
 use std::collections::HashMap;
 use chrono::{DateTime, Utc, Duration};
-use reqwest::Client;
+use crate::network::build_http_client;
 use scraper::{Html, Selector};
 use url::Url;
 use log::{info, error, warn};
 use std::time::Instant;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
 use tokio::time::{sleep, Duration as TokioDuration};
 
 // Rate limiting configuration
-const RATE_LIMIT_DELAY_MS: u64 = 2000; // 2 seconds between requests
-static mut LAST_REQUEST_TIME: Option<Instant> = None;
+const RATE_LIMIT_DELAY_MS: u64 = 2000; // 2 seconds between requests to the same host
+const DEFAULT_MAX_CONCURRENT_FETCHES: usize = 3;
+const PAGE_CACHE_TTL_MINUTES: i64 = 15; // Extracted pages are reused for this long
+const PAGE_CACHE_MAX_ENTRIES: usize = 50;
+const BUILTIN_SEARCH_SOURCES: &[&str] = &["wikipedia", "duckduckgo"];
+const CUSTOM_SOURCE_RESULT_LIMIT: usize = 5; // Results taken from each custom source
+const CUSTOM_SOURCE_RELEVANCE: f32 = 0.7; // Between Wikipedia's summary and the fallbacks
+const DEFAULT_MAX_CONTENT_BYTES: usize = 1024 * 1024; // Cap on extracted text and raw HTML per page
+const RESEARCH_DEFAULT_SOURCES: usize = 3; // Search results research() reads when not told otherwise
+const RESEARCH_MAX_SOURCES: usize = 8;
+const RESEARCH_SOURCE_WORDS: usize = 600; // Words of each page included in the research prompt
+const RESEARCH_TASK_PREFIX: &str = "research:";
+const LOCAL_FILE_EXTENSIONS: &[&str] = &["txt", "text", "md", "markdown", "log", "csv"]; // Read as plain text
+const MAX_PDF_BYTES: u64 = 50 * 1024 * 1024; // PDFs are parsed whole, so they can't be cut short like text
+const FILE_REPLY_RESERVE_TOKENS: usize = 1024; // Context left free for the answer to a file prompt
+
+// Earliest time the next request to each host may start
+static NEXT_REQUEST_TIME: Mutex<Option<HashMap<String, Instant>>> = Mutex::new(None);
+
+// Bounded, FIFO-fair queue for page fetches
+static FETCH_QUEUE: Mutex<Option<FetchQueue>> = Mutex::new(None);
+static FETCHES_WAITING: AtomicUsize = AtomicUsize::new(0);
+
+// Recently extracted pages, keyed by URL; memory only, never written to disk
+static PAGE_CACHE: Mutex<Option<HashMap<String, CachedPage>>> = Mutex::new(None);
+
+// Response bytes the web module has downloaded since the app started
+static WEB_BYTES_DOWNLOADED: AtomicU64 = AtomicU64::new(0);
+
+struct CachedPage {
+    page: PageContent,
+    cached_at: DateTime<Utc>,
+    expires_at: DateTime<Utc>,
+}
+
+struct FetchQueue {
+    semaphore: Arc<Semaphore>,
+    max_concurrency: usize,
+    avg_fetch_ms: f64,
+}
+
+impl FetchQueue {
+    fn new(max_concurrency: usize) -> Self {
+        Self {
+            semaphore: Arc::new(Semaphore::new(max_concurrency)),
+            max_concurrency,
+            avg_fetch_ms: 0.0,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WebQueueStatus {
+    pub max_concurrency: usize,
+    pub active: usize,
+    pub queued: usize,
+    pub average_fetch_ms: u64,
+    /// Rough wait for a request enqueued now.
+    pub estimated_wait_ms: u64,
+}
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct SearchResult {
@@ -29,6 +91,84 @@ pub struct SearchResults {
     pub total_results: usize,
     pub search_time_ms: u64,
     pub sources_used: Vec<String>,
+    pub citations: Vec<Citation>,
+    /// Milliseconds each queried source took, including ones that failed.
+    #[serde(default)]
+    pub source_timings: HashMap<String, u64>,
+    /// No connection, so no source was queried.
+    #[serde(default)]
+    pub offline: bool,
+}
+
+/// Emitted as `search-result` when one source of a streamed search returns.
+#[derive(Debug, Serialize, Clone)]
+pub struct SearchResultEvent {
+    pub request_id: String,
+    pub source: String,
+    pub results: Vec<SearchResult>,
+    pub citations: Vec<Citation>,
+}
+
+/// Emitted as `search-complete` once every source of a streamed search has finished.
+#[derive(Debug, Serialize, Clone)]
+pub struct SearchCompleteEvent {
+    pub request_id: String,
+    pub total_results: usize,
+    pub sources_used: Vec<String>,
+    pub failed_sources: Vec<String>,
+    pub search_time_ms: u64,
+}
+
+/// A user-configured search endpoint such as a SearXNG instance.
+///
+/// `url_template` contains `{query}`, which is replaced with the URL-encoded query.
+/// Paths are dot-separated keys into the JSON response, with numbers indexing arrays
+/// (e.g. `data.results` or `items.0.link`).
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CustomSearchSource {
+    pub name: String,
+    pub url_template: String,
+    /// Path to the array of results in the response.
+    pub result_json_path: String,
+    /// Paths within each result; default to SearXNG's `title`, `url` and `content`.
+    pub title_path: String,
+    pub url_path: String,
+    pub snippet_path: String,
+    pub enabled: bool,
+}
+
+/// A web source the assistant drew on, for the chat UI's "Sources" section.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Citation {
+    pub title: String,
+    pub url: String,
+    pub accessed_at: DateTime<Utc>,
+    pub snippet: String,
+}
+
+impl Citation {
+    fn from_search_result(result: &SearchResult, accessed_at: DateTime<Utc>) -> Self {
+        Self {
+            title: result.title.clone(),
+            url: result.url.clone(),
+            accessed_at,
+            snippet: result.snippet.clone(),
+        }
+    }
+
+    fn from_page(page: &PageContent) -> Self {
+        // Prefer the page's own description, otherwise the opening of its content
+        let snippet = page.meta_description.clone().unwrap_or_else(|| {
+            page.content.split_whitespace().take(30).collect::<Vec<_>>().join(" ")
+        });
+
+        Self {
+            title: page.title.trim().to_string(),
+            url: page.url.clone(),
+            accessed_at: page.extracted_at,
+            snippet,
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -39,9 +179,94 @@ pub struct PageContent {
     pub meta_description: Option<String>,
     pub headings: Vec<String>,
     pub links: Vec<String>,
-    pub images: Vec<String>,
+    pub images: Vec<PageImage>,
     pub word_count: usize,
     pub extracted_at: DateTime<Utc>,
+    /// SHA-256 of the whitespace-normalized text; equal for the same article under different URLs.
+    #[serde(default)]
+    pub content_hash: String,
+    /// The fetched HTML, only when asked for with `include_raw_html`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub raw_html: Option<String>,
+    /// Where the fetch joined the queue, when it had to wait for a slot.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub queued: Option<QueuedFetch>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct QueuedFetch {
+    pub position: usize,
+    pub eta_ms: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CachedPageInfo {
+    pub url: String,
+    pub word_count: usize,
+    pub cached_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PageImage {
+    pub src: String,
+    pub alt: Option<String>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PageSummary {
+    pub summary: String,
+    pub source_title: String,
+    pub source_url: String,
+    pub word_count_in: usize,
+    pub word_count_out: usize,
+    pub citations: Vec<Citation>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FileGeneration {
+    pub response: String,
+    pub file_name: String,
+    pub file_path: String,
+    /// Words of the file given to the model, after trimming to the token budget.
+    pub words_included: usize,
+    pub words_total: usize,
+    pub truncated: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WebDataUsage {
+    pub bytes_downloaded: u64,
+    pub cap_bytes: Option<u64>,
+    pub remaining_bytes: Option<u64>,
+    pub cap_reached: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ResearchProgress {
+    pub research_id: String,
+    /// "searching", "fetching" or "summarizing".
+    pub stage: String,
+    pub detail: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ResearchCancelled {
+    pub research_id: String,
+    /// The stage that was running: "searching", "fetching" or "summarizing".
+    pub stage: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ResearchResult {
+    /// The answer streams as `llm-stream-event`s under this ID, which also tags the progress events.
+    pub stream_id: String,
+    /// Numbered in the order the answer cites them as [1], [2], ...
+    pub citations: Vec<Citation>,
+    /// Results whose page couldn't be fetched, so only their search snippet was used.
+    pub snippet_only: Vec<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -56,16 +281,37 @@ pub struct RobotsTxtRules {
 #[command]
 pub async fn search_web(query: String) -> Result<SearchResults, String> {
     info!("🔍 Starting web search for query: {}", query);
+    crate::network::ensure_online("web search")?;
+    ensure_download_allowed()?;
     let start_time = Instant::now();
+
+    // Fail fast rather than waiting out rate limits and timeouts on every source
+    if !crate::network::internet_reachable().await {
+        warn!("📴 Skipping web search: no internet connection");
+        return Ok(SearchResults {
+            query,
+            results: Vec::new(),
+            total_results: 0,
+            search_time_ms: start_time.elapsed().as_millis() as u64,
+            sources_used: Vec::new(),
+            citations: Vec::new(),
+            source_timings: HashMap::new(),
+            offline: true,
+        });
+    }
     
     // Apply rate limiting
-    apply_rate_limit().await;
+    apply_rate_limit("search").await;
     
     let mut all_results = Vec::new();
     let mut sources_used = Vec::new();
+    let mut source_timings = HashMap::new();
     
     // 1. Try Wikipedia first (most reliable for factual information)
-    match search_wikipedia(&query).await {
+    let source_start = Instant::now();
+    let wikipedia_outcome = search_wikipedia(&query).await;
+    source_timings.insert("wikipedia".to_string(), source_start.elapsed().as_millis() as u64);
+    match wikipedia_outcome {
         Ok(mut wikipedia_results) => {
             info!("✅ Wikipedia search returned {} results", wikipedia_results.len());
             all_results.append(&mut wikipedia_results);
@@ -78,7 +324,10 @@ pub async fn search_web(query: String) -> Result<SearchResults, String> {
     
     // 2. Try DuckDuckGo for additional results
     if all_results.len() < 5 {
-        match search_duckduckgo(&query).await {
+        let source_start = Instant::now();
+        let ddg_outcome = search_duckduckgo(&query).await;
+        source_timings.insert("duckduckgo".to_string(), source_start.elapsed().as_millis() as u64);
+        match ddg_outcome {
             Ok(mut ddg_results) => {
                 info!("✅ DuckDuckGo search returned {} results", ddg_results.len());
                 all_results.append(&mut ddg_results);
@@ -90,24 +339,138 @@ pub async fn search_web(query: String) -> Result<SearchResults, String> {
         }
     }
     
+    // 3. Query every enabled custom source
+    for source in enabled_custom_sources() {
+        let source_start = Instant::now();
+        let custom_outcome = search_custom_source(&source, &query).await;
+        source_timings.insert(source.name.clone(), source_start.elapsed().as_millis() as u64);
+        match custom_outcome {
+            Ok(mut custom_results) => {
+                info!("✅ {} search returned {} results", source.name, custom_results.len());
+                all_results.append(&mut custom_results);
+                sources_used.push(source.name.clone());
+            }
+            Err(e) => {
+                warn!("⚠️ {} search failed: {}", source.name, e);
+            }
+        }
+    }
+
     // Sort by relevance score and limit results
     all_results.sort_by(|a, b| b.relevance_score.partial_cmp(&a.relevance_score).unwrap());
     all_results.truncate(10); // Limit to top 10 results
     
     let search_time = start_time.elapsed().as_millis() as u64;
     
-    info!("🔍 Web search completed in {}ms with {} results from {} sources", 
-          search_time, all_results.len(), sources_used.len());
+    info!("🔍 Web search completed in {}ms with {} results from {} sources (per source: {:?})", 
+          search_time, all_results.len(), sources_used.len(), source_timings);
     
+    let accessed_at = Utc::now();
+    let citations = all_results
+        .iter()
+        .map(|result| Citation::from_search_result(result, accessed_at))
+        .collect();
+
     Ok(SearchResults {
         query,
         total_results: all_results.len(),
         results: all_results,
         search_time_ms: search_time,
         sources_used,
+        citations,
+        source_timings,
+        offline: false,
     })
 }
 
+// Web search that emits each source's results as soon as they arrive
+#[command]
+pub async fn start_web_search_stream(app_handle: AppHandle, query: String, request_id: String) -> Result<String, String> {
+    info!("🔍 Starting streamed web search {} for query: {}", request_id, query);
+    crate::network::ensure_online("web search")?;
+
+    if request_id.trim().is_empty() {
+        return Err("Request ID cannot be empty".to_string());
+    }
+
+    let request_id_clone = request_id.clone();
+    tokio::spawn(async move {
+        stream_web_search(app_handle, query, request_id_clone).await;
+    });
+
+    Ok(request_id)
+}
+
+async fn stream_web_search(app_handle: AppHandle, query: String, request_id: String) {
+    let start_time = Instant::now();
+    apply_rate_limit("search").await;
+
+    // Query all sources at once; whichever answers first is emitted first
+    let sources = BUILTIN_SEARCH_SOURCES
+        .iter()
+        .map(|source| source.to_string())
+        .chain(enabled_custom_sources().into_iter().map(|source| source.name));
+    let mut searches: FuturesUnordered<_> = sources
+        .map(|source| search_source(source, &query))
+        .collect();
+
+    let mut total_results = 0;
+    let mut sources_used = Vec::new();
+    let mut failed_sources = Vec::new();
+
+    while let Some((source, outcome)) = searches.next().await {
+        match outcome {
+            Ok(results) => {
+                info!("✅ {} returned {} results for search {}", source, results.len(), request_id);
+                total_results += results.len();
+                sources_used.push(source.clone());
+
+                let accessed_at = Utc::now();
+                let event = SearchResultEvent {
+                    request_id: request_id.clone(),
+                    source,
+                    citations: results
+                        .iter()
+                        .map(|result| Citation::from_search_result(result, accessed_at))
+                        .collect(),
+                    results,
+                };
+                if let Err(e) = app_handle.emit("search-result", &event) {
+                    error!("❌ Failed to emit search result: {}", e);
+                }
+            }
+            Err(e) => {
+                warn!("⚠️ {} search failed: {}", source, e);
+                failed_sources.push(source);
+            }
+        }
+    }
+
+    let event = SearchCompleteEvent {
+        request_id: request_id.clone(),
+        total_results,
+        sources_used,
+        failed_sources,
+        search_time_ms: start_time.elapsed().as_millis() as u64,
+    };
+    info!("🔍 Streamed web search {} completed in {}ms", request_id, event.search_time_ms);
+    if let Err(e) = app_handle.emit("search-complete", &event) {
+        error!("❌ Failed to emit search completion: {}", e);
+    }
+}
+
+async fn search_source(source: String, query: &str) -> (String, Result<Vec<SearchResult>, String>) {
+    let outcome = match source.as_str() {
+        "wikipedia" => search_wikipedia(query).await,
+        "duckduckgo" => search_duckduckgo(query).await,
+        other => match custom_search_source(other) {
+            Some(custom) => search_custom_source(&custom, query).await,
+            None => Err(format!("Unknown search source: {}", other)),
+        },
+    };
+    (source, outcome)
+}
+
 // Navigate to URL with validation
 #[command]
 pub async fn navigate_to_url(url: String) -> Result<(), String> {
@@ -123,7 +486,7 @@ pub async fn navigate_to_url(url: String) -> Result<(), String> {
     }
     
     // Apply rate limiting
-    apply_rate_limit().await;
+    apply_rate_limit(parsed_url.host_str().unwrap_or("")).await;
     
     info!("✅ URL validation passed for: {}", url);
     Ok(())
@@ -131,12 +494,19 @@ pub async fn navigate_to_url(url: String) -> Result<(), String> {
 
 // Extract page content with rate limiting
 #[command]
-pub async fn extract_page_content(url: String) -> Result<PageContent, String> {
+pub async fn extract_page_content(url: String, include_raw_html: Option<bool>) -> Result<PageContent, String> {
     info!("📄 Extracting content from: {}", url);
+    let include_raw_html = include_raw_html.unwrap_or(false);
     
     // Validate URL
     let parsed_url = Url::parse(&url)
         .map_err(|e| format!("Invalid URL: {}", e))?;
+
+    // The cache only holds the extracted text, so raw HTML always means a fresh fetch
+    if let Some(page) = cached_page(&url).filter(|_| !include_raw_html) {
+        info!("📦 Using cached content for {}", url);
+        return Ok(page);
+    }
     
     // Check robots.txt compliance
     match check_robots_txt_compliance(&parsed_url).await {
@@ -155,11 +525,20 @@ pub async fn extract_page_content(url: String) -> Result<PageContent, String> {
         }
     }
     
-    // Apply rate limiting
-    apply_rate_limit().await;
+    // Wait for a slot in the fetch queue, then respect per-host politeness
+    let (position, eta_ms) = queue_position();
+    let queued = (position > 0).then(|| {
+        info!("⏳ Page fetch queued at position {} (ETA ~{}ms): {}", position, eta_ms, url);
+        QueuedFetch { position, eta_ms }
+    });
+    ensure_download_allowed()?;
+    let _permit = acquire_fetch_slot().await;
+    let fetch_started = Instant::now();
+
+    apply_rate_limit(parsed_url.host_str().unwrap_or("")).await;
     
     // Fetch and parse content
-    let client = Client::new();
+    let client = build_http_client(&url, "page extraction", None)?;
     let response = client
         .get(&url)
         .header("User-Agent", "Privacy-AI-Assistant/1.0 (Educational Purpose)")
@@ -171,13 +550,562 @@ pub async fn extract_page_content(url: String) -> Result<PageContent, String> {
         .text()
         .await
         .map_err(|e| format!("Failed to read response: {}", e))?;
+    record_download(html_content.len());
     
-    let page_content = parse_html_content(&url, &html_content)?;
+    record_fetch_duration(fetch_started.elapsed().as_millis() as u64);
+
+    let max_bytes = max_content_bytes();
+    let mut page_content = parse_html_content(&url, &html_content)?;
+    if truncate_to_bytes(&mut page_content.content, max_bytes) {
+        warn!("✂️ Extracted text from {} exceeded {} bytes, truncating", url, max_bytes);
+        page_content.word_count = page_content.content.split_whitespace().count();
+        page_content.content_hash = content_hash(&page_content.content);
+    }
+    cache_page(&url, &page_content);
+
+    if include_raw_html {
+        let mut raw_html = html_content;
+        if truncate_to_bytes(&mut raw_html, max_bytes) {
+            warn!("✂️ Raw HTML from {} exceeded {} bytes, truncating", url, max_bytes);
+        }
+        page_content.raw_html = Some(raw_html);
+    }
+    // Not cached: a later cache hit didn't wait in the queue
+    page_content.queued = queued;
     
     info!("✅ Successfully extracted {} words from {}", page_content.word_count, url);
     Ok(page_content)
 }
 
+pub fn max_content_bytes() -> usize {
+    crate::settings::get().max_content_bytes.unwrap_or(DEFAULT_MAX_CONTENT_BYTES)
+}
+
+/// Cut `text` to at most `max_bytes` on a character boundary; true if anything was dropped.
+fn truncate_to_bytes(text: &mut String, max_bytes: usize) -> bool {
+    if text.len() <= max_bytes {
+        return false;
+    }
+    let mut cut = max_bytes;
+    while !text.is_char_boundary(cut) {
+        cut -= 1;
+    }
+    text.truncate(cut);
+    true
+}
+
+/// SHA-256 of `text` with runs of whitespace collapsed, as lowercase hex.
+pub fn content_hash(text: &str) -> String {
+    use sha2::{Digest, Sha256};
+    let normalized = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    format!("{:x}", Sha256::digest(normalized.as_bytes()))
+}
+
+/// Drop pages whose text duplicates an earlier page's, keeping the first of each.
+pub fn dedupe_page_contents(pages: Vec<PageContent>) -> Vec<PageContent> {
+    let mut seen = std::collections::HashSet::new();
+    let before = pages.len();
+    let unique: Vec<PageContent> = pages
+        .into_iter()
+        .filter(|page| seen.insert(if page.content_hash.is_empty() { content_hash(&page.content) } else { page.content_hash.clone() }))
+        .collect();
+    if unique.len() < before {
+        info!("🧬 Dropped {} duplicate page(s)", before - unique.len());
+    }
+    unique
+}
+
+// Remove pages that carry the same article as an earlier one
+#[command]
+pub async fn dedupe_pages(pages: Vec<PageContent>) -> Result<Vec<PageContent>, String> {
+    Ok(dedupe_page_contents(pages))
+}
+
+pub fn session_download_cap_bytes() -> Option<u64> {
+    crate::settings::get().session_download_cap_bytes
+}
+
+/// Refuse further downloads once this session has used up its download cap.
+fn ensure_download_allowed() -> Result<(), String> {
+    let downloaded = WEB_BYTES_DOWNLOADED.load(Ordering::Relaxed);
+    match session_download_cap_bytes() {
+        Some(cap) if downloaded >= cap => {
+            warn!("🚫 Session download limit reached ({} of {} bytes)", downloaded, cap);
+            Err(format!("Session download limit reached: {} of {} bytes used", downloaded, cap))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn record_download(bytes: usize) {
+    let total = WEB_BYTES_DOWNLOADED.fetch_add(bytes as u64, Ordering::Relaxed) + bytes as u64;
+    if session_download_cap_bytes().map_or(false, |cap| total >= cap) {
+        warn!("📶 Session download cap reached after {} bytes; further web requests will be refused", total);
+    }
+}
+
+/// Read a whole response body, counting it towards the session download total.
+async fn read_body(response: reqwest::Response) -> Result<bytes::Bytes, String> {
+    ensure_download_allowed()?;
+    let body = response.bytes().await.map_err(|e| format!("Failed to read response: {}", e))?;
+    record_download(body.len());
+    Ok(body)
+}
+
+// Bytes downloaded by web search and page fetches this session, against the optional cap
+#[command]
+pub async fn get_web_data_usage() -> Result<WebDataUsage, String> {
+    let bytes_downloaded = WEB_BYTES_DOWNLOADED.load(Ordering::Relaxed);
+    let cap_bytes = session_download_cap_bytes();
+    Ok(WebDataUsage {
+        bytes_downloaded,
+        cap_bytes,
+        remaining_bytes: cap_bytes.map(|cap| cap.saturating_sub(bytes_downloaded)),
+        cap_reached: cap_bytes.map_or(false, |cap| bytes_downloaded >= cap),
+    })
+}
+
+// Limit web downloads for the rest of the session; None removes the limit
+#[command]
+pub async fn set_session_download_cap(cap_bytes: Option<u64>) -> Result<WebDataUsage, String> {
+    info!("⚙️ Session download cap set to {:?} bytes", cap_bytes);
+    crate::settings::update(|settings| settings.session_download_cap_bytes = cap_bytes)?;
+    get_web_data_usage().await
+}
+
+fn cached_page(url: &str) -> Option<PageContent> {
+    let cache = PAGE_CACHE.lock().unwrap();
+    cache
+        .as_ref()
+        .and_then(|pages| pages.get(url))
+        .filter(|cached| cached.expires_at > Utc::now())
+        .map(|cached| cached.page.clone())
+}
+
+fn cache_page(url: &str, page: &PageContent) {
+    let now = Utc::now();
+    let mut cache = PAGE_CACHE.lock().unwrap();
+    let pages = cache.get_or_insert_with(HashMap::new);
+
+    pages.retain(|_, cached| cached.expires_at > now);
+    if pages.len() >= PAGE_CACHE_MAX_ENTRIES {
+        let oldest = pages.iter().min_by_key(|(_, cached)| cached.cached_at).map(|(url, _)| url.clone());
+        if let Some(oldest) = oldest {
+            pages.remove(&oldest);
+        }
+    }
+
+    pages.insert(url.to_string(), CachedPage {
+        page: page.clone(),
+        cached_at: now,
+        expires_at: now + Duration::minutes(PAGE_CACHE_TTL_MINUTES),
+    });
+}
+
+/// Drop every cached page, returning how many were held.
+pub fn clear_cached_pages() -> usize {
+    let cleared = PAGE_CACHE.lock().unwrap().take().map_or(0, |pages| pages.len());
+    info!("🧹 Cleared {} cached pages", cleared);
+    cleared
+}
+
+// What web content is currently held in memory
+#[command]
+pub async fn list_cached_pages() -> Result<Vec<CachedPageInfo>, String> {
+    let now = Utc::now();
+    let cache = PAGE_CACHE.lock().unwrap();
+    let mut pages: Vec<CachedPageInfo> = cache
+        .iter()
+        .flat_map(|pages| pages.iter())
+        .filter(|(_, cached)| cached.expires_at > now)
+        .map(|(url, cached)| CachedPageInfo {
+            url: url.clone(),
+            word_count: cached.page.word_count,
+            cached_at: cached.cached_at,
+            expires_at: cached.expires_at,
+        })
+        .collect();
+    pages.sort_by_key(|page| page.cached_at);
+    Ok(pages)
+}
+
+#[command]
+pub async fn clear_page_cache() -> Result<usize, String> {
+    Ok(clear_cached_pages())
+}
+
+// Search, read the top results and stream an answer that cites them.
+// `request_id` (generated when omitted) names the run for cancel_research and is the answer's stream ID.
+#[command]
+pub async fn research(
+    app_handle: AppHandle,
+    query: String,
+    top_k: Option<usize>,
+    request_id: Option<String>,
+) -> Result<ResearchResult, String> {
+    let stream_id = request_id.unwrap_or_else(|| format!("research-{}", uuid::Uuid::new_v4()));
+    let task_key = format!("{}{}", RESEARCH_TASK_PREFIX, stream_id);
+    let token = crate::cancellation::register(&task_key);
+    let result = run_research(&app_handle, query, top_k, stream_id, &token).await;
+    crate::cancellation::unregister(&task_key, &token);
+    result
+}
+
+// Stop a research run in whatever stage it is in, including its answer stream
+#[command]
+pub async fn cancel_research(app_handle: AppHandle, request_id: String) -> Result<bool, String> {
+    // Searching and fetching watch the research token and report their own stage
+    if crate::cancellation::cancel(&format!("{}{}", RESEARCH_TASK_PREFIX, request_id)) {
+        info!("⏹️ Cancelling research {}", request_id);
+        return Ok(true);
+    }
+
+    // Past that, only the answer stream is left
+    if crate::llm::cancel_stream(&request_id) {
+        info!("⏹️ Cancelled research {} while summarizing", request_id);
+        emit_research_cancelled(&app_handle, &request_id, "summarizing");
+        return Ok(true);
+    }
+
+    warn!("⚠️ No running research with ID {}", request_id);
+    Ok(false)
+}
+
+fn emit_research_cancelled(app_handle: &AppHandle, research_id: &str, stage: &str) {
+    let event = ResearchCancelled {
+        research_id: research_id.to_string(),
+        stage: stage.to_string(),
+    };
+    if let Err(e) = app_handle.emit("research-cancelled", &event) {
+        warn!("⚠️ Failed to emit research cancellation: {}", e);
+    }
+}
+
+async fn run_research(
+    app_handle: &AppHandle,
+    query: String,
+    top_k: Option<usize>,
+    stream_id: String,
+    token: &crate::cancellation::CancellationToken,
+) -> Result<ResearchResult, String> {
+    let top_k = top_k.unwrap_or(RESEARCH_DEFAULT_SOURCES).clamp(1, RESEARCH_MAX_SOURCES);
+    let cancelled = |stage: &str| {
+        info!("⏹️ Research {} cancelled while {}", stream_id, stage);
+        emit_research_cancelled(app_handle, &stream_id, stage);
+        Err(format!("Research cancelled while {}", stage))
+    };
+    let progress = |stage: &str, detail: Option<String>| {
+        let event = ResearchProgress {
+            research_id: stream_id.clone(),
+            stage: stage.to_string(),
+            detail,
+        };
+        if let Err(e) = app_handle.emit("research-progress", &event) {
+            warn!("⚠️ Failed to emit research progress: {}", e);
+        }
+    };
+
+    info!("🔬 Researching '{}' from up to {} sources", query, top_k);
+    progress("searching", None);
+    let search = tokio::select! {
+        search = search_web(query.clone()) => search?,
+        _ = token.cancelled() => return cancelled("searching"),
+    };
+    if search.offline {
+        return Err("Research needs an internet connection".to_string());
+    }
+    let results: Vec<SearchResult> = search.results.into_iter().take(top_k).collect();
+    if results.is_empty() {
+        return Err(format!("No search results for '{}'", query));
+    }
+
+    // Goes through the page cache, robots.txt checks and the fetch queue.
+    // Cancelling drops the in-flight fetches.
+    progress("fetching", Some(format!("{} pages", results.len())));
+    let pages = tokio::select! {
+        pages = futures_util::future::join_all(
+            results.iter().map(|result| extract_page_content(result.url.clone(), None)),
+        ) => pages,
+        _ = token.cancelled() => return cancelled("fetching"),
+    };
+
+    // The same article under several URLs would only be cited twice
+    let mut seen_hashes = std::collections::HashSet::new();
+
+    let accessed_at = Utc::now();
+    let mut citations = Vec::with_capacity(results.len());
+    let mut snippet_only = Vec::new();
+    let mut sources = String::new();
+    for (result, page) in results.iter().zip(pages) {
+        if let Ok(page) = &page {
+            if !seen_hashes.insert(page.content_hash.clone()) {
+                info!("🧬 Skipping {}: same article as an earlier source", result.url);
+                continue;
+            }
+        }
+        let (citation, text) = match page {
+            Ok(page) if page.word_count > 0 => {
+                let excerpt = page.content.split_whitespace().take(RESEARCH_SOURCE_WORDS).collect::<Vec<_>>().join(" ");
+                (Citation::from_page(&page), excerpt)
+            }
+            outcome => {
+                if let Err(e) = outcome {
+                    warn!("⚠️ Using the search snippet for {}: {}", result.url, e);
+                }
+                snippet_only.push(result.url.clone());
+                (Citation::from_search_result(result, accessed_at), result.snippet.clone())
+            }
+        };
+        sources.push_str(&format!("[{}] {} ({})\n{}\n\n", citations.len() + 1, citation.title, citation.url, text));
+        citations.push(citation);
+    }
+
+    let prompt = format!(
+        "Answer the question using only the numbered sources below. Cite them inline as [1], [2] and so on, \
+         and say so if the sources don't answer it.\n\nQuestion: {}\n\nSources:\n{}",
+        query, sources
+    );
+    if token.is_cancelled() {
+        return cancelled("summarizing");
+    }
+    progress("summarizing", None);
+    let stream_id = crate::llm::start_llm_stream(app_handle.clone(), stream_id.clone(), prompt, None, None, None, None).await?;
+    // A cancel that arrived while the stream was starting only reached the research token
+    if token.is_cancelled() {
+        crate::llm::cancel_stream(&stream_id);
+        return cancelled("summarizing");
+    }
+
+    info!("✅ Research for '{}' is streaming as {} ({} sources)", query, stream_id, citations.len());
+    Ok(ResearchResult { stream_id, citations, snippet_only })
+}
+
+// Answer a prompt in a chat with a local text, markdown or PDF file as context.
+// Without `file_path` a file dialog asks for one; a given path must already be in the fs scope,
+// which the frontend's dialog adds picked files to. The text is trimmed to what fits beside
+// the chat's system prompt, history and the prompt itself.
+#[command]
+pub async fn generate_with_file(
+    app_handle: AppHandle,
+    chat_id: String,
+    prompt: String,
+    file_path: Option<String>,
+) -> Result<FileGeneration, String> {
+    use tauri_plugin_dialog::DialogExt;
+    use tauri_plugin_fs::FsExt;
+
+    let path = match file_path {
+        Some(file_path) => {
+            let path = std::path::PathBuf::from(file_path);
+            if !app_handle.fs_scope().is_allowed(&path) {
+                return Err(format!("{:?} was not picked through the file dialog", path));
+            }
+            path
+        }
+        None => {
+            let mut extensions = LOCAL_FILE_EXTENSIONS.to_vec();
+            extensions.push("pdf");
+            let dialog = app_handle.dialog().file().add_filter("Documents", &extensions);
+
+            // The blocking dialog must not run on an async runtime worker
+            let picked = tokio::task::spawn_blocking(move || dialog.blocking_pick_file())
+                .await
+                .map_err(|e| format!("Open dialog failed: {}", e))?;
+
+            picked
+                .ok_or_else(|| "No file selected".to_string())?
+                .into_path()
+                .map_err(|e| format!("Invalid file: {}", e))?
+        }
+    };
+    let file_path = path.to_string_lossy().to_string();
+    let file_name = path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_else(|| file_path.clone());
+    let extension = path.extension().map(|ext| ext.to_string_lossy().to_lowercase()).unwrap_or_default();
+
+    let mut options = tauri_plugin_fs::OpenOptions::new();
+    options.read(true);
+    let file = app_handle
+        .fs()
+        .open(path.clone(), options)
+        .map_err(|e| format!("Failed to open {}: {}", file_name, e))?;
+    let content = if extension == "pdf" {
+        read_pdf_text(file, &file_name).await?
+    } else if LOCAL_FILE_EXTENSIONS.contains(&extension.as_str()) {
+        read_text_file(file, &file_name)?
+    } else {
+        return Err(format!("Unsupported file type '{}'; attach a PDF or one of: {}", extension, LOCAL_FILE_EXTENSIONS.join(", ")));
+    };
+    let words_total = content.split_whitespace().count();
+    if words_total == 0 {
+        return Err(format!("{} has no readable text", file_name));
+    }
+
+    // Whatever the chat, prompt and wrapper already take is not available to the file
+    let prompt_for = |excerpt: &str| format!(
+        "Use the attached file to answer where it is relevant.\n\nFile: {}\n---\n{}\n---\n\n{}",
+        file_name, excerpt, prompt
+    );
+    let fit = crate::llm::check_context_fits(Some(chat_id.clone()), prompt_for(""), None).await?;
+    let budget = (fit.context_limit as usize)
+        .saturating_sub(fit.estimated_tokens)
+        .saturating_sub(FILE_REPLY_RESERVE_TOKENS);
+    if budget == 0 {
+        return Err(format!(
+            "No room for {} in the {}-token context: the chat and prompt already use about {} tokens",
+            file_name, fit.context_limit, fit.estimated_tokens
+        ));
+    }
+    let excerpt = crate::llm::chunk_by_tokens(&content, budget).into_iter().next().unwrap_or_default();
+    let words_included = excerpt.split_whitespace().count();
+    let truncated = words_included < words_total;
+    if truncated {
+        warn!("⚠️ {} is too long for the context window, using the first {} of {} words", file_name, words_included, words_total);
+    }
+
+    info!("📎 Generating for chat {} with {} ({} words)", chat_id, file_name, words_included);
+    let response = crate::llm::generate_llm_response(app_handle, prompt_for(&excerpt), Some(chat_id)).await?.response;
+
+    Ok(FileGeneration {
+        response,
+        file_name,
+        file_path,
+        words_included,
+        words_total,
+        truncated,
+    })
+}
+
+/// Read at most `max_content_bytes` of a file as text, refusing binary files.
+fn read_text_file(file: std::fs::File, file_name: &str) -> Result<String, String> {
+    use std::io::Read;
+
+    let mut bytes = Vec::new();
+    file.take(max_content_bytes() as u64)
+        .read_to_end(&mut bytes)
+        .map_err(|e| format!("Failed to read {}: {}", file_name, e))?;
+    if bytes.contains(&0) {
+        return Err(format!("{} does not look like a text file", file_name));
+    }
+    Ok(String::from_utf8_lossy(&bytes).to_string())
+}
+
+/// Extract a PDF's text, capped at `max_content_bytes` like any other file.
+async fn read_pdf_text(file: std::fs::File, file_name: &str) -> Result<String, String> {
+    use std::io::Read;
+
+    let mut bytes = Vec::new();
+    file.take(MAX_PDF_BYTES + 1)
+        .read_to_end(&mut bytes)
+        .map_err(|e| format!("Failed to read {}: {}", file_name, e))?;
+    if bytes.len() as u64 > MAX_PDF_BYTES {
+        return Err(format!("{} is larger than {} MB", file_name, MAX_PDF_BYTES / (1024 * 1024)));
+    }
+
+    // Parsing is CPU-bound and can take a while on large documents
+    let mut text = tokio::task::spawn_blocking(move || pdf_extract::extract_text_from_mem(&bytes))
+        .await
+        .map_err(|e| format!("PDF extraction failed: {}", e))?
+        .map_err(|e| format!("Could not extract text from {}: {}", file_name, e))?;
+
+    truncate_to_bytes(&mut text, max_content_bytes());
+    Ok(text)
+}
+
+// Extract a page and summarize it as bullet points with the local model
+#[command]
+pub async fn summarize_url(url: String, max_words: Option<usize>) -> Result<PageSummary, String> {
+    let max_words = max_words.unwrap_or(150).clamp(20, 1000);
+    info!("📝 Summarizing {} in up to {} words", url, max_words);
+
+    // Goes through robots.txt checks, the fetch queue and per-host politeness
+    let page = extract_page_content(url.clone(), None).await?;
+    if page.word_count == 0 {
+        return Err("No readable content found on the page".to_string());
+    }
+
+    let client = crate::llm::LLMClient::new()?;
+    let context_length = crate::llm::detect_context_length(&crate::llm::LLMConfig::default().model).await;
+
+    // Leave half the context for instructions and the model's output
+    let chunks = crate::llm::chunk_by_tokens(&page.content, context_length as usize / 2);
+    let mut summary = if chunks.len() == 1 {
+        summarize_text(&client, &page.title, &chunks[0], max_words).await?
+    } else {
+        info!("📚 Page is too long for one pass, summarizing {} chunks", chunks.len());
+        let mut partials = Vec::with_capacity(chunks.len());
+        for chunk in &chunks {
+            partials.push(summarize_text(&client, &page.title, chunk, max_words).await?);
+        }
+        summarize_text(&client, &page.title, &partials.join("\n"), max_words).await?
+    };
+
+    // Models don't always respect the word limit
+    let words: Vec<&str> = summary.split_whitespace().collect();
+    if words.len() > max_words {
+        warn!("⚠️ Summary exceeded {} words, truncating", max_words);
+        // Keep the line structure, cutting the line that crosses the limit mid-way
+        let mut remaining = max_words;
+        let mut truncated = Vec::new();
+        for line in summary.lines() {
+            if remaining == 0 {
+                break;
+            }
+            let line_words: Vec<&str> = line.split_whitespace().take(remaining).collect();
+            remaining -= line_words.len();
+            let indent = &line[..line.len() - line.trim_start().len()];
+            truncated.push(format!("{}{}", indent, line_words.join(" ")));
+        }
+        summary = truncated.join("\n");
+    }
+
+    let word_count_out = summary.split_whitespace().count();
+    info!("✅ Summarized {} words into {} words", page.word_count, word_count_out);
+
+    Ok(PageSummary {
+        summary,
+        citations: vec![Citation::from_page(&page)],
+        source_title: page.title.trim().to_string(),
+        source_url: page.url,
+        word_count_in: page.word_count,
+        word_count_out,
+    })
+}
+
+// Render citations for display; "footnotes" (default) or "list"
+#[command]
+pub async fn format_citations(citations: Vec<Citation>, style: Option<String>) -> Result<String, String> {
+    let style = style.unwrap_or_else(|| "footnotes".to_string());
+    render_citations(&citations, &style)
+}
+
+pub fn render_citations(citations: &[Citation], style: &str) -> Result<String, String> {
+    let lines: Vec<String> = match style {
+        "footnotes" => citations
+            .iter()
+            .enumerate()
+            .map(|(i, c)| format!(
+                "[^{}]: [{}]({}) - accessed {}",
+                i + 1,
+                escape_markdown_link_text(&c.title),
+                c.url,
+                c.accessed_at.format("%Y-%m-%d")
+            ))
+            .collect(),
+        "list" => citations
+            .iter()
+            .map(|c| format!(
+                "- [{}]({}) (accessed {})",
+                escape_markdown_link_text(&c.title),
+                c.url,
+                c.accessed_at.format("%Y-%m-%d")
+            ))
+            .collect(),
+        other => return Err(format!("Unknown citation style: {}", other)),
+    };
+
+    Ok(lines.join("\n"))
+}
+
 // Check robots.txt rules
 #[command]
 pub async fn check_robots_txt(domain: String) -> Result<RobotsTxtRules, String> {
@@ -187,27 +1115,284 @@ pub async fn check_robots_txt(domain: String) -> Result<RobotsTxtRules, String>
     check_robots_txt_compliance(&Url::parse(&robots_url).unwrap()).await
 }
 
-// Helper functions
+// Current state of the page fetch queue
+#[command]
+pub async fn get_web_queue_status() -> Result<WebQueueStatus, String> {
+    let guard = FETCH_QUEUE.lock().unwrap();
+    let (max_concurrency, available, avg_fetch_ms) = match guard.as_ref() {
+        Some(queue) => (queue.max_concurrency, queue.semaphore.available_permits(), queue.avg_fetch_ms),
+        None => (DEFAULT_MAX_CONCURRENT_FETCHES, DEFAULT_MAX_CONCURRENT_FETCHES, 0.0),
+    };
+    drop(guard);
 
-async fn apply_rate_limit() {
-    unsafe {
-        if let Some(last_time) = LAST_REQUEST_TIME {
-            let elapsed = last_time.elapsed();
-            if elapsed.as_millis() < RATE_LIMIT_DELAY_MS as u128 {
-                let sleep_time = RATE_LIMIT_DELAY_MS - elapsed.as_millis() as u64;
-                sleep(TokioDuration::from_millis(sleep_time)).await;
-            }
+    let (_, estimated_wait_ms) = queue_position();
+    Ok(WebQueueStatus {
+        max_concurrency,
+        active: max_concurrency.saturating_sub(available),
+        queued: FETCHES_WAITING.load(Ordering::SeqCst),
+        average_fetch_ms: avg_fetch_ms as u64,
+        estimated_wait_ms,
+    })
+}
+
+// Change how many page fetches may run at once
+#[command]
+pub async fn set_web_queue_concurrency(max_concurrency: usize) -> Result<(), String> {
+    if max_concurrency == 0 || max_concurrency > 16 {
+        return Err("Max concurrency must be between 1 and 16".to_string());
+    }
+
+    info!("⚙️ Setting web fetch concurrency to {}", max_concurrency);
+    let mut guard = FETCH_QUEUE.lock().unwrap();
+    let avg_fetch_ms = guard.as_ref().map_or(0.0, |queue| queue.avg_fetch_ms);
+
+    // In-flight fetches keep their permits on the old semaphore and finish normally
+    let mut queue = FetchQueue::new(max_concurrency);
+    queue.avg_fetch_ms = avg_fetch_ms;
+    *guard = Some(queue);
+    Ok(())
+}
+
+// Add or replace a custom search source; it is enabled and joins search_web immediately
+#[command]
+pub async fn register_custom_search_source(
+    name: String,
+    url_template: String,
+    result_json_path: String,
+    title_path: Option<String>,
+    url_path: Option<String>,
+    snippet_path: Option<String>,
+) -> Result<CustomSearchSource, String> {
+    let name = name.trim().to_string();
+    let source = CustomSearchSource {
+        name: name.clone(),
+        url_template,
+        result_json_path: result_json_path.trim().to_string(),
+        title_path: title_path.unwrap_or_else(|| "title".to_string()),
+        url_path: url_path.unwrap_or_else(|| "url".to_string()),
+        snippet_path: snippet_path.unwrap_or_else(|| "content".to_string()),
+        enabled: true,
+    };
+    validate_custom_search_source(&source)?;
+
+    info!("🔍 Registering custom search source {}", name);
+    let stored = source.clone();
+    crate::settings::update(move |settings| {
+        settings.custom_search_sources.retain(|existing| existing.name != stored.name);
+        settings.custom_search_sources.push(stored);
+    })?;
+    Ok(source)
+}
+
+#[command]
+pub async fn list_custom_search_sources() -> Result<Vec<CustomSearchSource>, String> {
+    Ok(crate::settings::get().custom_search_sources)
+}
+
+#[command]
+pub async fn set_custom_search_source_enabled(name: String, enabled: bool) -> Result<(), String> {
+    custom_search_source(&name).ok_or_else(|| format!("No custom search source named {}", name))?;
+
+    info!("⚙️ Custom search source {} {}", name, if enabled { "enabled" } else { "disabled" });
+    crate::settings::update(|settings| {
+        for source in settings.custom_search_sources.iter_mut().filter(|source| source.name == name) {
+            source.enabled = enabled;
         }
-        LAST_REQUEST_TIME = Some(Instant::now());
+    })?;
+    Ok(())
+}
+
+#[command]
+pub async fn remove_custom_search_source(name: String) -> Result<bool, String> {
+    let before = crate::settings::get().custom_search_sources.len();
+    let after = crate::settings::update(|settings| {
+        settings.custom_search_sources.retain(|source| source.name != name);
+    })?
+    .custom_search_sources
+    .len();
+    Ok(after < before)
+}
+
+// Run one query against a custom source, enabled or not, to check its template and paths
+#[command]
+pub async fn test_custom_search_source(name: String, query: String) -> Result<Vec<SearchResult>, String> {
+    let source = custom_search_source(&name).ok_or_else(|| format!("No custom search source named {}", name))?;
+    crate::network::ensure_online("web search")?;
+
+    info!("🔍 Testing custom search source {} with query: {}", name, query);
+    let results = search_custom_source(&source, &query).await?;
+    if results.is_empty() {
+        warn!("⚠️ {} returned no results; check result_json_path and the field paths", name);
+    }
+    Ok(results)
+}
+
+// Helper functions
+
+pub(crate) fn validate_custom_search_source(source: &CustomSearchSource) -> Result<(), String> {
+    if source.name.trim().is_empty() {
+        return Err("Search source name cannot be empty".to_string());
+    }
+    if BUILTIN_SEARCH_SOURCES.contains(&source.name.as_str()) {
+        return Err(format!("{} is a built-in search source", source.name));
+    }
+    if !source.url_template.contains("{query}") {
+        return Err(format!("URL template for {} must contain {{query}}", source.name));
+    }
+    let example_url = Url::parse(&source.url_template.replace("{query}", "test"))
+        .map_err(|e| format!("Invalid URL template for {}: {}", source.name, e))?;
+    if example_url.scheme() != "http" && example_url.scheme() != "https" {
+        return Err(format!("URL template for {} must use http or https", source.name));
+    }
+    Ok(())
+}
+
+/// Remove credentials (userinfo and key/token query parameters) from a source's URL
+/// template. Returns whether anything was removed.
+pub(crate) fn strip_url_template_secrets(source: &mut CustomSearchSource) -> bool {
+    const PLACEHOLDER: &str = "__query__";
+    let Ok(mut url) = Url::parse(&source.url_template.replace("{query}", PLACEHOLDER)) else {
+        return false;
+    };
+
+    let is_secret = |key: &str| {
+        let key = key.to_lowercase();
+        ["key", "token", "secret", "password", "auth"].iter().any(|marker| key.contains(marker))
+    };
+    let pairs: Vec<(String, String)> = url.query_pairs().map(|(k, v)| (k.into_owned(), v.into_owned())).collect();
+    let kept: Vec<&(String, String)> = pairs.iter().filter(|(key, _)| !is_secret(key)).collect();
+    let had_userinfo = !url.username().is_empty() || url.password().is_some();
+    if kept.len() == pairs.len() && !had_userinfo {
+        return false;
+    }
+
+    let _ = url.set_username("");
+    let _ = url.set_password(None);
+    if kept.is_empty() {
+        url.set_query(None);
+    } else {
+        url.query_pairs_mut().clear().extend_pairs(kept.iter().map(|(k, v)| (k, v)));
+    }
+    source.url_template = url.to_string().replace(PLACEHOLDER, "{query}");
+    true
+}
+
+fn custom_search_source(name: &str) -> Option<CustomSearchSource> {
+    crate::settings::get()
+        .custom_search_sources
+        .into_iter()
+        .find(|source| source.name == name)
+}
+
+fn enabled_custom_sources() -> Vec<CustomSearchSource> {
+    crate::settings::get()
+        .custom_search_sources
+        .into_iter()
+        .filter(|source| source.enabled)
+        .collect()
+}
+
+/// Follow a dot-separated path through a JSON value; an empty path is the value itself.
+fn json_at_path<'a>(value: &'a serde_json::Value, path: &str) -> Option<&'a serde_json::Value> {
+    path.split('.')
+        .filter(|segment| !segment.is_empty())
+        .try_fold(value, |current, segment| match current {
+            serde_json::Value::Array(items) => items.get(segment.parse::<usize>().ok()?),
+            _ => current.get(segment),
+        })
+}
+
+fn escape_markdown_link_text(text: &str) -> String {
+    text.trim().replace('[', "\\[").replace(']', "\\]")
+}
+
+async fn summarize_text(
+    client: &crate::llm::LLMClient,
+    title: &str,
+    text: &str,
+    max_words: usize,
+) -> Result<String, String> {
+    let instructions = format!(
+        "Summarize the following content from the web page \"{}\" as concise bullet points starting with \"- \". \
+         Use at most {} words in total and only state facts found in the content.",
+        title.trim(),
+        max_words
+    );
+    let prompt = crate::llm::combine_with_system_prompt(Some(&instructions), text.to_string());
+
+    client
+        .generate_response(prompt)
+        .await
+        .map_err(|e| format!("Failed to summarize content: {}", e))
+}
+
+async fn acquire_fetch_slot() -> OwnedSemaphorePermit {
+    let semaphore = {
+        let mut guard = FETCH_QUEUE.lock().unwrap();
+        guard
+            .get_or_insert_with(|| FetchQueue::new(DEFAULT_MAX_CONCURRENT_FETCHES))
+            .semaphore
+            .clone()
+    };
+
+    FETCHES_WAITING.fetch_add(1, Ordering::SeqCst);
+    let permit = semaphore.acquire_owned().await;
+    FETCHES_WAITING.fetch_sub(1, Ordering::SeqCst);
+
+    // The semaphore is never closed, so acquiring cannot fail
+    permit.expect("fetch queue semaphore closed")
+}
+
+// Position a new request would take in the queue and its estimated wait
+fn queue_position() -> (usize, u64) {
+    let guard = FETCH_QUEUE.lock().unwrap();
+    let (available, max_concurrency, avg_fetch_ms) = match guard.as_ref() {
+        Some(queue) => (queue.semaphore.available_permits(), queue.max_concurrency, queue.avg_fetch_ms),
+        None => return (0, 0),
+    };
+
+    if available > 0 {
+        return (0, 0);
+    }
+
+    let position = FETCHES_WAITING.load(Ordering::SeqCst) + 1;
+    let rounds = (position as f64 / max_concurrency as f64).ceil();
+    (position, (rounds * avg_fetch_ms) as u64)
+}
+
+fn record_fetch_duration(duration_ms: u64) {
+    if let Some(queue) = FETCH_QUEUE.lock().unwrap().as_mut() {
+        // Exponential moving average keeps the ETA responsive to network changes
+        queue.avg_fetch_ms = if queue.avg_fetch_ms == 0.0 {
+            duration_ms as f64
+        } else {
+            queue.avg_fetch_ms * 0.8 + duration_ms as f64 * 0.2
+        };
+    }
+}
+
+// Space out requests to the same host; different hosts don't wait on each other
+async fn apply_rate_limit(host: &str) {
+    let wait = {
+        let mut guard = NEXT_REQUEST_TIME.lock().unwrap();
+        let next_times = guard.get_or_insert_with(HashMap::new);
+        let now = Instant::now();
+        let start_at = next_times.get(host).copied().filter(|t| *t > now).unwrap_or(now);
+        next_times.insert(host.to_string(), start_at + TokioDuration::from_millis(RATE_LIMIT_DELAY_MS));
+        start_at - now
+    };
+
+    if !wait.is_zero() {
+        sleep(wait).await;
     }
 }
 
 async fn search_wikipedia(query: &str) -> Result<Vec<SearchResult>, String> {
-    let client = Client::new();
     let search_url = format!(
         "https://en.wikipedia.org/api/rest_v1/page/summary/{}",
         urlencoding::encode(query)
     );
+    let client = build_http_client(&search_url, "web search", None)?;
     
     let response = client
         .get(&search_url)
@@ -217,9 +1402,7 @@ async fn search_wikipedia(query: &str) -> Result<Vec<SearchResult>, String> {
         .map_err(|e| format!("Wikipedia API error: {}", e))?;
     
     if response.status().is_success() {
-        let json: serde_json::Value = response
-            .json()
-            .await
+        let json: serde_json::Value = serde_json::from_slice(&read_body(response).await?)
             .map_err(|e| format!("Failed to parse Wikipedia response: {}", e))?;
         
         if let (Some(title), Some(extract), Some(url)) = (
@@ -242,6 +1425,48 @@ async fn search_wikipedia(query: &str) -> Result<Vec<SearchResult>, String> {
     }
 }
 
+async fn search_custom_source(source: &CustomSearchSource, query: &str) -> Result<Vec<SearchResult>, String> {
+    let search_url = source.url_template.replace("{query}", &urlencoding::encode(query));
+    let parsed = Url::parse(&search_url).map_err(|e| format!("Invalid URL for {}: {}", source.name, e))?;
+    apply_rate_limit(parsed.host_str().unwrap_or(&source.name)).await;
+
+    let client = build_http_client(&search_url, "web search", None)?;
+    let response = client
+        .get(&search_url)
+        .header("User-Agent", "Privacy-AI-Assistant/1.0")
+        .header("Accept", "application/json")
+        .send()
+        .await
+        .map_err(|e| format!("{} request failed: {}", source.name, e))?;
+    if !response.status().is_success() {
+        return Err(format!("{} returned status {}", source.name, response.status()));
+    }
+
+    let json: serde_json::Value = serde_json::from_slice(&read_body(response).await?)
+        .map_err(|e| format!("Failed to parse {} response as JSON: {}", source.name, e))?;
+    let items = json_at_path(&json, &source.result_json_path)
+        .and_then(|results| results.as_array())
+        .ok_or_else(|| format!("No result array at '{}' in the {} response", source.result_json_path, source.name))?;
+
+    let text_at = |item: &serde_json::Value, path: &str| {
+        json_at_path(item, path).and_then(|value| value.as_str()).map(|text| text.trim().to_string())
+    };
+    Ok(items
+        .iter()
+        .filter_map(|item| {
+            let url = text_at(item, &source.url_path).filter(|url| !url.is_empty())?;
+            Some(SearchResult {
+                title: text_at(item, &source.title_path).unwrap_or_else(|| url.clone()),
+                snippet: text_at(item, &source.snippet_path).unwrap_or_default(),
+                url,
+                source: source.name.clone(),
+                relevance_score: CUSTOM_SOURCE_RELEVANCE,
+            })
+        })
+        .take(CUSTOM_SOURCE_RESULT_LIMIT)
+        .collect())
+}
+
 async fn search_duckduckgo(query: &str) -> Result<Vec<SearchResult>, String> {
     // Note: DuckDuckGo doesn't provide a public API for search results
     // This is a placeholder implementation
@@ -269,7 +1494,7 @@ async fn check_robots_txt_compliance(url: &Url) -> Result<RobotsTxtRules, String
     let domain = url.host_str().ok_or("Invalid domain")?;
     let robots_url = format!("https://{}/robots.txt", domain);
     
-    let client = Client::new();
+    let client = build_http_client(&robots_url, "robots.txt check", None)?;
     let response = client
         .get(&robots_url)
         .header("User-Agent", "Privacy-AI-Assistant/1.0")
@@ -279,6 +1504,7 @@ async fn check_robots_txt_compliance(url: &Url) -> Result<RobotsTxtRules, String
     match response {
         Ok(resp) if resp.status().is_success() => {
             let robots_content = resp.text().await.unwrap_or_default();
+            record_download(robots_content.len());
             parse_robots_txt(&robots_content)
         }
         _ => {
@@ -322,6 +1548,12 @@ fn parse_robots_txt(content: &str) -> Result<RobotsTxtRules, String> {
     })
 }
 
+/// Parse an HTML size attribute such as `"300"` or `"300px"`.
+fn parse_dimension(value: &str) -> Option<u32> {
+    let digits: String = value.trim().chars().take_while(|c| c.is_ascii_digit()).collect();
+    digits.parse().ok()
+}
+
 fn parse_html_content(url: &str, html: &str) -> Result<PageContent, String> {
     let document = Html::parse_document(html);
     
@@ -364,15 +1596,33 @@ fn parse_html_content(url: &str, html: &str) -> Result<PageContent, String> {
         .map(|s| s.to_string())
         .collect();
     
-    // Extract images
+    // Extract images with their alt text and declared size, skipping tracking pixels
     let img_selector = Selector::parse("img[src]").unwrap();
+    let base_url = Url::parse(url).ok();
     let images = document
         .select(&img_selector)
-        .filter_map(|el| el.value().attr("src"))
-        .map(|s| s.to_string())
+        .filter_map(|el| {
+            let element = el.value();
+            let src = element.attr("src")?.trim();
+            let src = match &base_url {
+                Some(base) => base.join(src).map(|u| u.to_string()).unwrap_or_else(|_| src.to_string()),
+                None => src.to_string(),
+            };
+            let width = element.attr("width").and_then(parse_dimension);
+            let height = element.attr("height").and_then(parse_dimension);
+            if width.map_or(false, |w| w <= 1) && height.map_or(false, |h| h <= 1) {
+                return None;
+            }
+            let alt = element
+                .attr("alt")
+                .map(|alt| alt.trim().to_string())
+                .filter(|alt| !alt.is_empty());
+            Some(PageImage { src, alt, width, height })
+        })
         .collect();
     
     let word_count = content.split_whitespace().count();
+    let content_hash = content_hash(&content);
     
     Ok(PageContent {
         url: url.to_string(),
@@ -384,5 +1634,8 @@ fn parse_html_content(url: &str, html: &str) -> Result<PageContent, String> {
         images,
         word_count,
         extracted_at: Utc::now(),
+        content_hash,
+        raw_html: None,
+        queued: None,
     })
 }
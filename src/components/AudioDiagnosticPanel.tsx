@@ -1,6 +1,7 @@
 import React, { useState, useEffect } from 'react';
 import { Mic, MicOff, Volume2, VolumeX, CheckCircle, XCircle, AlertTriangle } from 'lucide-react';
 import { invoke } from '@tauri-apps/api/core';
+import { FallbackGeneration } from '../types';
 import { usePythonBackendLLM } from '../hooks/usePythonBackendLLM';
 import { useRealtimeSTT } from '../hooks/useRealtimeSTT';
 
@@ -117,7 +118,7 @@ export const AudioDiagnosticPanel: React.FC<AudioDiagnosticPanelProps> = ({
     // Test 6: LLM Response Test
     setCurrentTest('LLM Response Test');
     try {
-      const response = await invoke<string>('generate_llm_response', {
+      const { response } = await invoke<FallbackGeneration>('generate_llm_response', {
         prompt: 'Hello, this is a test message. Please respond briefly.'
       });
       if (response && response.trim().length > 0) {
@@ -3,6 +3,7 @@ import { AlertTriangle, CheckCircle, Loader2, RefreshCw } from 'lucide-react';
 import { cn } from '../utils/cn';
 import { modelHealthChecker } from '../utils/modelHealth';
 import { invoke } from '@tauri-apps/api/core';
+import { FallbackGeneration } from '../types';
 
 interface StartupVerificationProps {
   onVerificationComplete: (success: boolean) => void;
@@ -120,7 +121,7 @@ export const StartupVerification: React.FC<StartupVerificationProps> = ({
     
     try {
       // Try to make a simple test request to verify the model
-      const response = await invoke<string>('generate_llm_response', { 
+      const { response } = await invoke<FallbackGeneration>('generate_llm_response', { 
         prompt: 'Hello' 
       });
       
@@ -17,6 +17,7 @@ import {
 } from 'lucide-react';
 import { cn } from '../utils/cn';
 import { invoke } from '@tauri-apps/api/core';
+import { FallbackGeneration } from '../types';
 
 interface VoiceChatProps {
   className?: string;
@@ -236,9 +237,9 @@ export const VoiceChat: React.FC<VoiceChatProps> = ({
       setVoiceMessages(prev => [...prev, aiMessage]);
       
       // Generate response using existing LLM
-      const response = await invoke('generate_llm_response', { 
+      const { response } = await invoke<FallbackGeneration>('generate_llm_response', { 
         prompt: userInput 
-      }) as string;
+      });
       
       // Update message with response
       setVoiceMessages(prev => 
@@ -1,5 +1,5 @@
 import { invoke } from '@tauri-apps/api/core';
-import { LLMProvider, LLMModel, LLMConfig, LLMRoutingPreferences, NetworkStatus } from '../../types';
+import { LLMProvider, LLMModel, LLMConfig, LLMRoutingPreferences, NetworkStatus, FallbackGeneration } from '../../types';
 
 // Enhanced model provider types for hybrid configuration
 export enum ModelProvider {
@@ -191,7 +191,7 @@ export class LLMRouter {
       console.log(`🖥️ [LLM ROUTER] Executing local request with model: ${model}`);
 
       // Use Tauri invoke for local requests
-      const response = await invoke('generate_llm_response', { prompt });
+      const { response } = await invoke<FallbackGeneration>('generate_llm_response', { prompt });
 
       return {
         success: true,
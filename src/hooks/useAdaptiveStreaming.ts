@@ -2,6 +2,7 @@ import { useState, useRef, useCallback, useEffect } from 'react';
 import { invoke } from '@tauri-apps/api/core';
 import { listen, UnlistenFn } from '@tauri-apps/api/event';
 import { TAURI_ENV } from '../utils/tauriDetection';
+import { LlmStreamEvent } from '../types';
 import { useAppStore } from '../stores/chatStore';
 
 interface StreamingState {
@@ -11,12 +12,6 @@ interface StreamingState {
   currentStreamId: string | null;
 }
 
-interface StreamEvent {
-  stream_id: string;
-  event_type: string;
-  data: string;
-}
-
 interface UseAdaptiveStreamingReturn {
   streamingState: StreamingState;
   startStream: (prompt: string, onChunk?: (chunk: string) => void, onComplete?: (fullContent: string) => void) => Promise<string>;
@@ -167,7 +162,7 @@ export const useAdaptiveStreaming = (): UseAdaptiveStreamingReturn => {
 
       // FIXED: Listen for the correct event name that backend emits
       console.log('👂 [ADAPTIVE STREAMING] Listening for events: llm-stream-event');
-      const unlisten = await listen<StreamEvent>('llm-stream-event', (event) => {
+      const unlisten = await listen<LlmStreamEvent>('llm-stream-event', (event) => {
         const streamEvent = event.payload;
         
         // FIXED: Check if this event is for our stream
@@ -176,17 +171,17 @@ export const useAdaptiveStreaming = (): UseAdaptiveStreamingReturn => {
           return;
         }
         
-        if (streamEvent.event_type === 'chunk') {
-          fullContentRef.current += streamEvent.data;
+        if (streamEvent.type === 'chunk') {
+          fullContentRef.current += streamEvent.text;
           
           setStreamingState(prev => ({
             ...prev,
             streamedContent: fullContentRef.current,
           }));
           
-          onChunk?.(streamEvent.data);
+          onChunk?.(streamEvent.text);
           
-        } else if (streamEvent.event_type === 'complete') {
+        } else if (streamEvent.type === 'complete' || streamEvent.type === 'cancelled') {
           setStreamingState(prev => ({
             ...prev,
             isStreaming: false,
@@ -197,16 +192,16 @@ export const useAdaptiveStreaming = (): UseAdaptiveStreamingReturn => {
           onComplete?.(fullContentRef.current);
           resolve?.(fullContentRef.current);
           
-        } else if (streamEvent.event_type === 'error') {
+        } else if (streamEvent.type === 'error') {
           setStreamingState(prev => ({
             ...prev,
             isStreaming: false,
-            error: streamEvent.data,
+            error: streamEvent.message,
             currentStreamId: null,
           }));
           
           currentStreamIdRef.current = null;
-          reject?.(new Error(streamEvent.data));
+          reject?.(new Error(streamEvent.message));
         }
       });
 
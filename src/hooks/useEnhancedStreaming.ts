@@ -6,6 +6,7 @@ import { ModelProvider, llmRouter } from '../core/agents/llmRouter';
 import { geminiApi } from '../services/geminiApi';
 import { useAppStore } from '../stores/chatStore';
 import { useSettingsStore } from '../stores/settingsStore';
+import { LlmStreamEvent } from '../types';
 
 // Utility function to format tool context for LLM
 const formatToolContext = (toolContext: any): string => {
@@ -72,17 +73,6 @@ interface EnhancedStreamingState {
   estimatedTimeRemaining: number; // seconds
 }
 
-interface StreamEvent {
-  stream_id: string;
-  event_type: string;
-  data: string;
-  metadata?: {
-    tokens?: number;
-    model?: string;
-    provider?: string;
-  };
-}
-
 interface UseEnhancedStreamingReturn {
   streamingState: EnhancedStreamingState;
   startStream: (
@@ -651,8 +641,9 @@ export const useEnhancedStreaming = (): UseEnhancedStreamingReturn => {
         console.log('✅ [TAURI STREAMING] Stream started successfully');
 
         // FIXED: Listen for events with proper content accumulation
-        const unlisten = await listen<StreamEvent>('llm-stream-event', (event) => {
-          const { stream_id, event_type, data, metadata } = event.payload;
+        const unlisten = await listen<LlmStreamEvent>('llm-stream-event', (event) => {
+          const streamEvent = event.payload;
+          const { stream_id } = streamEvent;
           
           // FIXED: Verify this event is for our stream
           if (stream_id !== streamId) {
@@ -660,15 +651,16 @@ export const useEnhancedStreaming = (): UseEnhancedStreamingReturn => {
             return;
           }
 
-          console.log(`📨 [TAURI STREAMING] Processing ${event_type} event`);
+          console.log(`📨 [TAURI STREAMING] Processing ${streamEvent.type} event`);
 
-          switch (event_type) {
-            case 'chunk':
+          switch (streamEvent.type) {
+            case 'chunk': {
+              const data = streamEvent.text;
               console.log(`📝 [TAURI STREAMING] Processing chunk: "${data}" (length: ${data.length})`);
               if (!isPausedRef.current) {
                 // FIXED: Accumulate content properly
                 fullContentRef.current += data;
-                tokenCountRef.current += metadata?.tokens || 1;
+                tokenCountRef.current += 1;
 
                 // Calculate metrics
                 const elapsedTime = (Date.now() - startTimeRef.current) / 1000;
@@ -686,7 +678,6 @@ export const useEnhancedStreaming = (): UseEnhancedStreamingReturn => {
 
                 // Call chunk callback with full accumulated content
                 options?.onChunk?.(fullContentRef.current, {
-                  ...metadata,
                   chunk: data,
                   totalTokens: tokenCountRef.current,
                   speed,
@@ -699,9 +690,20 @@ export const useEnhancedStreaming = (): UseEnhancedStreamingReturn => {
                 console.log(`⏸️ [TAURI STREAMING] Stream paused, skipping chunk`);
               }
               break;
+            }
 
             case 'complete':
-              console.log('✅ [TAURI STREAMING] Stream completed');
+            case 'cancelled':
+              console.log(`✅ [TAURI STREAMING] Stream ${streamEvent.type === 'complete' ? 'completed' : 'cancelled'}`);
+              if (streamEvent.type === 'complete') {
+                if (streamEvent.method === 'fallback') {
+                  console.warn(`⚠️ [TAURI STREAMING] Served by fallback streaming: ${streamEvent.fallback_reason ?? 'unknown reason'}`);
+                }
+                // Let the backend know the final event arrived so it doesn't re-send it
+                invoke('ack_stream_complete', { stream_id }).catch(err =>
+                  console.warn('⚠️ [TAURI STREAMING] Failed to ack stream completion:', err)
+                );
+              }
               
               setStreamingState(prev => ({
                 ...prev,
@@ -713,7 +715,8 @@ export const useEnhancedStreaming = (): UseEnhancedStreamingReturn => {
               
               // FIXED: Call complete callback with final accumulated content
               options?.onComplete?.(fullContentRef.current, {
-                totalTokens: tokenCountRef.current,
+                totalTokens: streamEvent.type === 'complete' ? streamEvent.stats.tokens : tokenCountRef.current,
+                truncated: streamEvent.type === 'complete' && streamEvent.stats.truncated,
                 model: options?.model || 'gemma3n:latest',
                 provider: 'local'
               });
@@ -722,24 +725,26 @@ export const useEnhancedStreaming = (): UseEnhancedStreamingReturn => {
               resolve(fullContentRef.current);
               break;
 
-            case 'error':
-              console.error('❌ [TAURI STREAMING] Stream error:', data);
+            case 'error': {
+              const message = streamEvent.message;
+              console.error('❌ [TAURI STREAMING] Stream error:', message);
               
               setStreamingState(prev => ({
                 ...prev,
                 isStreaming: false,
-                error: data,
+                error: message,
                 currentStreamId: null,
               }));
 
               currentStreamIdRef.current = null;
               
               // FIXED: Call error callback
-              options?.onError?.(data);
+              options?.onError?.(message);
               
               // FIXED: Reject the promise
-              reject(new Error(data));
+              reject(new Error(message));
               break;
+            }
           }
         });
 
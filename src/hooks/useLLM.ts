@@ -1,5 +1,6 @@
 import { useState, useCallback } from 'react';
 import { invoke } from '@tauri-apps/api/core';
+import { FallbackGeneration } from '../types';
 
 export interface LLMError {
   message: string;
@@ -100,7 +101,7 @@ export const useLLM = (): LLMHookReturn => {
     try {
       console.log('Sending prompt to LLM:', trimmedPrompt.substring(0, 100) + '...');
       
-      const response = await invoke<string>('generate_llm_response', {
+      const { response } = await invoke<FallbackGeneration>('generate_llm_response', {
         prompt: trimmedPrompt,
       });
 
@@ -1,11 +1,13 @@
 import { useState, useCallback } from 'react';
 import { invoke } from '@tauri-apps/api/core';
+import { SystemPromptSource } from '../types';
 
 interface LLMResponse {
   response: string;
   model: string;
   success: boolean;
   error?: string;
+  system_prompt_source: SystemPromptSource;
 }
 
 interface BackendHealthResponse {
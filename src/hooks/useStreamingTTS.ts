@@ -1,4 +1,5 @@
 import { useState, useCallback, useRef, useEffect } from 'react';
+import { invoke } from '@tauri-apps/api/core';
 
 interface StreamingTTSState {
   isPlaying: boolean;
@@ -12,7 +13,7 @@ interface StreamingTTSState {
 interface UseStreamingTTSReturn {
   ttsState: StreamingTTSState;
   speakStreaming: (text: string, voice?: string, speed?: number) => Promise<void>;
-  addToQueue: (text: string) => void;
+  addToQueue: (text: string) => Promise<void>;
   stop: () => void;
   clearQueue: () => void;
   isSupported: boolean;
@@ -246,8 +247,18 @@ export const useStreamingTTS = (): UseStreamingTTSReturn => {
     }
   }, [ttsState.queuedText, synthesizeChunk]);
 
-  const addToQueue = useCallback((text: string) => {
-    const chunks = splitTextIntoChunks(text);
+  const addToQueue = useCallback(async (text: string) => {
+    let chunks: string[];
+    try {
+      // Backend segmentation keeps abbreviations, decimals and URLs intact
+      const sentences = await invoke<string[]>('segment_speakable_text', { text });
+      chunks = sentences.flatMap(sentence =>
+        sentence.length <= CHUNK_SIZE ? [sentence] : splitTextIntoChunks(sentence)
+      );
+    } catch (error) {
+      console.warn('⚠️ Sentence segmentation unavailable, using basic splitting:', error);
+      chunks = splitTextIntoChunks(text);
+    }
     setTTSState(prev => ({
       ...prev,
       queuedText: [...prev.queuedText, ...chunks],
@@ -261,7 +272,7 @@ export const useStreamingTTS = (): UseStreamingTTSReturn => {
       stop();
 
       // Add text to queue
-      addToQueue(text);
+      await addToQueue(text);
 
       // Start processing queue
       await processQueue(voice, speed);
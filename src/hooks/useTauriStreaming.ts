@@ -2,6 +2,7 @@ import { useState, useRef, useCallback, useEffect } from 'react';
 import { invoke } from '@tauri-apps/api/core';
 import { listen, UnlistenFn } from '@tauri-apps/api/event';
 import { TAURI_ENV } from '../utils/tauriDetection';
+import { LlmStreamEvent } from '../types';
 
 interface StreamingState {
   isStreaming: boolean;
@@ -10,12 +11,6 @@ interface StreamingState {
   currentStreamId: string | null;
 }
 
-interface StreamEvent {
-  stream_id: string;
-  event_type: string;
-  data: string;
-}
-
 interface UseTauriStreamingReturn {
   streamingState: StreamingState;
   startStream: (prompt: string, onChunk?: (chunk: string) => void, onComplete?: (fullContent: string) => void) => Promise<string>;
@@ -79,7 +74,7 @@ export const useTauriStreaming = (): UseTauriStreamingReturn => {
         // FIXED: Listen for the correct event name that backend emits
         console.log('👂 [TAURI STREAMING] Listening for events: llm-stream-event');
         
-        const unlisten = await listen<StreamEvent>('llm-stream-event', (event) => {
+        const unlisten = await listen<LlmStreamEvent>('llm-stream-event', (event) => {
           const streamEvent = event.payload;
           console.log('📤 [TAURI STREAMING] Received event:', streamEvent);
 
@@ -89,17 +84,17 @@ export const useTauriStreaming = (): UseTauriStreamingReturn => {
             return;
           }
 
-          if (streamEvent.event_type === 'chunk') {
-            fullContentRef.current += streamEvent.data;
+          if (streamEvent.type === 'chunk') {
+            fullContentRef.current += streamEvent.text;
             
             setStreamingState(prev => ({
               ...prev,
               streamedContent: fullContentRef.current,
             }));
 
-            onChunk?.(streamEvent.data);
+            onChunk?.(streamEvent.text);
             
-          } else if (streamEvent.event_type === 'complete') {
+          } else if (streamEvent.type === 'complete' || streamEvent.type === 'cancelled') {
             console.log('✅ [TAURI STREAMING] Stream completed');
             
             setStreamingState(prev => ({
@@ -112,18 +107,18 @@ export const useTauriStreaming = (): UseTauriStreamingReturn => {
             onComplete?.(fullContentRef.current);
             resolve(fullContentRef.current);
             
-          } else if (streamEvent.event_type === 'error') {
-            console.error('❌ [TAURI STREAMING] Stream error:', streamEvent.data);
+          } else if (streamEvent.type === 'error') {
+            console.error('❌ [TAURI STREAMING] Stream error:', streamEvent.message);
             
             setStreamingState(prev => ({
               ...prev,
               isStreaming: false,
-              error: streamEvent.data,
+              error: streamEvent.message,
               currentStreamId: null,
             }));
 
             currentStreamIdRef.current = null;
-            reject(new Error(streamEvent.data));
+            reject(new Error(streamEvent.message));
           }
         });
 
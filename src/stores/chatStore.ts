@@ -16,7 +16,8 @@ import {
   ChatSessionActions,
   LLMRoutingPreferences,
   LLMProvider,
-  PluginResult
+  PluginResult,
+  SystemPromptSource
 } from '../types';
 
 // Generate unique ID for messages
@@ -865,7 +866,7 @@ export const useMultiChatStore = create<MultiChatStore>()(
           const session = state.chatSessions[activeChatId];
           const model = session?.metadata?.model || 'gemma3n:latest';
 
-          const response = await invoke<{response: string, success: boolean, error?: string}>('generate_chat_llm_response', {
+          const response = await invoke<{response: string, success: boolean, error?: string, system_prompt_source: SystemPromptSource}>('generate_chat_llm_response', {
             chatId: activeChatId,
             prompt,
             model,
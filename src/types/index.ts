@@ -144,6 +144,7 @@ export interface AppVersion {
 
   name: string;
   build_date: string;
+  git_commit: string;
 }
 
 
@@ -1152,3 +1153,79 @@ export interface StreamingConfig {
   autoScroll: boolean;
   showTypingIndicator: boolean;
 }
+
+// ===== LLM STREAM EVENTS =====
+// Payload of the `llm-stream-event` Tauri event, discriminated by `type`
+export interface LlmStreamStats {
+  tokens: number;
+  duration_ms: number;
+  tokens_per_second: number | null;
+  truncated: boolean;
+  context_truncated: boolean;
+}
+
+// Which path served a stream: live tokens, a replayed one-shot reply after streaming failed, or streaming turned off
+export type LlmStreamMethod = 'ollama' | 'fallback' | 'one_shot';
+
+export type LlmStreamEvent = {
+  schema_version: number;
+  stream_id: string;
+} & (
+  | { type: 'chunk'; text: string }
+  | { type: 'metrics'; tokens: number; elapsed_ms: number; tokens_per_second: number }
+  | { type: 'first_token'; ms: number }
+  | { type: 'complete'; stats: LlmStreamStats; method: LlmStreamMethod; fallback_reason?: string }
+  | { type: 'error'; code: string; message: string }
+  | { type: 'cancelled'; partial: string }
+  | { type: 'paused' }
+  | { type: 'resumed' }
+  | { type: 'repetition_detected'; text: string; repeats: number }
+);
+
+// ===== ONE-SHOT GENERATION =====
+// Where the system prompt for a generation came from
+export type SystemPromptSource = 'request' | 'chat' | 'default' | 'none';
+
+// Result of `generate_llm_response` and `generate_with_model_fallback`
+export interface FallbackGeneration {
+  response: string;
+  model: string;
+  // Set when the requested model failed and a smaller one answered
+  fell_back_from: string | null;
+  original_error: string | null;
+  system_prompt_source: SystemPromptSource;
+}
+
+// ===== TTS PROGRESS EVENTS =====
+// Payloads of the `tts-progress` and `tts-complete` events emitted by `speak_text`
+export interface TtsProgress {
+  tts_id: string;
+  sentence_index: number;
+  sentence_count: number;
+  chars_done: number;
+  chars_total: number;
+}
+
+export interface TtsComplete {
+  tts_id: string;
+  sentences_spoken: number;
+  sentence_count: number;
+  cancelled: boolean;
+  error: string | null;
+}
+
+// ===== HEALTH MONITOR EVENTS =====
+// Payload of the `health-changed` event emitted by `start_health_monitor`
+export type HealthState = 'up' | 'down';
+
+export interface SubsystemHealth {
+  subsystem: 'ollama' | 'backend';
+  previous: HealthState | null;
+  current: HealthState;
+  changed: boolean;
+}
+
+export interface HealthChanged {
+  subsystems: SubsystemHealth[];
+  checked_at: string;
+}
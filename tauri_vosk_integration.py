@@ -123,6 +123,30 @@ class TauriVoskSTT:
         if final_result.get('text', '').strip():
             self._handle_result('final', final_result['text'])
     
+    def transcribe_stdin(self, chunk_bytes: int = 4000):
+        """Recognize raw 16kHz mono int16 PCM from stdin until EOF, printing partial and final results."""
+        last_partial = ''
+        while True:
+            data = sys.stdin.buffer.read(chunk_bytes)
+            if not data:
+                break
+
+            if self.recognizer.AcceptWaveform(data):
+                result = json.loads(self.recognizer.Result())
+                last_partial = ''
+                if result.get('text', '').strip():
+                    self._handle_result('final', result['text'])
+            else:
+                partial = json.loads(self.recognizer.PartialResult()).get('partial', '').strip()
+                # Vosk repeats the same partial for every chunk of silence
+                if partial and partial != last_partial:
+                    last_partial = partial
+                    self._handle_result('partial', partial)
+
+        final_result = json.loads(self.recognizer.FinalResult())
+        if final_result.get('text', '').strip():
+            self._handle_result('final', final_result['text'])
+
     def transcribe_duration(self, duration: float = 5.0) -> str:
         """Record for a specific duration and return transcript."""
         transcript = ""
@@ -148,9 +172,20 @@ def main():
     if len(sys.argv) < 2:
         print("Usage: python tauri_vosk_integration.py <model_path> [duration]")
         print("Example: python tauri_vosk_integration.py vosk-model-en-us-0.22 5.0")
+        print("Streaming: python tauri_vosk_integration.py <model_path> --stdin < audio.raw")
         sys.exit(1)
     
     model_path = sys.argv[1] if len(sys.argv) > 1 else "vosk-model-small-en-us-0.15"
+
+    # Streaming mode: the app feeds microphone audio over stdin
+    if len(sys.argv) > 2 and sys.argv[2] == '--stdin':
+        try:
+            TauriVoskSTT(model_path).transcribe_stdin()
+        except Exception as e:
+            print(json.dumps({'type': 'error', 'text': str(e), 'timestamp': time.time()}), flush=True)
+            sys.exit(1)
+        return
+
     duration = float(sys.argv[2]) if len(sys.argv) > 2 else 5.0
 
     try:
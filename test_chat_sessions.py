@@ -0,0 +1,38 @@
+"""Round-trip tests for chat session files shared with the Tauri app."""
+
+import json
+import tempfile
+import unittest
+from pathlib import Path
+
+from chat_sessions import ChatSessionManager
+
+
+class ChatSessionRoundTripTest(unittest.TestCase):
+    def test_resave_keeps_metadata_written_by_tauri(self):
+        with tempfile.TemporaryDirectory() as tmp:
+            manager = ChatSessionManager(Path(tmp))
+            session = manager.create_session("Round trip")
+            session_file = Path(tmp) / f"{session.id}.json"
+
+            # Add the keys the Rust chat store writes
+            data = json.loads(session_file.read_text(encoding="utf-8"))
+            tauri_keys = {
+                "generation_options": {"temperature": 0.2, "num_ctx": 4096},
+                "system_prompt": "Answer briefly.",
+                "forked_from": {"chat_id": "chat_abc", "message_id": "msg_def"},
+                "imported_from": "openai",
+            }
+            data["metadata"].update(tauri_keys)
+            session_file.write_text(json.dumps(data), encoding="utf-8")
+
+            reloaded = manager.load_session(session.id)
+            self.assertTrue(manager._save_session(reloaded))
+
+            saved = json.loads(session_file.read_text(encoding="utf-8"))
+            for key, value in tauri_keys.items():
+                self.assertEqual(saved["metadata"][key], value)
+
+
+if __name__ == "__main__":
+    unittest.main()